@@ -0,0 +1,47 @@
+//! Criterion benchmarks of the operations in the inner loop of the simulations, used as
+//! baselines for optimizations
+//!
+//! Run them with `cargo bench`
+
+#[allow(dead_code, unused_imports)]
+#[path = "../src/modules/mod.rs"]
+pub mod modules;
+use modules::{
+    resource_based_landscape::ResourceBasedFitnessLandscape,
+    population::{FixedSizePopulation, InitialPopulation},
+    fitness_model::FitnessModel,
+    genotype::Genotype,
+    math::linear_algebra::Vector
+};
+
+use criterion::{criterion_group, criterion_main, Criterion, black_box};
+use rand::{SeedableRng, rngs::StdRng};
+
+const L: usize = 10;
+const S: usize = 2;
+const POP_SIZE: usize = 10_000;
+const MUTATION_RATE: f64 = 1e-3;
+
+fn n_differences(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let (g1, g2) = (Genotype::<L>::random_with_rng(&mut rng), Genotype::<L>::random_with_rng(&mut rng));
+    c.bench_function("n_differences", |b| b.iter(|| black_box(&g1).n_differences(black_box(&g2))));
+}
+
+fn mutation(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut population = FixedSizePopulation::<L>::new(POP_SIZE);
+    population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random_with_rng(&mut rng)));
+    c.bench_function("mutation", |b| b.iter(|| population.mutation_with_rng(MUTATION_RATE, &mut rng)));
+}
+
+fn maxima(c: &mut Criterion) {
+    let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::<S>::new_hoc(vec![0.1, 0.05]));
+    let mut population = FixedSizePopulation::<L>::new(POP_SIZE);
+    population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::from_index(0)));
+    let fitness_landscape = landscape.get_full_fitness_landscape(&population, &Vector::from([1.; S]));
+    c.bench_function("maxima", |b| b.iter(|| black_box(&fitness_landscape).maxima()));
+}
+
+criterion_group!(benches, n_differences, mutation, maxima);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+use std::process::Command;
+
+/// Records the current commit, so that the output files can be traced back to the code
+fn main() {
+    let hash = Command::new("git").args(["rev-parse", "HEAD"]).output().ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", hash);
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+}
@@ -0,0 +1,39 @@
+//! analyze_convergence computes convergent evolution metrics from a set of
+//! evolutionary trajectories, each given in a file with the genotype in each
+//! generation as a row of tab-separated alleles
+//!
+//! For information on the parameters, run `analyze_convergence --help`
+
+pub mod modules;
+use modules::convergence_analysis::{
+    convergence_index,
+    parallel_evolution_fraction,
+    final_genotype_counts,
+    read_trajectory
+};
+
+use clap::{Arg, App};
+
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    const L: usize = 10;
+
+    let matches = App::new("")
+          .author("André Amado <andre.amado@pm.me>")
+          .arg(Arg::with_name("trajectories").help("Files with one trajectory each").multiple(true).required(true))
+          .get_matches();
+
+    let trajectories = matches.values_of("trajectories").unwrap()
+                              .map(read_trajectory::<L>)
+                              .collect::<Result<Vec<_>, _>>()?;
+
+    println!("trajectories\t{}", trajectories.len());
+    println!("convergence_index\t{}", convergence_index(&trajectories));
+    println!("parallel_evolution_fraction\t{}", parallel_evolution_fraction(&trajectories));
+    println!("#final_genotype\tcount");
+    for (g, n) in final_genotype_counts(&trajectories) {
+        println!("{}\t{}", g, n);
+    }
+    Ok(())
+}
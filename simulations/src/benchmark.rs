@@ -0,0 +1,59 @@
+//! benchmark measures the time taken by the main operations of the simulations
+//! and prints it as CSV with the header `operation,mean_ms,std_ms`
+//!
+//! Build it with `cargo build --release` for meaningful timings
+
+pub mod modules;
+use modules::{
+    resource_based_landscape::{ResourceBasedFitnessLandscape, FitnessCache},
+    population::{FixedSizePopulation, InitialPopulation},
+    fitness_model::FitnessModel,
+    genotype::Genotype,
+    math::{linear_algebra::Vector, statistics}
+};
+
+use std::time::Instant;
+
+/// Runs `operation` `repetitions` times and returns the mean and standard deviation of the time
+/// taken by each run in milliseconds
+fn time_ms(repetitions: usize, mut operation: impl FnMut()) -> (f64, f64) {
+    let times: Vec<f64> = (0..repetitions).map(|_| {
+        let start = Instant::now();
+        operation();
+        start.elapsed().as_secs_f64() * 1000.
+    }).collect();
+    (statistics::mean(&times), statistics::variance(&times).sqrt())
+}
+
+fn main() {
+    const L: usize = 10;
+    const S: usize = 2;
+    const POP_SIZE: usize = 10_000;
+    const MUTATION_RATE: f64 = 1e-3;
+
+    let model = FitnessModel::<S>::new_hoc(vec![0.1, 0.05]);
+    let resources = Vector::from([1.; S]);
+
+    let mut landscape = ResourceBasedFitnessLandscape::<L, S>::new(model.clone());
+    let generation = time_ms(100, || landscape = ResourceBasedFitnessLandscape::<L, S>::new(model.clone()));
+
+    let mut population = FixedSizePopulation::<L>::new(POP_SIZE);
+    population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random()));
+    let mutation = time_ms(100, || population.mutation(MUTATION_RATE));
+    let wright_fisher = time_ms(100, || population.wright_fisher(&landscape, &resources));
+    let full_landscape = time_ms(1000, || { landscape.get_full_fitness_landscape(&population, &resources); });
+    // Repeated calls with the same population and resources
+    let cache = FitnessCache::new(landscape.clone());
+    let cached_landscape = time_ms(1000, || { cache.get_full_fitness_landscape_cached(&population, &resources); });
+
+    println!("operation,mean_ms,std_ms");
+    for (operation, (mean, std)) in [
+        ("landscape_generation", generation),
+        ("mutation", mutation),
+        ("wright_fisher", wright_fisher),
+        ("get_full_fitness_landscape", full_landscape),
+        ("get_full_fitness_landscape_cached", cached_landscape)
+    ] {
+        println!("{},{},{}", operation, mean, std);
+    }
+}
@@ -5,19 +5,25 @@
 //! 
 //! The user can convert a time series of plots to a video using ffmpeg with a 
 //! command like `ffmpeg -i example/%06d.svg -vf format=yuv420p output.mp4`
+//!
+//! With `--snapshots`, the population in each generation is written to the
+//! standard output as line-delimited JSON, e.g., `convergence ... --snapshots | python analyze.py`
 
 
 pub mod modules;
 use modules::{
     population::{FixedSizePopulation, InitialPopulation},
-    resource_based_landscape::ResourceBasedFitnessLandscape,
+    resource_based_landscape::{ResourceBasedFitnessLandscape, FitnessCache},
     genotype::Genotype,
     data::Data,
     parameters::Parameters,
     plot_landscape::FitnessLandscapePlot
 };
 
-use std::error::Error;
+use std::{
+    error::Error,
+    io::{self, BufWriter}
+};
 
 fn main() -> Result<(), Box<dyn Error>> {
     const L: usize = 10;
@@ -25,6 +31,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let t_max = 100_000;
     let params = Parameters::<S>::from_command_line_convergence();
+    std::fs::create_dir_all(&params.folder_name)?;
 
     let mut data = Data::from_parameters(&params, L);
     let l = params.landscapes[0];
@@ -36,20 +43,28 @@ fn main() -> Result<(), Box<dyn Error>> {
         );
         let mut landscape = ResourceBasedFitnessLandscape::<L, S>::load(&landscape_filename[..]);
         if params.null_model { landscape.as_null_model(); }
-        landscape
+        FitnessCache::new(landscape)
     };
 
+    let mut stdout = BufWriter::new(io::stdout());
+
     for &pop_size in &params.pop_size {
         let mut population = FixedSizePopulation::<L>::new(pop_size);
         population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random()));
 
         for t in 0..t_max {
             population.mutation(params.mutation_rate_per_locus);
-            population.wright_fisher(&landscape, &params.resources);
+            population.wright_fisher(landscape.landscape(), &params.resources);
 
-            data.save_datapoint(l, 0, &population, &landscape, &params.resources, t, true).unwrap();
+            data.save_datapoint_cached(l, 0, &population, &landscape, &params.resources, t, true).unwrap();
+            if t % 1000 == 0 {
+                eprintln!("N={} t={} {}", pop_size, t, population.diversity_summary_line());
+            }
+            if params.snapshots {
+                population.write_snapshot_to_writer(&mut stdout)?;
+            }
 
-            let fitness_landscape = landscape.get_full_fitness_landscape(&population, &params.resources);
+            let fitness_landscape = landscape.get_full_fitness_landscape_cached(&population, &params.resources);
             let filename = format!("{}landscape_data_{:06}.dat", params.folder_name, t);
             fitness_landscape.save(&filename)?;
 
@@ -57,7 +72,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             let filename = format!("{}{:06}.svg", params.folder_name, t);
             let res = FitnessLandscapePlot::new(&fitness_landscape.landscape, None, Some(&colors)).plot(&filename);
             if res.is_err() {
-              println!("Could not save file {}. Skipping...", filename);
+              eprintln!("Could not save file {}. Skipping...", filename);
             }
         }
     }
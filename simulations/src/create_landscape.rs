@@ -27,7 +27,7 @@ fn main() {
         if Path::new(&landscape_filename[..]).exists() {
             println!("{} already exists. Skipping...", landscape_filename)
         } else {
-            let res = ResourceBasedFitnessLandscape::<L, S>::new(params.model).save(&params.model.get_name()[..], l);
+            let res = ResourceBasedFitnessLandscape::<L, S>::new(params.model.clone()).save(&params.model.get_name()[..], l);
             if res.is_err() {
                 println!("Could not save file {}", landscape_filename);
             }
@@ -1,70 +1,445 @@
 //! ecoevo_landscapes simulates a population evolving on the fitness landscape
-//! and records statistical information about the population and fitness 
+//! and records statistical information about the population and fitness
 //! landscape
-//! 
+//!
 //! For information on the parameters, run `ecoevo_landscape --help`
 
 pub mod modules;
 use modules::{
     population::{
-        FixedSizePopulation, 
+        FixedSizePopulation,
+        FixationTracker,
         InitialPopulation
     },
-    resource_based_landscape::ResourceBasedFitnessLandscape,
+    resource_based_landscape::{ResourceBasedFitnessLandscape, resource_landscape_correlation, interpolate},
     genotype::Genotype,
-    data::Data,
-    parameters::Parameters
+    data::{Data, DataPoint, EvolutionaryDynamicsStats},
+    parameters::Parameters,
+    progress::{self, ProgressReporter}
 };
 
-use std::time::Instant;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use rand_distr::{Distribution, Exp};
+use rayon::prelude::*;
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    sync::Mutex,
+    time::Instant
+};
+
+const T_MAX: usize = 100_000;
+const T_MIN: usize = 15_000;
+/// Number of generations between updates of a gradually changing landscape
+const INTERPOLATION_STEP: usize = 100;
+
+/// Generations at which the landscape is replaced by a new one drawn from the same model
+struct LandscapeShiftSchedule {
+    shifts: Vec<usize>
+}
+
+impl LandscapeShiftSchedule {
+    fn new(rate: f64, rng: &mut impl Rng, t_max: usize) -> Self {
+        Self { shifts: Self::poisson_shifts(rate, rng, t_max) }
+    }
+
+    /// Returns the sorted generations in 1..t_max at which a Poisson process with the given rate
+    /// per generation has at least one event. An infinite rate shifts the landscape every generation.
+    fn poisson_shifts(rate: f64, rng: &mut impl Rng, t_max: usize) -> Vec<usize> {
+        if rate <= 0. { return Vec::new() }
+        if rate.is_infinite() { return (1..t_max).collect() }
+
+        let waiting_time = Exp::new(rate).unwrap();
+        let mut shifts: Vec<usize> = Vec::new();
+        let mut time = 0.;
+        loop {
+            time += waiting_time.sample(rng);
+            let t = (time.ceil() as usize).max(1);
+            if t >= t_max { break }
+            if shifts.last() != Some(&t) {
+                shifts.push(t);
+            }
+        }
+        shifts
+    }
+
+    #[inline]
+    fn is_shift(&self, t: usize) -> bool {
+        self.shifts.binary_search(&t).is_ok()
+    }
+}
+
+/// Returns the earliest generation from which the standard deviation of the entropy over a window
+/// of `window` generations is below `threshold`, or None if the entropy has not stabilized yet
+fn estimate_stabilization_time(entropy_history: &[f64], window: usize, threshold: f64) -> Option<usize> {
+    if window == 0 || entropy_history.len() < window { return None }
+
+    let (mut sum, mut sum_squares) = entropy_history[..window].iter().fold((0., 0.), |(s, s2), h| (s + h, s2 + h * h));
+    for t in 0..=entropy_history.len() - window {
+        if t > 0 {
+            let (old, new) = (entropy_history[t - 1], entropy_history[t + window - 1]);
+            sum += new - old;
+            sum_squares += new * new - old * old;
+        }
+        let mean = sum / window as f64;
+        let variance = (sum_squares / window as f64 - mean * mean).max(0.);
+        if variance.sqrt() < threshold {
+            return Some(t)
+        }
+    }
+    None
+}
+
+/// Returns the population size in the next generation with logistic growth,
+/// `N_{t+1} = N_t exp(r (1 - N_t/K))`, keeping at least one individual
+fn logistic_growth(n: usize, carrying_capacity: usize, growth_rate: f64) -> usize {
+    let n = n as f64;
+    ((n * (growth_rate * (1. - n / carrying_capacity as f64)).exp()).round() as usize).max(1)
+}
+
+/// Output of a replicate
+struct ReplicateResult<const L: usize> {
+    /// Datapoints of the last generations
+    datapoints: Vec<DataPoint>,
+    /// Fixation times of the new alleles, if `params.track_fixation` is set
+    tracker: Option<FixationTracker<L>>,
+    /// Generations at which the landscape changed
+    shifts: Vec<usize>,
+    /// Generation after which the population was considered stable
+    t_min: usize,
+    /// Generation at which the replicate stopped
+    t_stop: usize,
+    /// Rank correlation between the final fitness landscapes under the two resource vectors of
+    /// `params.compare_env`, if given
+    resource_correlation: Option<f64>
+}
+
+/// Runs a single replicate. If a target landscape is given, the landscape changes gradually into it
+/// during the first `t_min` generations. If `params.shift_rate` is positive, the landscape is
+/// replaced at random times by a new one drawn from the same model. If `params.adapt_t_min` is
+/// set, the population is considered stable as soon as its entropy stabilizes, and after `t_min`
+/// generations otherwise, but never before `params.t_min`.
+#[allow(clippy::too_many_arguments)]
+fn run_replicate<const L: usize, const S: usize>(
+    params: &Parameters<S>,
+    initial_landscape: &ResourceBasedFitnessLandscape<L, S>,
+    target_landscape: Option<&ResourceBasedFitnessLandscape<L, S>>,
+    l: usize,
+    pop_size: usize,
+    r: usize,
+    seed: u64,
+    (t_min, t_max): (usize, usize),
+    reporter: &Mutex<Box<dyn ProgressReporter + Send>>
+) -> ReplicateResult<L> {
+    let start = Instant::now();
+    let report_interval = reporter.lock().unwrap().interval();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut data = Data::in_memory(params, params.convergence);
+
+    let mut population = FixedSizePopulation::<L>::new(pop_size);
+    population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random_with_rng(&mut rng)));
+    let mut tracker = if params.track_fixation { Some(FixationTracker::new(&population)) } else { None };
+    let schedule = LandscapeShiftSchedule::new(params.shift_rate, &mut rng, t_max);
+    let mut shifts = Vec::new();
+    let mut landscape = initial_landscape.clone();
+    let t_lower = params.t_min.unwrap_or(if params.adapt_t_min.is_some() { 0 } else { t_min });
+    let mut t_stable = if params.adapt_t_min.is_some() { None } else { Some(t_lower) };
+    let mut entropy_history = Vec::new();
+    let mut polymorphic = false;
+    let mut t_stop = t_max;
+    for t in 0..t_max {
+        if let Some(target) = target_landscape {
+            if t % INTERPOLATION_STEP == 0 && t <= t_min {
+                landscape = interpolate(initial_landscape, target, t as f64 / t_min as f64);
+            }
+        }
+        if schedule.is_shift(t) {
+            landscape = ResourceBasedFitnessLandscape::new(params.model.clone());
+            if params.null_model { landscape.as_null_model(); }
+            shifts.push(t);
+        }
+        if report_interval > 0 && t % report_interval == 0 {
+            reporter.lock().unwrap().report(l, r, t, start.elapsed().as_secs_f32());
+        }
+        // The noise is drawn anew each generation around the current landscape
+        let perturbed = (params.landscape_noise > 0.).then(|| landscape.perturb_phenotype(params.landscape_noise, &mut rng));
+        let current = perturbed.as_ref().unwrap_or(&landscape);
+        population.mutation_with_rng(params.mutation_rate_per_locus, &mut rng);
+        population.wright_fisher_with_rng(current, &params.resources, &mut rng);
+        if let Some(k) = params.carrying_capacity {
+            population.resize(logistic_growth(population.size(), k, params.growth_rate), &mut rng);
+        }
+        if let Some(tracker) = tracker.as_mut() {
+            tracker.update(&population, t);
+        }
+        // The population starts monomorphic, so it has fixated once it loses its diversity again
+        polymorphic |= !population.is_fixed();
+        let fixated = params.stop_on_fixation && polymorphic && population.is_fixed();
+
+        if let (None, Some((window, threshold))) = (t_stable, params.adapt_t_min) {
+            entropy_history.push(population.shannon_entropy());
+            let recent = &entropy_history[entropy_history.len().saturating_sub(window)..];
+            if estimate_stabilization_time(recent, window, threshold).is_some() || t >= t_min.max(t_lower) {
+                t_stable = Some(t.max(t_lower));
+            }
+        }
+
+        let Some(t_stable) = t_stable.or(fixated.then_some(t)) else { continue };
+        if t + params.stable_window > t_stable || fixated {
+            if let Some(perturbed) = &perturbed {
+                let original = landscape.get_full_fitness_landscape(&population, &params.resources);
+                data.record_perturbation(perturbed.get_full_fitness_landscape(&population, &params.resources).rms_difference(&original));
+            }
+            let _ = data.save_datapoint(l, r, &population, current, &params.resources, t, false);
+        }
+        if fixated || (t > t_stable && data.stable_state()) {
+            t_stop = t;
+            break
+        }
+    }
+    let resource_correlation = params.compare_env.as_ref().map(|[r1, r2]| resource_landscape_correlation(&landscape, &population, r1, r2));
+    ReplicateResult { datapoints: data.last_datapoints(), tracker, shifts, t_min: t_stable.unwrap_or(t_max), t_stop, resource_correlation }
+}
+
+/// Derives the seed of a replicate from the seed of the run, so that the results do not depend on
+/// the order in which the replicates are run (nor on the Rust version, unlike `DefaultHasher`)
+fn replicate_seed(seed: u64, l: usize, pop_size: usize, r: usize) -> u64 {
+    [l, pop_size, r].iter().fold(splitmix64(seed), |state, &x| splitmix64(state ^ x as u64))
+}
+
+/// Finalizer of the SplitMix64 generator, which maps nearby inputs to uncorrelated outputs
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
 
 fn main() {
     const L: usize = 10;
     const S: usize = 2;
 
-    let t_max = 100_000;
-    let t_min = 15_000;
     let params = Parameters::<S>::from_command_line();
+    let seed = params.seed.unwrap_or_else(|| rand::thread_rng().gen());
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(params.threads).build().unwrap();
 
-    let mut data = Data::from_parameters(&params, L);
+    let data = Mutex::new(Data::from_parameters(&params, L));
+    let reporter = Mutex::new(progress::from_parameters(&params));
+    let start = Instant::now();
 
     let mut output = String::new();
-    output.push_str(&format!("#{}\t{} model\n", params.model.get_name(), if params.null_model {"null"} else {"full"}));
-    output.push_str(&format!("#landscape_id\tpop_size\treplicate\ttime(s)\n"));
+    output.push_str(&format!("#{}\t{} model\tseed {}\n", params.model.get_name(), if params.null_model {"null"} else {"full"}, seed));
+    output.push_str(&format!("#landscape_id\tpop_size\treplicate\ttime(s)\tt_min\tt_stop\n"));
 
-    for l in params.landscapes[0]..params.landscapes[1] {
-        let landscape = {
-            let landscape_filename = format!(
-                "landscapes/L{}_{}_{}.dat",
-                L, params.model.get_name(), l
-            );
-            let mut landscape = ResourceBasedFitnessLandscape::<L, S>::load(&landscape_filename[..]);
-            if params.null_model { landscape.as_null_model(); }
-            landscape
-        };
+    let load_landscape = |l: usize| {
+        let landscape_filename = format!(
+            "landscapes/L{}_{}_{}.dat",
+            L, params.model.get_name(), l
+        );
+        let mut landscape = ResourceBasedFitnessLandscape::<L, S>::load(&landscape_filename[..]);
+        if params.null_model { landscape.as_null_model(); }
+        landscape
+    };
+    let target_landscape = params.target_landscape.map(load_landscape);
 
-        for &pop_size in &params.pop_size {
-            let mut population = FixedSizePopulation::<L>::new(pop_size);
-            for r in 0..params.replicates {
-                let start = Instant::now();
+    let mut fixation_file = if params.track_fixation {
+        let folder_name = if !params.folder_name.is_empty() { &params.folder_name[..] } else { "data/" };
+        let filename = format!("{}fixation_L{}_{}_seed{}.dat", folder_name, L, params.model.get_name(), seed);
+        let mut file = BufWriter::new(File::create(filename).unwrap());
+        file.write_all(b"#landscape_idx\tn_pop\treplicate\tlocus\tt_half\tt_fixation\n").unwrap();
+        Some(file)
+    } else {
+        None
+    };
 
-                population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random()));
-                for t in 0..t_max {
-                    population.mutation(params.mutation_rate_per_locus);
-                    population.wright_fisher(&landscape, &params.resources);
+    for l in params.landscapes[0]..params.landscapes[1] {
+        let landscape = load_landscape(l);
 
-                    if t > t_min - 501 {
-                        let _ = data.save_datapoint(l, r, &population, &landscape, &params.resources, t, false);
+        for &pop_size in &params.pop_size {
+            let results: Vec<(String, Option<FixationTracker<L>>, Option<f64>)> = pool.install(|| {
+                (0..params.replicates).into_par_iter().map(|r| {
+                    let start = Instant::now();
+                    let result = run_replicate(
+                        &params, &landscape, target_landscape.as_ref(), l, pop_size, r,
+                        replicate_seed(seed, l, pop_size, r), (T_MIN, T_MAX), &reporter
+                    );
+                    {
+                        let mut data = data.lock().unwrap();
+                        for &t in &result.shifts {
+                            let _ = data.write_shift_marker(pop_size, l, r, t);
+                        }
+                        let _ = data.write_datapoints(&result.datapoints);
+                        let _ = data.write_replicate_stats(&EvolutionaryDynamicsStats::from_data_history(&result.datapoints));
                     }
-                    if t > t_min && data.stable_state() {
-                        break
+                    (format!("{}\t{}\t{}\t{:.3}\t{}\t{}\n", l, pop_size, r, start.elapsed().as_secs_f32(), result.t_min, result.t_stop), result.tracker, result.resource_correlation)
+                }).collect()
+            });
+            data.lock().unwrap().flush().unwrap();
+            for (r, (timing, tracker, rho)) in results.into_iter().enumerate() {
+                output.push_str(&timing);
+                if let Some(rho) = rho {
+                    println!("landscape {}, pop_size {}, replicate {}: rho = {:.5}", l, pop_size, r, rho);
+                }
+                if let (Some(file), Some(tracker)) = (fixation_file.as_mut(), tracker) {
+                    let format_time = |t: Option<usize>| t.map_or("NA".to_string(), |t| t.to_string());
+                    for locus in 0..L {
+                        writeln!(
+                            file, "{}\t{}\t{}\t{}\t{}\t{}", l, pop_size, r, locus,
+                            format_time(tracker.locus_half_frequency_time(locus)), format_time(tracker.locus_fixation_time(locus))
+                        ).unwrap();
                     }
                 }
-                let _ = data.write_to_file();
-                output.push_str(&format!("{}\t{}\t{}\t{:.3}\n", l, pop_size, r, start.elapsed().as_secs_f32()));
             }
         }
-        data.flush().unwrap();
+        reporter.lock().unwrap().report_landscape(
+            l + 1 - params.landscapes[0], params.landscapes[1] - params.landscapes[0], start.elapsed().as_secs_f32()
+        );
+    }
+    if let Some(mut file) = fixation_file {
+        file.flush().unwrap();
     }
     println!("{}\n", output);
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use modules::fitness_model::FitnessModel;
+
+    #[test]
+    fn parallel_replicates() {
+        const L: usize = 3;
+        const S: usize = 2;
+        let params = Parameters::<S> {
+            mutation_rate_per_locus: 0.01,
+            model: FitnessModel::new_hoc(vec![0.1, 0.05]),
+            replicates: 4,
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(params.model.clone());
+        let reporter: Mutex<Box<dyn ProgressReporter + Send>> = Mutex::new(Box::new(progress::SilentReporter));
+
+        let run = |threads: usize| -> Vec<Vec<DataPoint>> {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| {
+                (0..params.replicates).into_par_iter().map(|r| {
+                    run_replicate(&params, &landscape, None, 0, 20, r, replicate_seed(42, 0, 20, r), (600, 1000), &reporter).datapoints
+                }).collect()
+            })
+        };
+
+        let (sequential, parallel) = (run(1), run(4));
+        assert_eq!(sequential.len(), parallel.len());
+        for (d1, d2) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!(d1.len(), d2.len());
+            for (p1, p2) in d1.iter().zip(d2.iter()) {
+                for property in ["strains", "n_maxima", "maximum", "entropy"] {
+                    assert!((p1.get(property) - p2.get(property)).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn stable_replicate_seeds() {
+        assert_eq!(splitmix64(0), 0xe220a8397b1dcdaf);
+        assert_eq!(replicate_seed(42, 0, 20, 1), replicate_seed(42, 0, 20, 1));
+        assert_ne!(replicate_seed(42, 0, 20, 1), replicate_seed(42, 0, 20, 2));
+        assert_ne!(replicate_seed(42, 0, 20, 1), replicate_seed(42, 1, 20, 1));
+    }
+
+    #[test]
+    fn landscape_shifts() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(LandscapeShiftSchedule::poisson_shifts(0., &mut rng, 1000).is_empty());
+        assert_eq!(LandscapeShiftSchedule::poisson_shifts(f64::INFINITY, &mut rng, 1000), (1..1000).collect::<Vec<usize>>());
+
+        let shifts = LandscapeShiftSchedule::poisson_shifts(0.01, &mut rng, 100_000);
+        assert!(shifts.windows(2).all(|w| w[0] < w[1]));
+        assert!(shifts.len() > 800 && shifts.len() < 1200);
+
+        const L: usize = 3;
+        const S: usize = 2;
+        let params = Parameters::<S> {
+            mutation_rate_per_locus: 0.01,
+            model: FitnessModel::new_hoc(vec![0.1, 0.05]),
+            shift_rate: f64::INFINITY,
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(params.model.clone());
+        let reporter: Mutex<Box<dyn ProgressReporter + Send>> = Mutex::new(Box::new(progress::SilentReporter));
+        let result = run_replicate(&params, &landscape, None, 0, 20, 0, 42, (10, 20), &reporter);
+        assert_eq!(result.shifts, (1..result.shifts.len() + 1).collect::<Vec<usize>>());
+        assert!(result.shifts.len() >= 10);
+    }
+    #[test]
+    fn stop_on_fixation() {
+        const L: usize = 3;
+        const S: usize = 2;
+        let params = Parameters::<S> {
+            mutation_rate_per_locus: 0.01,
+            model: FitnessModel::new_hoc(vec![0.1, 0.05]),
+            stop_on_fixation: true,
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(params.model.clone());
+        let reporter: Mutex<Box<dyn ProgressReporter + Send>> = Mutex::new(Box::new(progress::SilentReporter));
+        let result = run_replicate(&params, &landscape, None, 0, 20, 0, 42, (T_MIN, T_MAX), &reporter);
+        // New mutations are lost or fixed long before the population is considered stable
+        assert!(result.t_stop < T_MIN);
+        assert_eq!(result.datapoints.last().unwrap().get("strains"), 1.);
+    }
+
+    #[test]
+    fn adaptive_t_min() {
+        let mut entropy: Vec<f64> = (0..500).map(|t| 1. - (-(t as f64) / 50.).exp()).collect();
+        assert_eq!(estimate_stabilization_time(&entropy[..100], 200, 0.01), None);
+        let t = estimate_stabilization_time(&entropy, 50, 0.01).unwrap();
+        assert!(t > 100 && t < 300);
+        assert_eq!(estimate_stabilization_time(&entropy[t..], 50, 0.01), Some(0));
+        entropy.iter_mut().skip(1).step_by(2).for_each(|h| *h += 0.1);
+        assert_eq!(estimate_stabilization_time(&entropy, 50, 0.01), None);
+
+        const L: usize = 3;
+        const S: usize = 2;
+        let params = Parameters::<S> {
+            model: FitnessModel::new_hoc(vec![0.1, 0.05]),
+            adapt_t_min: Some((200, 0.01)),
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(params.model.clone());
+        let reporter: Mutex<Box<dyn ProgressReporter + Send>> = Mutex::new(Box::new(progress::SilentReporter));
+        let result = run_replicate(&params, &landscape, None, 0, 1000, 0, 42, (T_MIN, T_MAX), &reporter);
+        // Without mutations the population stays monomorphic and its entropy is stable from the start
+        assert_eq!(result.t_min, 199);
+        assert!(!result.datapoints.is_empty());
+
+        let params = Parameters::<S> { t_min: Some(2000), ..params };
+        let result = run_replicate(&params, &landscape, None, 0, 1000, 0, 42, (T_MIN, T_MAX), &reporter);
+        assert_eq!(result.t_min, 2000);
+    }
+    #[test]
+    fn logistic_population_growth() {
+        assert_eq!(logistic_growth(100, 100, 0.5), 100);
+        assert!(logistic_growth(10, 100, 0.5) > 10);
+        assert!(logistic_growth(200, 100, 0.5) < 200);
+        assert_eq!(logistic_growth(1000, 10, 10.), 1);
+
+        const L: usize = 3;
+        const S: usize = 2;
+        let params = Parameters::<S> {
+            mutation_rate_per_locus: 0.01,
+            model: FitnessModel::new_hoc(vec![0.1, 0.05]),
+            carrying_capacity: Some(200),
+            growth_rate: 0.5,
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(params.model.clone());
+        let reporter: Mutex<Box<dyn ProgressReporter + Send>> = Mutex::new(Box::new(progress::SilentReporter));
+        let result = run_replicate(&params, &landscape, None, 0, 20, 0, 42, (100, 200), &reporter);
+        // The population grows from 20 individuals to the carrying capacity, up to rounding
+        assert!((result.datapoints.last().unwrap().get("size") - 200.).abs() <= 1.);
+    }
+}
@@ -0,0 +1,145 @@
+use rand_distr::{Bernoulli, Distribution, WeightedIndex};
+
+use super::{
+    genotype::Genotype,
+    fitness_landscape::FitnessLandscape
+};
+
+/// Snapshot of one generation of an individual-based population that keeps track of the lineage
+/// of each individual, i.e., the index of its parent in the previous generation.
+#[derive(Clone)]
+pub struct PopulationTrajectory<const L: usize> {
+    pub t: usize,
+    pub genotypes: Vec<Genotype<L>>,
+    pub parents: Vec<usize>
+}
+
+impl<const L: usize> PopulationTrajectory<L> {
+    /// Creates a founder generation with `size` individuals carrying the same genotype
+    pub fn founder(genotype: Genotype<L>, size: usize) -> Self {
+        Self {
+            t: 0,
+            genotypes: vec![genotype; size],
+            parents: (0..size).collect()
+        }
+    }
+
+    /// Returns the number of individuals in the generation
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.genotypes.len()
+    }
+
+    /// Generates the next generation by Wright-Fisher sampling of the parents proportionally to
+    /// their fitness, followed by mutation of the offspring.
+    pub fn next_generation(&self, landscape: &FitnessLandscape<L>, mutation_rate_per_locus: f64) -> Self {
+        let mut rng = rand::thread_rng();
+
+        let fitnesses: Vec<f64> = self.genotypes.iter().map(|g| {
+            *landscape.get(g).expect("genotype missing from the fitness landscape")
+        }).collect();
+        let parent = WeightedIndex::new(&fitnesses).unwrap();
+        let mutation = Bernoulli::new(mutation_rate_per_locus).unwrap();
+
+        let parents: Vec<usize> = (0..self.size()).map(|_| parent.sample(&mut rng)).collect();
+        let genotypes = parents.iter().map(|&p| {
+            let mut g = self.genotypes[p];
+            for i in 0..L {
+                if mutation.sample(&mut rng) { g.mutate(i); }
+            }
+            g
+        }).collect();
+
+        Self {
+            t: self.t + 1,
+            genotypes,
+            parents
+        }
+    }
+}
+
+/// Follows the lineages of the individuals in the last generation backwards and returns the
+/// position in `trajectories` of the most recent generation at which they all descend from a
+/// single individual, together with the index of that individual.
+fn find_mrca<const L: usize>(trajectories: &[PopulationTrajectory<L>]) -> Option<(usize, usize)> {
+    let last = trajectories.len().checked_sub(1)?;
+    let mut lineages: Vec<usize> = (0..trajectories[last].size()).collect();
+
+    let mut k = last;
+    loop {
+        lineages.sort_unstable();
+        lineages.dedup();
+        if lineages.len() == 1 {
+            return Some((k, lineages[0]))
+        }
+        if k == 0 {
+            return None
+        }
+        lineages = lineages.iter().map(|&i| trajectories[k].parents[i]).collect();
+        k -= 1;
+    }
+}
+
+/// Returns the number of generations separating the last generation in the trajectory from the
+/// most recent common ancestor of all its individuals, or None if the lineages do not coalesce
+/// within the recorded trajectory.
+pub fn coalescence_time<const L: usize>(trajectories: &[PopulationTrajectory<L>]) -> Option<usize> {
+    let (k, _) = find_mrca(trajectories)?;
+    Some(trajectories[trajectories.len() - 1].t - trajectories[k].t)
+}
+
+/// Returns the genotype of the most recent common ancestor of the last generation in the
+/// trajectory, or None if the lineages do not coalesce within the recorded trajectory.
+pub fn mrca_genotype<const L: usize>(trajectories: &[PopulationTrajectory<L>]) -> Option<Genotype<L>> {
+    let (k, i) = find_mrca(trajectories)?;
+    Some(trajectories[k].genotypes[i])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        fitness_landscape::FitnessType,
+        genotype::possible_sequences
+    };
+
+    #[test]
+    fn general() {
+        const L: usize = 3;
+        let founder = Genotype::<L>::new();
+        let mutant  = Genotype::<L>::from_sequence(&[1, 0, 0]);
+
+        // The population diverges in the first generation and one of the founder lineages
+        // sweeps in the second generation
+        let t0 = PopulationTrajectory::<L>::founder(founder, 4);
+        let t1 = PopulationTrajectory {
+            t: 1,
+            genotypes: vec![founder, founder, mutant, mutant],
+            parents: vec![0, 0, 1, 1]
+        };
+        let t2 = PopulationTrajectory {
+            t: 2,
+            genotypes: vec![founder; 4],
+            parents: vec![0, 0, 0, 0]
+        };
+        let trajectories = [t0, t1, t2];
+
+        assert_eq!(coalescence_time(&trajectories), Some(1));
+        assert_eq!(mrca_genotype(&trajectories), Some(founder));
+        assert_eq!(coalescence_time(&trajectories[..1]), None);
+
+        // Without mutation every lineage eventually coalesces back to the founder genotype
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), 1.);
+        }
+        let mut trajectories = vec![PopulationTrajectory::<L>::founder(founder, 10)];
+        for _ in 0..1000 {
+            let next = trajectories.last().unwrap().next_generation(&landscape, 0.);
+            trajectories.push(next);
+        }
+        assert!(coalescence_time(&trajectories).is_some());
+        assert_eq!(mrca_genotype(&trajectories), Some(founder));
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    error::Error
+};
+
+use super::genotype::Genotype;
+
+/// Returns the final genotype of each non-empty trajectory
+fn final_genotypes<const L: usize>(trajectories: &[Vec<Genotype<L>>]) -> Vec<Genotype<L>> {
+    trajectories.iter().filter_map(|trajectory| trajectory.last().copied()).collect()
+}
+
+/// Returns the fraction of pairs of trajectories that end at the same genotype, or NaN if there
+/// are fewer than two trajectories
+pub fn convergence_index<const L: usize>(trajectories: &[Vec<Genotype<L>>]) -> f64 {
+    let finals = final_genotypes(trajectories);
+    let n = finals.len();
+    let mut same = 0;
+    for (i, g1) in finals.iter().enumerate() {
+        same += finals[(i+1)..].iter().filter(|&g2| g1 == g2).count();
+    }
+    same as f64 / (n * n.saturating_sub(1) / 2) as f64
+}
+
+/// Returns the number of trajectories that end at genotype g
+pub fn genotype_convergence_count<const L: usize>(trajectories: &[Vec<Genotype<L>>], g: &Genotype<L>) -> usize {
+    final_genotypes(trajectories).iter().filter(|&f| f == g).count()
+}
+
+/// Returns the fraction of loci at which all trajectories end with the same allele
+pub fn parallel_evolution_fraction<const L: usize>(trajectories: &[Vec<Genotype<L>>]) -> f64 {
+    let finals = final_genotypes(trajectories);
+    let first = match finals.first() {
+        Some(g) => *g,
+        None    => return f64::NAN
+    };
+    let parallel = (0..L).filter(|&i| finals.iter().all(|g| g[i] == first[i])).count();
+    parallel as f64 / L as f64
+}
+
+/// Reads a trajectory from a file with the genotype in each generation as a row of L
+/// tab-separated alleles. Lines starting with `#` are ignored.
+pub fn read_trajectory<const L: usize>(filename: &str) -> Result<Vec<Genotype<L>>, Box<dyn Error>> {
+    let mut trajectory = Vec::new();
+    for (n, line) in std::fs::read_to_string(filename)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue }
+
+        let alleles = line.split('\t').map(|a| match a.trim() {
+            "0" => Ok(0),
+            "1" => Ok(1),
+            a   => Err(format!("{}, line {}: invalid allele '{}'", filename, n+1, a))
+        }).collect::<Result<Vec<u8>, _>>()?;
+        if alleles.len() != L {
+            return Err(format!("{}, line {}: expected {} alleles, found {}", filename, n+1, L, alleles.len()).into())
+        }
+        trajectory.push(Genotype::from_sequence(&alleles));
+    }
+    Ok(trajectory)
+}
+
+/// Returns the number of trajectories ending at each genotype, sorted by decreasing count
+pub fn final_genotype_counts<const L: usize>(trajectories: &[Vec<Genotype<L>>]) -> Vec<(Genotype<L>, usize)> {
+    let mut counts = HashMap::<Genotype<L>, usize>::new();
+    for g in final_genotypes(trajectories) {
+        *counts.entry(g).or_insert(0) += 1;
+    }
+    let mut counts: Vec<(Genotype<L>, usize)> = counts.into_iter().collect();
+    counts.sort_unstable_by(|(g1, n1), (g2, n2)| n2.cmp(n1).then(g1.cmp(g2)));
+    counts
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convergence_metrics() {
+        let g = |seq: &[u8]| Genotype::<3>::from_sequence(seq);
+        let trajectories = vec![
+            vec![g(&[0, 0, 0]), g(&[1, 0, 0]), g(&[1, 1, 0])],
+            vec![g(&[0, 0, 0]), g(&[0, 1, 0]), g(&[1, 1, 0])],
+            vec![g(&[0, 0, 0]), g(&[0, 1, 1])],
+            vec![]
+        ];
+        assert!((convergence_index(&trajectories) - 1. / 3.).abs() < 1e-12);
+        assert_eq!(genotype_convergence_count(&trajectories, &g(&[1, 1, 0])), 2);
+        assert_eq!(genotype_convergence_count(&trajectories, &g(&[0, 0, 0])), 0);
+        assert!((parallel_evolution_fraction(&trajectories) - 1. / 3.).abs() < 1e-12);
+        assert_eq!(final_genotype_counts(&trajectories), vec![(g(&[1, 1, 0]), 2), (g(&[0, 1, 1]), 1)]);
+
+        assert_eq!(convergence_index(&trajectories[..2]), 1.);
+        assert_eq!(parallel_evolution_fraction(&trajectories[..2]), 1.);
+        assert!(convergence_index(&trajectories[..1]).is_nan());
+
+        let filename = std::env::temp_dir().join(format!("trajectory_{}.tsv", std::process::id()));
+        std::fs::write(&filename, "# trajectory\n0\t0\t0\n1\t0\t0\n").unwrap();
+        assert_eq!(read_trajectory::<3>(filename.to_str().unwrap()).unwrap(), vec![g(&[0, 0, 0]), g(&[1, 0, 0])]);
+        std::fs::write(&filename, "0\t0\n").unwrap();
+        assert!(read_trajectory::<3>(filename.to_str().unwrap()).is_err());
+        std::fs::remove_file(&filename).unwrap();
+    }
+}
@@ -1,8 +1,9 @@
 use std::{
-    collections::HashMap,
+    any::Any,
+    collections::{HashMap, VecDeque},
     error::Error,
     fs::File,
-    io::{BufWriter, Write}
+    io::{BufRead, BufReader, BufWriter, Write}
 };
 
 use serde::{Serialize, Deserialize};
@@ -12,25 +13,175 @@ use super::{
     genotype::Genotype,
     math::linear_algebra::Vector,
     population::FixedSizePopulation,
-    resource_based_landscape::ResourceBasedFitnessLandscape,
-    fitness_landscape::VecLandscape,
+    resource_based_landscape::{ResourceBasedFitnessLandscape, FitnessCache, resource_landscape_correlation},
+    fitness_landscape::{FitnessLandscape, FitnessType, VecLandscape, fitness_flux},
     parameters::Parameters
 };
 
-const BUFFER_SIZE: usize = 5000;
-const MAX_GENERATIONS: usize = 500;
+/// Default number of datapoints kept in memory
+pub const BUFFER_SIZE: usize = 5000;
+/// Default number of generations used to identify the top genotypes and to check convergence
+pub const MAX_GENERATIONS: usize = 500;
 const THRESHOLD: f64 = 0.1;
+/// Change in the amount of each resource used to compute the fitness sensitivity
+const SENSITIVITY_DELTA: f64 = 1e-6;
 
-pub struct Data<'a, const S: usize> {
-    summary: BufWriter<File>,
+/// A FitnessLandscape<L>, type-erased because Data is not generic over the number of loci
+type ErasedLandscape = Box<dyn Any + Send>;
+
+/// Criterion used to decide whether a simulation has reached a stationary state
+pub trait ConvergenceCriterion<const S: usize> {
+    fn is_converged<C: ConvergenceCriterion<S>>(&self, data: &Data<S, C>) -> bool;
+}
+
+/// Converged when the most abundant genotypes did not change over the last `window` generations
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StableTopGenotypes {
+    pub window: usize
+}
+
+/// Converged when the Shannon entropy of the population stayed below `max_entropy` over the last
+/// `window` generations
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntropyThreshold {
+    pub max_entropy: f64,
+    pub window: usize
+}
+
+/// Converged when the variance of the fitness landscape stayed below `max_var` over the last
+/// `window` generations
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FitnessVarianceThreshold {
+    pub max_var: f64,
+    pub window: usize
+}
+
+/// Convergence criterion selected at runtime
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Convergence {
+    StableTopGenotypes(StableTopGenotypes),
+    EntropyThreshold(EntropyThreshold),
+    FitnessVarianceThreshold(FitnessVarianceThreshold)
+}
+
+impl Default for Convergence {
+    fn default() -> Self {
+        Convergence::StableTopGenotypes(StableTopGenotypes { window: MAX_GENERATIONS })
+    }
+}
+
+impl<const S: usize> ConvergenceCriterion<S> for StableTopGenotypes {
+    fn is_converged<C: ConvergenceCriterion<S>>(&self, data: &Data<S, C>) -> bool {
+        let mut recent = data.past_top_genotypes.iter().rev().take(self.window);
+        match recent.next() {
+            Some(tg1) => data.has_window(self.window) && recent.all(|tg| tg == tg1),
+            None      => false
+        }
+    }
+}
+
+impl<const S: usize> ConvergenceCriterion<S> for EntropyThreshold {
+    fn is_converged<C: ConvergenceCriterion<S>>(&self, data: &Data<S, C>) -> bool {
+        data.has_window(self.window) &&
+        data.buffer.iter().rev().take(self.window).all(|d| d.entropy < self.max_entropy)
+    }
+}
+
+impl<const S: usize> ConvergenceCriterion<S> for FitnessVarianceThreshold {
+    fn is_converged<C: ConvergenceCriterion<S>>(&self, data: &Data<S, C>) -> bool {
+        data.has_window(self.window) &&
+        data.buffer.iter().rev().take(self.window).all(|d| d.var < self.max_var)
+    }
+}
+
+impl<const S: usize> ConvergenceCriterion<S> for Convergence {
+    fn is_converged<C: ConvergenceCriterion<S>>(&self, data: &Data<S, C>) -> bool {
+        match self {
+            Convergence::StableTopGenotypes(c)       => c.is_converged(data),
+            Convergence::EntropyThreshold(c)         => c.is_converged(data),
+            Convergence::FitnessVarianceThreshold(c) => c.is_converged(data)
+        }
+    }
+}
+
+/// Returns the metadata of a simulation as YAML: the commit of the code and all the parameters
+pub fn metadata<const S: usize>(parameters: &Parameters<S>) -> String {
+    format!("git_hash: \"{}\"\n{}", env!("GIT_HASH"), parameters.to_yaml())
+}
+
+/// Statistics of the diversity of a replicate over the generations written to the output file
+struct DiversitySummary {
+    generations: usize,
+    /// Sum, minimum, maximum and last value of the entropy, haplotype diversity, nucleotide
+    /// diversity and number of strains
+    diversity: [(f64, f64, f64, f64); 4],
+    pop_mean_fitness: f64,
+    pop_fitness_var: f64
+}
+
+impl DiversitySummary {
+    fn new() -> Self {
+        Self {
+            generations: 0,
+            diversity: [(0., f64::INFINITY, f64::NEG_INFINITY, f64::NAN); 4],
+            pop_mean_fitness: 0.,
+            pop_fitness_var: 0.
+        }
+    }
+
+    fn add(&mut self, datapoint: &DataPoint) {
+        let values = [datapoint.entropy, datapoint.haplotype_diversity, datapoint.nucleotide_diversity, datapoint.strains as f64];
+        for ((sum, min, max, last), x) in self.diversity.iter_mut().zip(values) {
+            *sum += x;
+            *min = min.min(x);
+            *max = max.max(x);
+            *last = x;
+        }
+        self.pop_mean_fitness += datapoint.pop_mean_fitness;
+        self.pop_fitness_var  += datapoint.pop_fitness_var;
+        self.generations += 1;
+    }
+
+    fn save(&self, file: &mut BufWriter<File>) -> Result<(), Box<dyn Error>> {
+        let n = self.generations as f64;
+        write!(file, "\t{}", self.generations)?;
+        for (sum, min, max, last) in self.diversity {
+            write!(file, "\t{}\t{}\t{}\t{}", sum / n, min, max, last)?;
+        }
+        writeln!(file, "\t{}\t{}", self.pop_mean_fitness / n, self.pop_fitness_var / n)?;
+        Ok(())
+    }
+}
+
+pub struct Data<'a, const S: usize, C: ConvergenceCriterion<S> = Convergence> {
+    summary: Option<BufWriter<File>>,
+    summary_filename: Option<String>,
+    stats: Option<BufWriter<File>>,
+    diversity: HashMap<(usize, usize, usize), DiversitySummary>,
     parameters: &'a Parameters<S>,
-    buffer: Vec<DataPoint>,
-    pos: usize,
-    past_top_genotypes: Vec<[i64; MAX_TOPGENOTYPES]>
+    buffer: VecDeque<DataPoint>,
+    past_top_genotypes: VecDeque<[i64; MAX_TOPGENOTYPES]>,
+    buffer_size: usize,
+    stable_window: usize,
+    /// Full fitness landscape of the last recorded generation, identified by (l, r, t)
+    previous_landscape: Option<((usize, usize, usize), ErasedLandscape)>,
+    /// RMS change of the fitness landscape due to the last perturbation, see record_perturbation
+    perturbation: f64,
+    /// Statistics of the last phenotypic landscape, identified by its fingerprint
+    phenotypic_stats: Option<(u64, PhenotypicLandscapeStats)>,
+    /// Statistics of the last full fitness landscape, identified by its fingerprint
+    fitness_stats: Option<(u64, FitnessLandscapeStats)>,
+    criterion: C
 }
 
 impl<'a, const S: usize> Data<'a, S> {
     pub fn from_parameters(parameters: &'a Parameters<S>, l: usize) -> Self {
+        Self::with_criterion(parameters, l, parameters.convergence)
+    }
+}
+
+impl<'a, const S: usize, C: ConvergenceCriterion<S>> Data<'a, S, C> {
+    pub fn with_criterion(parameters: &'a Parameters<S>, l: usize, criterion: C) -> Self {
         let unique_id = rand::thread_rng().gen_range(0..10000);
 
         let folder_name = if parameters.folder_name.len() > 0 {
@@ -53,29 +204,70 @@ impl<'a, const S: usize> Data<'a, S> {
             unique_id
         ) };
 
-        let file = File::create(filename).unwrap();
-        let mut summary = BufWriter::new(file);
+        std::fs::write(format!("{}.meta.yaml", filename.trim_end_matches(".dat")), metadata(parameters)).unwrap();
+
+        let mut data = Self::in_memory(parameters, criterion);
+        data.summary = Some(BufWriter::new(File::create(&filename).unwrap()));
+        data.summary_filename = Some(filename);
+        data.write_header(parameters).unwrap();
 
-        summary.write(b"#n_pop\tlandscape_idx\treplicate\tt\tentropy\thaplotype_diversity\tnucleotide_diversity\tstrains\tn_maxima\tn_minima\tmaximum\tminimum\tgamma\tmean\tvar\tfitness_wildtype\tmean_phenotypic_distance").unwrap();
+        let summary = data.summary.as_mut().unwrap();
+        summary.write_all(b"#n_pop\tlandscape_idx\treplicate\tt\tentropy\thaplotype_diversity\tnucleotide_diversity\tstrains\tn_maxima\tn_minima\tmaximum\tminimum\tgamma\tmean\tvar\tfitness_wildtype\tmean_phenotypic_distance\tmean_walk_length\twalk_length_variance\tperturbation\tpop_mean_fitness\tpop_fitness_var\tpop_max_fitness\tpop_min_fitness\tmean_evolvability\tfitness_flux\tclass1_mean_fitness\tphenotypic_variance\tfitness_sensitivity\tphenotypic_neighbor_correlation\tepistasis_strength\tmean_mutations\tmutations_variance\tdeepest_valley\tmean_s\tfrac_beneficial\tinformation_content\tepistatic_content\tmean_hamming_from_founder\tgenetic_load\tmagnitude_epistasis_fraction\tsign_epistasis_fraction\tmin_resource_correlation\tlog_fitness_flux\tresource_landscape_correlation").unwrap();
         for i in 0..MAX_TOPGENOTYPES {
             summary.write(format!("\ttg{}\tn{}", i, i).as_bytes()).unwrap();
         }
-        summary.write(b"\n").unwrap();
+        #[cfg(feature = "allele_frequencies")]
+        for i in 0..l {
+            summary.write_all(format!("\taf{}", i).as_bytes()).unwrap();
+        }
+        summary.write_all(b"\n").unwrap();
+        data
+    }
+
+    /// Writes the simulation metadata as a YAML block of `# key: value` comment lines
+    pub fn write_header(&mut self, parameters: &Parameters<S>) -> Result<(), Box<dyn Error>> {
+        if let Some(summary) = &mut self.summary {
+            for line in metadata(parameters).lines() {
+                writeln!(summary, "# {}", line)?;
+            }
+        }
+        Ok(())
+    }
 
+    /// Creates a data buffer that is not backed by a file, e.g., to follow a single replicate
+    pub fn in_memory(parameters: &'a Parameters<S>, criterion: C) -> Self {
+        let buffer_size = parameters.buffer_size.max(1);
         Self {
-            summary,
+            summary: None,
+            summary_filename: None,
+            stats: None,
+            diversity: HashMap::new(),
             parameters,
-            buffer: vec![DataPoint::empty(); BUFFER_SIZE],
-            pos: 0,
-            past_top_genotypes: vec![[-1; MAX_TOPGENOTYPES]; BUFFER_SIZE]
+            buffer: VecDeque::with_capacity(buffer_size),
+            past_top_genotypes: VecDeque::with_capacity(buffer_size),
+            buffer_size,
+            stable_window: parameters.stable_window.max(1),
+            previous_landscape: None,
+            perturbation: 0.,
+            phenotypic_stats: None,
+            fitness_stats: None,
+            criterion
         }
     }
 
+    /// Sets the RMS change of the fitness landscape caused by perturbing it, which is recorded in
+    /// the following datapoints
+    pub fn record_perturbation(&mut self, perturbation: f64) {
+        self.perturbation = perturbation;
+    }
+
     pub fn save_landscape<const L: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L,S>, l: usize) -> Result<(), Box<dyn Error>> {
         landscape.save(&self.parameters.model.get_name()[..], l)?;
         Ok(())
     }
 
+    /// Records the statistics of generation t, including the full fitness landscape every
+    /// `save_landscape_every` generations
     pub fn save_datapoint<const L: usize>(&mut self,
         l: usize,
         r: usize,
@@ -85,34 +277,148 @@ impl<'a, const S: usize> Data<'a, S> {
         t: usize,
         write_to_file: bool
     ) -> Result<(), Box<dyn Error>> {
-            self.buffer[self.pos] = DataPoint::new(&population, &landscape, &resources, l, r, t);
-            self.past_top_genotypes[self.pos] = self.top_genotypes();
-            self.pos = (self.pos + 1) % BUFFER_SIZE;
+        let include_landscape = self.parameters.save_landscape_every.is_some_and(|n| n > 0 && t % n == 0);
+        self.save_datapoint_with_landscape(l, r, population, landscape, resources, t, write_to_file, include_landscape)
+    }
+
+    /// Records the statistics of generation t, storing the full fitness landscape in the datapoint
+    /// if `include_landscape` is set
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_datapoint_with_landscape<const L: usize>(&mut self,
+        l: usize,
+        r: usize,
+        population: &FixedSizePopulation<L>,
+        landscape:  &ResourceBasedFitnessLandscape<L,S>,
+        resources:  &Vector<S>,
+        t: usize,
+        write_to_file: bool,
+        include_landscape: bool
+    ) -> Result<(), Box<dyn Error>> {
+        let fitness_landscape = landscape.get_full_fitness_landscape(population, resources);
+        self.record_datapoint(l, r, population, landscape, fitness_landscape, resources, t, write_to_file, include_landscape)
+    }
+
+    /// Same as `save_datapoint`, but takes the full fitness landscape from the cache, so that it
+    /// is not computed again when the caller also needs it
+    #[allow(clippy::too_many_arguments)]
+    pub fn save_datapoint_cached<const L: usize>(&mut self,
+        l: usize,
+        r: usize,
+        population: &FixedSizePopulation<L>,
+        cache: &FitnessCache<L,S>,
+        resources:  &Vector<S>,
+        t: usize,
+        write_to_file: bool
+    ) -> Result<(), Box<dyn Error>> {
+        let include_landscape = self.parameters.save_landscape_every.is_some_and(|n| n > 0 && t % n == 0);
+        let fitness_landscape = cache.get_full_fitness_landscape_cached(population, resources).clone();
+        self.record_datapoint(l, r, population, cache.landscape(), fitness_landscape, resources, t, write_to_file, include_landscape)
+    }
+
+    /// Records the statistics of generation t given the full fitness landscape
+    #[allow(clippy::too_many_arguments)]
+    fn record_datapoint<const L: usize>(&mut self,
+        l: usize,
+        r: usize,
+        population: &FixedSizePopulation<L>,
+        landscape:  &ResourceBasedFitnessLandscape<L,S>,
+        fitness_landscape: FitnessLandscape<L>,
+        resources:  &Vector<S>,
+        t: usize,
+        write_to_file: bool,
+        include_landscape: bool
+    ) -> Result<(), Box<dyn Error>> {
+            if self.buffer.len() == self.buffer_size {
+                self.buffer.pop_front();
+                self.past_top_genotypes.pop_front();
+            }
+            let landscape_stats = self.landscape_stats(landscape, &fitness_landscape);
+            let mut datapoint = DataPoint::new(population, landscape, &fitness_landscape, (&landscape_stats.0, &landscape_stats.1), resources, self.perturbation, l, r, t);
+            if let Some(((pl, pr, pt), previous)) = &self.previous_landscape {
+                if let (true, Some(previous)) = ((*pl, *pr) == (l, r) && *pt < t, previous.downcast_ref::<FitnessLandscape<L>>()) {
+                    datapoint.log_fitness_flux = fitness_flux(previous, &fitness_landscape, population, (t - pt) as f64);
+                }
+            }
+            if let Some([r1, r2]) = &self.parameters.compare_env {
+                datapoint.resource_landscape_correlation = resource_landscape_correlation(landscape, population, r1, r2);
+            }
+            if include_landscape {
+                datapoint.landscape = Some(fitness_landscape.to_vec());
+            }
+            self.previous_landscape = Some(((l, r, t), Box::new(fitness_landscape)));
+            self.buffer.push_back(datapoint);
+            let top_genotypes = self.top_genotypes();
+            self.past_top_genotypes.push_back(top_genotypes);
 
             if write_to_file {
-                self.buffer[self.pos].save(&mut self.summary)?;
+                if let (Some(summary), Some(datapoint)) = (self.summary.as_mut(), self.buffer.back()) {
+                    datapoint.save_with_landscape(summary)?;
+                    self.diversity.entry((datapoint.size, datapoint.l, datapoint.r)).or_insert_with(DiversitySummary::new).add(datapoint);
+                }
             }
             Ok(())
         }
 
+    /// Returns the statistics of the landscapes, which are only recomputed when they change
+    fn landscape_stats<const L: usize>(&mut self,
+        landscape: &ResourceBasedFitnessLandscape<L,S>,
+        fitness_landscape: &FitnessLandscape<L>
+    ) -> (PhenotypicLandscapeStats, FitnessLandscapeStats) {
+        let key = landscape.fingerprint();
+        let phenotypic_stats = match self.phenotypic_stats {
+            Some((k, stats)) if k == key => stats,
+            _ => self.phenotypic_stats.insert((key, PhenotypicLandscapeStats::new(landscape))).1
+        };
+        let key = fitness_landscape.fingerprint();
+        let fitness_stats = match self.fitness_stats {
+            Some((k, stats)) if k == key => stats,
+            _ => self.fitness_stats.insert((key, FitnessLandscapeStats::new(fitness_landscape))).1
+        };
+        (phenotypic_stats, fitness_stats)
+    }
+
     pub fn write_to_file(&mut self) -> Result<(), Box<dyn Error>> {
-        let beg = self.pos - 1 - MAX_GENERATIONS + BUFFER_SIZE;
-        for i in 0..MAX_GENERATIONS {
-            self.buffer[(beg + i) % BUFFER_SIZE].save(&mut self.summary)?;
+        let datapoints = self.last_datapoints();
+        self.write_datapoints(&datapoints)
+    }
+
+    /// Returns the datapoints of the last `stable_window` generations, i.e., the ones written by
+    /// write_to_file
+    pub fn last_datapoints(&self) -> Vec<DataPoint> {
+        let beg = self.buffer.len().saturating_sub(self.stable_window);
+        self.buffer.range(beg..).cloned().collect()
+    }
+
+    /// Writes a list of datapoints, e.g., collected from another Data instance, to the file
+    pub fn write_datapoints(&mut self, datapoints: &[DataPoint]) -> Result<(), Box<dyn Error>> {
+        if let Some(summary) = self.summary.as_mut() {
+            for datapoint in datapoints {
+                datapoint.save_with_landscape(summary)?;
+                self.diversity.entry((datapoint.size, datapoint.l, datapoint.r)).or_insert_with(DiversitySummary::new).add(datapoint);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a comment row marking that the landscape of a replicate changed in generation t
+    pub fn write_shift_marker(&mut self, size: usize, l: usize, r: usize, t: usize) -> Result<(), Box<dyn Error>> {
+        if let Some(summary) = self.summary.as_mut() {
+            writeln!(summary, "#shift\t{}\t{}\t{}\t{}", size, l, r, t)?;
         }
         Ok(())
     }
 
     pub fn top_genotypes(&self) -> [i64; MAX_TOPGENOTYPES] {
         let mut tg = HashMap::<i64, usize>::with_capacity(MAX_TOPGENOTYPES);
-        for i in 0..MAX_GENERATIONS {
-            for &g in &self.buffer[(self.pos - i + BUFFER_SIZE) % BUFFER_SIZE].top_genotypes {
+        for datapoint in self.buffer.iter().rev().take(self.stable_window) {
+            for &g in &datapoint.top_genotypes {
                 if g == -1 { break }
                 let count = tg.entry(g).or_insert(0);
                 *count += 1;
             }
         }
-        tg.retain(|_, n| (*n as f64) / (MAX_GENERATIONS as f64) > THRESHOLD);
+        let window = self.stable_window as f64;
+        tg.retain(|_, n| (*n as f64) / window > THRESHOLD);
 
         let mut arr: Vec<i64> = tg.into_keys().collect();
         arr.sort_unstable();
@@ -120,31 +426,168 @@ impl<'a, const S: usize> Data<'a, S> {
         arr.try_into().unwrap()
     }
 
+    /// Writes the mean, minimum, maximum and final values of the diversity measures and the
+    /// time-averaged mean and variance of the fitness of each replicate written to the output
+    /// file to `<output file>.summary`
+    pub fn write_diversity_summary(&mut self) -> Result<(), Box<dyn Error>> {
+        let filename = match &self.summary_filename {
+            Some(filename) => format!("{}.summary", filename.trim_end_matches(".dat")),
+            None           => return Ok(())
+        };
+        let mut file = BufWriter::new(File::create(filename)?);
+        write!(file, "#n_pop\tlandscape_idx\treplicate\tgenerations")?;
+        for name in ["entropy", "haplotype_diversity", "nucleotide_diversity", "strains"] {
+            write!(file, "\t{0}_mean\t{0}_min\t{0}_max\t{0}_final", name)?;
+        }
+        writeln!(file, "\tpop_mean_fitness\tpop_fitness_var")?;
+
+        let mut replicates: Vec<_> = self.diversity.iter().collect();
+        replicates.sort_unstable_by_key(|&(&(size, l, r), _)| (l, size, r));
+        for (&(size, l, r), summary) in replicates {
+            write!(file, "{}\t{}\t{}", size, l, r)?;
+            summary.save(&mut file)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Appends the statistics of a replicate to `<output file>.stats.tsv`, which is created with
+    /// its header on the first call
+    pub fn write_replicate_stats(&mut self, stats: &EvolutionaryDynamicsStats) -> Result<(), Box<dyn Error>> {
+        let filename = match &self.summary_filename {
+            Some(filename) => format!("{}.stats.tsv", filename.trim_end_matches(".dat")),
+            None           => return Ok(())
+        };
+        if self.stats.is_none() {
+            let mut file = BufWriter::new(File::create(filename)?);
+            EvolutionaryDynamicsStats::write_header(&mut file)?;
+            self.stats = Some(file);
+        }
+        stats.save(self.stats.as_mut().unwrap())
+    }
+
     pub fn flush(&mut self) -> Result<(), Box<dyn Error>> {
-        self.summary.flush()?;
+        if let Some(summary) = self.summary.as_mut() {
+            summary.flush()?;
+        }
+        if let Some(stats) = self.stats.as_mut() {
+            stats.flush()?;
+        }
         Ok(())
     }
 
+    /// Returns whether the simulation has converged according to the chosen criterion
     pub fn stable_state(&self) -> bool {
-        let tg1 = self.past_top_genotypes[(self.pos - 1 + BUFFER_SIZE) % BUFFER_SIZE];
-        for i in 1..MAX_GENERATIONS {
-            let tg2 = self.past_top_genotypes[(self.pos - i + BUFFER_SIZE) % BUFFER_SIZE];
-            if tg1 != tg2 {
-                return false
-            }
-        }
-        true
+        self.criterion.is_converged(self)
+    }
+
+    /// Returns whether enough datapoints are stored to check a convergence window, which is
+    /// capped by the size of the buffer
+    #[inline]
+    fn has_window(&self, window: usize) -> bool {
+        self.buffer.len() >= window.min(self.buffer_size)
     }
 }
 
-impl<'a, const S: usize> Drop for Data<'a, S> {
+impl<'a, const S: usize, C: ConvergenceCriterion<S>> Drop for Data<'a, S, C> {
     fn drop(&mut self) {
-        self.flush().unwrap();
+        if let Err(e) = self.flush() {
+            eprintln!("Could not flush the output files: {}", e);
+        }
+        if let Err(e) = self.write_diversity_summary() {
+            eprintln!("Could not write the diversity summary: {}", e);
+        }
     }
 }
 
 const MAX_TOPGENOTYPES: usize = 10;
 
+/// Summary of the dynamics of a replicate, computed from the datapoints recorded until it stopped
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EvolutionaryDynamicsStats {
+    pub size: usize,
+    pub l: usize,
+    pub r: usize,
+    /// Generation at which the replicate stopped
+    pub t_convergence: usize,
+    pub mean_entropy_stationary: f64,
+    /// Time average of the mean fitness of the population
+    pub mean_fitness_stationary: f64,
+    /// Number of genotypes that are among the most abundant ones in more than 10% of the
+    /// datapoints, as for the top genotypes of `Data`
+    pub n_dominant_peaks: usize,
+    pub mean_walk_length: f64,
+    /// Fraction of the datapoints with more than one strain
+    pub fraction_time_polymorphic: f64
+}
+
+impl EvolutionaryDynamicsStats {
+    pub fn from_data_history(points: &[DataPoint]) -> Self {
+        let n = points.len() as f64;
+        let mean = |f: fn(&DataPoint) -> f64| points.iter().map(f).sum::<f64>() / n;
+
+        let mut top_genotypes = HashMap::<i64, usize>::new();
+        for point in points {
+            for &g in point.top_genotypes.iter().take_while(|&&g| g != -1) {
+                *top_genotypes.entry(g).or_insert(0) += 1;
+            }
+        }
+
+        let first = points.first().cloned().unwrap_or_else(DataPoint::empty);
+        Self {
+            size: first.size,
+            l: first.l,
+            r: first.r,
+            t_convergence: points.last().map_or(0, |point| point.t),
+            mean_entropy_stationary: mean(|point| point.entropy),
+            mean_fitness_stationary: mean(|point| point.pop_mean_fitness),
+            n_dominant_peaks: top_genotypes.values().filter(|&&count| count as f64 / n > THRESHOLD).count(),
+            mean_walk_length: mean(|point| point.mean_walk_length),
+            fraction_time_polymorphic: mean(|point| (point.strains > 1) as usize as f64)
+        }
+    }
+
+    pub fn write_header<W: Write>(file: &mut W) -> Result<(), Box<dyn Error>> {
+        writeln!(file, "#n_pop\tlandscape_idx\treplicate\tt_convergence\tmean_entropy_stationary\tmean_fitness_stationary\tn_dominant_peaks\tmean_walk_length\tfraction_time_polymorphic")?;
+        Ok(())
+    }
+
+    pub fn save<W: Write>(&self, file: &mut W) -> Result<(), Box<dyn Error>> {
+        writeln!(
+            file, "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            self.size, self.l, self.r, self.t_convergence,
+            self.mean_entropy_stationary, self.mean_fitness_stationary,
+            self.n_dominant_peaks, self.mean_walk_length, self.fraction_time_polymorphic
+        )?;
+        Ok(())
+    }
+
+    /// Reads the statistics of all replicates in a file written by `Data::write_replicate_stats`
+    pub fn load(filename: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let mut stats = Vec::new();
+        for line in BufReader::new(File::open(filename)?).lines() {
+            let line = line?;
+            if line.starts_with('#') || line.trim().is_empty() { continue }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 9 {
+                return Err(format!("expected 9 columns but found {} in line {}", fields.len(), line).into())
+            }
+            stats.push(Self {
+                size: fields[0].parse()?,
+                l: fields[1].parse()?,
+                r: fields[2].parse()?,
+                t_convergence: fields[3].parse()?,
+                mean_entropy_stationary: fields[4].parse()?,
+                mean_fitness_stationary: fields[5].parse()?,
+                n_dominant_peaks: fields[6].parse()?,
+                mean_walk_length: fields[7].parse()?,
+                fraction_time_polymorphic: fields[8].parse()?
+            });
+        }
+        Ok(stats)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct DataPoint {
     size: usize,
@@ -165,19 +608,95 @@ pub struct DataPoint {
     var:   f64,
     fitness_wildtype: f64,
     mean_phenotypic_distance: f64,
+    mean_walk_length: f64,
+    walk_length_variance: f64,
+    perturbation: f64,
+    pop_mean_fitness: f64,
+    pop_fitness_var: f64,
+    pop_max_fitness: f64,
+    pop_min_fitness: f64,
+    mean_evolvability: f64,
+    fitness_flux: f64,
+    class1_mean_fitness: f64,
+    phenotypic_variance: f64,
+    fitness_sensitivity: f64,
+    phenotypic_neighbor_correlation: f64,
+    epistasis_strength: f64,
+    mean_mutations: f64,
+    mutations_variance: f64,
+    deepest_valley: f64,
+    mean_s: f64,
+    frac_beneficial: f64,
+    information_content: f64,
+    epistatic_content: f64,
+    mean_hamming_from_founder: f64,
+    genetic_load: f64,
+    magnitude_epistasis_fraction: f64,
+    sign_epistasis_fraction: f64,
+    min_resource_correlation: f64,
+    log_fitness_flux: f64,
+    resource_landscape_correlation: f64,
+    #[cfg(feature = "allele_frequencies")]
+    allele_frequencies: Vec<f64>,
     landscape: Option<VecLandscape>
 }
 
+/// Statistics that only depend on the phenotypic landscape, which are expensive to compute and
+/// therefore computed once per landscape
+#[derive(Clone, Copy, Debug)]
+pub struct PhenotypicLandscapeStats {
+    pub phenotypic_neighbor_correlation: f64,
+    /// Largest trade-off between the resources
+    pub min_resource_correlation: f64
+}
+
+impl PhenotypicLandscapeStats {
+    pub fn new<const L: usize, const S: usize>(landscape: &ResourceBasedFitnessLandscape<L,S>) -> Self {
+        let min_resource_correlation = landscape.all_resource_correlations().as_full().iter().enumerate().flat_map(|(i, row)| {
+            row.iter().enumerate().filter(move |&(j, _)| j != i).map(|(_, &c)| c)
+        }).fold(f64::NAN, f64::min);
+        Self {
+            phenotypic_neighbor_correlation: landscape.phenotypic_neighbor_correlation(),
+            min_resource_correlation
+        }
+    }
+}
+
+/// Expensive statistics of the full fitness landscape, computed once per distinct landscape
+#[derive(Clone, Copy, Debug)]
+pub struct FitnessLandscapeStats {
+    pub deepest_valley: f64,
+    pub information_content: f64,
+    /// Number of mutations with (no, magnitude, sign) epistasis
+    pub epistasis: (usize, usize, usize)
+}
+
+impl FitnessLandscapeStats {
+    pub fn new<const L: usize>(fitness_landscape: &FitnessLandscape<L>) -> Self {
+        Self {
+            deepest_valley: fitness_landscape.deepest_valley(),
+            information_content: fitness_landscape.information_content(),
+            epistasis: fitness_landscape.classify_epistasis()
+        }
+    }
+}
+
 impl DataPoint {
+    /// Computes the statistics of generation t, where `fitness_landscape` is the full fitness
+    /// landscape of `landscape` for the given population and `landscape_stats` are the statistics
+    /// of both landscapes
+    #[allow(clippy::too_many_arguments)]
     pub fn new<const S: usize, const L: usize>(
         population: &FixedSizePopulation<L>,
         landscape:  &ResourceBasedFitnessLandscape<L,S>,
+        fitness_landscape: &FitnessLandscape<L>,
+        landscape_stats: (&PhenotypicLandscapeStats, &FitnessLandscapeStats),
         resources:  &Vector<S>,
+        perturbation: f64,
         l: usize,
         r: usize,
         t: usize
     ) -> Self {
-            let fitness_landscape = landscape.get_full_fitness_landscape(&population, &resources);
             let (_, &max) = fitness_landscape.max().unwrap_or((&Genotype::new(), &f64::NAN));
             let (_, &min) = fitness_landscape.min().unwrap_or((&Genotype::new(), &f64::NAN));
             let (mean, var) = fitness_landscape.mean_var();
@@ -186,27 +705,26 @@ impl DataPoint {
 
             let mean_phenotypic_distance = landscape.mean_phenotypic_distance(population);
 
+            let (entropy, haplotype_diversity, nucleotide_diversity) = population.diversity_stats_in_one_pass();
+            let (mean_mutations, mutations_variance) = population.hamming_class_moments();
+            let (mean_walk_length, walk_length_variance) = fitness_landscape.walk_length_moments();
+            let (phenotypic_stats, fitness_stats) = landscape_stats;
+            let (no_epistasis, magnitude, sign) = fitness_stats.epistasis;
+            let n_mutations = (no_epistasis + magnitude + sign) as f64;
+
             let mut top_genotypes   = [-1; MAX_TOPGENOTYPES];
             let mut n_top_genotypes = [ 0; MAX_TOPGENOTYPES];
-            let mut k = 0;
-
-            for (g, n) in population.iter() {
-                if let Some(f) = fitness_landscape.get(g) {
-                    if *f > 1. {
-                        top_genotypes[k] = g.index() as i64;
-                        n_top_genotypes[k] = *n;
-                        k += 1;
-                    }
-                }
-                if k >= MAX_TOPGENOTYPES { break; }
+            for (k, (g, n)) in population.top_n_by_frequency(MAX_TOPGENOTYPES).into_iter().enumerate() {
+                top_genotypes[k] = g.index() as i64;
+                n_top_genotypes[k] = n;
             }
 
             Self {
                 size: population.size(),
                 l, r, t,
-                entropy: population.shannon_entropy(),
-                haplotype_diversity: population.haplotype_diversity(),
-                nucleotide_diversity: population.nucleotide_diversity(),
+                entropy,
+                haplotype_diversity,
+                nucleotide_diversity,
                 strains: population.n_genotypes(),
                 n_maxima: fitness_landscape.maxima().len(),
                 n_minima: fitness_landscape.minima().len(),
@@ -218,6 +736,38 @@ impl DataPoint {
                 var:   var,
                 fitness_wildtype,
                 mean_phenotypic_distance,
+                mean_walk_length,
+                walk_length_variance,
+                perturbation,
+                pop_mean_fitness: population.mean_fitness(landscape, resources),
+                pop_fitness_var: population.fitness_variance(landscape, resources),
+                pop_max_fitness: population.max_fitness(landscape, resources),
+                pop_min_fitness: population.min_fitness(landscape, resources),
+                mean_evolvability: fitness_landscape.mean_evolvability(),
+                fitness_flux: fitness_landscape.fitness_flux(population),
+                class1_mean_fitness: fitness_landscape.conditional_mean_fitness(1),
+                phenotypic_variance: population.phenotypic_covariance(landscape).trace(),
+                fitness_sensitivity: landscape.mean_fitness_sensitivity(population, resources, SENSITIVITY_DELTA),
+                phenotypic_neighbor_correlation: phenotypic_stats.phenotypic_neighbor_correlation,
+                epistasis_strength: fitness_landscape.epistasis_strength(),
+                mean_mutations,
+                mutations_variance,
+                deepest_valley: fitness_stats.deepest_valley,
+                mean_s: fitness_landscape.mean_selection_coefficient(population, FitnessType::Multiplicative),
+                frac_beneficial: fitness_landscape.fraction_beneficial(population, FitnessType::Multiplicative),
+                information_content: fitness_stats.information_content,
+                epistatic_content: fitness_landscape.epistatic_content(),
+                mean_hamming_from_founder: population.mean_genetic_distance_from_founder().unwrap_or(f64::NAN),
+                genetic_load: population.substitutional_load(fitness_landscape),
+                magnitude_epistasis_fraction: magnitude as f64 / n_mutations,
+                sign_epistasis_fraction: sign as f64 / n_mutations,
+                min_resource_correlation: phenotypic_stats.min_resource_correlation,
+                // Set by `Data`, which knows the landscape of the previous generation
+                log_fitness_flux: f64::NAN,
+                // Set by `Data`, which knows the resource vectors to compare
+                resource_landscape_correlation: f64::NAN,
+                #[cfg(feature = "allele_frequencies")]
+                allele_frequencies: population.allele_frequencies().to_vec(),
                 landscape: None
             }
         }
@@ -242,12 +792,43 @@ impl DataPoint {
             var:   f64::NAN,
             fitness_wildtype: f64::NAN,
             mean_phenotypic_distance: f64::NAN,
+            mean_walk_length: f64::NAN,
+            walk_length_variance: f64::NAN,
+            perturbation: f64::NAN,
+            pop_mean_fitness: f64::NAN,
+            pop_fitness_var: f64::NAN,
+            pop_max_fitness: f64::NAN,
+            pop_min_fitness: f64::NAN,
+            mean_evolvability: f64::NAN,
+            fitness_flux: f64::NAN,
+            class1_mean_fitness: f64::NAN,
+            phenotypic_variance: f64::NAN,
+            fitness_sensitivity: f64::NAN,
+            phenotypic_neighbor_correlation: f64::NAN,
+            epistasis_strength: f64::NAN,
+            mean_mutations: f64::NAN,
+            mutations_variance: f64::NAN,
+            deepest_valley: f64::NAN,
+            mean_s: f64::NAN,
+            frac_beneficial: f64::NAN,
+            information_content: f64::NAN,
+            epistatic_content: f64::NAN,
+            mean_hamming_from_founder: f64::NAN,
+            genetic_load: f64::NAN,
+            magnitude_epistasis_fraction: f64::NAN,
+            sign_epistasis_fraction: f64::NAN,
+            min_resource_correlation: f64::NAN,
+            log_fitness_flux: f64::NAN,
+            resource_landscape_correlation: f64::NAN,
+            #[cfg(feature = "allele_frequencies")]
+            allele_frequencies: Vec::new(),
             landscape: None
         }
     }
 
     pub fn get(&self, property: &str) -> f64 {
         match property {
+            "size"     => self.size as f64,
             "entropy"  => self.entropy,
             "strains"  => self.strains as f64,
             "n_maxima" => self.n_maxima as f64,
@@ -259,6 +840,34 @@ impl DataPoint {
             "var"      => self.var,
             "fitness_wildtype" => self.fitness_wildtype,
             "phenotypic_distance" => self.mean_phenotypic_distance,
+            "mean_walk_length"    => self.mean_walk_length,
+            "walk_length_variance" => self.walk_length_variance,
+            "perturbation" => self.perturbation,
+            "pop_mean_fitness" => self.pop_mean_fitness,
+            "pop_fitness_var"  => self.pop_fitness_var,
+            "pop_max_fitness"  => self.pop_max_fitness,
+            "pop_min_fitness"  => self.pop_min_fitness,
+            "mean_evolvability" => self.mean_evolvability,
+            "fitness_flux"     => self.fitness_flux,
+            "class1_mean_fitness" => self.class1_mean_fitness,
+            "phenotypic_variance" => self.phenotypic_variance,
+            "fitness_sensitivity" => self.fitness_sensitivity,
+            "phenotypic_neighbor_correlation" => self.phenotypic_neighbor_correlation,
+            "epistasis_strength" => self.epistasis_strength,
+            "mean_mutations" => self.mean_mutations,
+            "mutations_variance" => self.mutations_variance,
+            "deepest_valley" => self.deepest_valley,
+            "mean_s" => self.mean_s,
+            "frac_beneficial" => self.frac_beneficial,
+            "information_content" => self.information_content,
+            "epistatic_content" => self.epistatic_content,
+            "mean_hamming_from_founder" => self.mean_hamming_from_founder,
+            "genetic_load" => self.genetic_load,
+            "magnitude_epistasis_fraction" => self.magnitude_epistasis_fraction,
+            "sign_epistasis_fraction" => self.sign_epistasis_fraction,
+            "min_resource_correlation" => self.min_resource_correlation,
+            "log_fitness_flux" => self.log_fitness_flux,
+            "resource_landscape_correlation" => self.resource_landscape_correlation,
             _ => panic!("unrecognized property: {}", property)
         }
     }
@@ -271,7 +880,7 @@ impl DataPoint {
         }
 
         file.write(
-            format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+            format!("{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
                 self.size, self.l, self.r, self.t,
                 self.entropy, self.haplotype_diversity,
                 self.nucleotide_diversity, self.strains,
@@ -281,9 +890,292 @@ impl DataPoint {
                 self.mean, self.var,
                 self.fitness_wildtype,
                 self.mean_phenotypic_distance,
+                self.mean_walk_length, self.walk_length_variance,
+                self.perturbation,
+                self.pop_mean_fitness, self.pop_fitness_var,
+                self.pop_max_fitness, self.pop_min_fitness,
+                self.mean_evolvability, self.fitness_flux,
+                self.class1_mean_fitness,
+                self.phenotypic_variance,
+                self.fitness_sensitivity,
+                self.phenotypic_neighbor_correlation,
+                self.epistasis_strength,
+                self.mean_mutations,
+                self.mutations_variance,
+                self.deepest_valley,
+                self.mean_s,
+                self.frac_beneficial,
+                self.information_content,
+                self.epistatic_content,
+                self.mean_hamming_from_founder,
+                self.genetic_load,
+                self.magnitude_epistasis_fraction,
+                self.sign_epistasis_fraction,
+                self.min_resource_correlation,
+                self.log_fitness_flux,
+                self.resource_landscape_correlation,
                 top_genotypes.join("\t")
             ).as_bytes()
         )?;
+        #[cfg(feature = "allele_frequencies")]
+        for f in &self.allele_frequencies {
+            file.write_all(format!("\t{}", f).as_bytes())?;
+        }
+        file.write_all(b"\n")?;
         Ok(())
     }
+
+    /// Writes the datapoint followed, if it stores the fitness landscape, by a comment row
+    /// `#landscape size l r t` with a `sequence:fitness` column per genotype
+    pub fn save_with_landscape(&self, file: &mut BufWriter<File>) -> Result<(), Box<dyn Error>> {
+        self.save(file)?;
+        if let Some(landscape) = &self.landscape {
+            write!(file, "#landscape\t{}\t{}\t{}\t{}", self.size, self.l, self.r, self.t)?;
+            for (seq, f) in landscape {
+                write!(file, "\t{}:{}", seq.iter().map(|a| a.to_string()).collect::<String>(), f)?;
+            }
+            file.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        fitness_model::FitnessModel,
+        population::InitialPopulation
+    };
+
+    #[test]
+    fn convergence_criteria() {
+        const L: usize = 5;
+        const S: usize = 2;
+        let parameters = Parameters::<S> {
+            pop_size: vec![100],
+            mutation_rate_per_locus: 0.,
+            model: FitnessModel::new_hoc(vec![0.1, 0.]),
+            replicates: 1,
+            resources: Vector::from([1., 1.]),
+            landscapes: [0, 1],
+            null_model: false,
+            load_landscape: false,
+            folder_name: std::env::temp_dir().to_str().unwrap().to_string() + "/",
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(parameters.model.clone());
+
+        // A monomorphic population without mutations is stationary with zero entropy
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random()));
+        let var = landscape.get_full_fitness_landscape(&population, &parameters.resources).mean_var().1;
+
+        let window = 5;
+        let criteria = [
+            (Convergence::StableTopGenotypes(StableTopGenotypes { window }), true),
+            (Convergence::EntropyThreshold(EntropyThreshold { max_entropy: 0.1, window }), true),
+            (Convergence::FitnessVarianceThreshold(FitnessVarianceThreshold { max_var: 2. * var, window }), true),
+            (Convergence::FitnessVarianceThreshold(FitnessVarianceThreshold { max_var: 0.5 * var, window }), false)
+        ];
+        for (criterion, converged) in criteria {
+            let mut data = Data::with_criterion(&parameters, L, criterion);
+            for t in 0..2*window {
+                data.save_datapoint(0, 0, &population, &landscape, &parameters.resources, t, false).unwrap();
+            }
+            assert_eq!(data.stable_state(), converged);
+        }
+    }
+
+    #[test]
+    fn buffer_size() {
+        const L: usize = 4;
+        const S: usize = 2;
+        let model = FitnessModel::new_hoc(vec![0.1, 0.]);
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(model.clone());
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+
+        // A single repeated state is enough with a window of one generation
+        let parameters = Parameters::<S> { model: model.clone(), stable_window: 1, ..Parameters::default() };
+        let mut data = Data::in_memory(&parameters, StableTopGenotypes { window: parameters.stable_window });
+        assert!(!data.stable_state());
+        data.save_datapoint(0, 0, &population, &landscape, &parameters.resources, 0, false).unwrap();
+        assert!(data.stable_state());
+        assert_eq!(data.last_datapoints().len(), 1);
+
+        let parameters = Parameters::<S> { model, buffer_size: 1, ..Parameters::default() };
+        let mut data = Data::in_memory(&parameters, Convergence::default());
+        for t in 0..10 {
+            data.save_datapoint(0, 0, &population, &landscape, &parameters.resources, t, false).unwrap();
+        }
+        assert!(data.stable_state());
+        assert_eq!(data.last_datapoints().len(), 1);
+    }
+
+    #[test]
+    fn landscape_snapshots() {
+        const L: usize = 3;
+        const S: usize = 2;
+        let model = FitnessModel::new_hoc(vec![0.1, 0.]);
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(model.clone());
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+
+        let parameters = Parameters::<S> { model, save_landscape_every: Some(2), ..Parameters::default() };
+        let mut data = Data::in_memory(&parameters, Convergence::default());
+        for t in 0..5 {
+            data.save_datapoint(0, 0, &population, &landscape, &parameters.resources, t, false).unwrap();
+        }
+        data.save_datapoint_with_landscape(0, 0, &population, &landscape, &parameters.resources, 5, false, true).unwrap();
+        let saved: Vec<bool> = data.last_datapoints().iter().map(|d| d.landscape.is_some()).collect();
+        assert_eq!(saved, vec![true, false, true, false, true, true]);
+        assert_eq!(data.last_datapoints()[0].landscape.as_ref().unwrap().len(), 1 << L);
+        // The landscape does not change for a constant population
+        let flux: Vec<f64> = data.last_datapoints().iter().map(|d| d.get("log_fitness_flux")).collect();
+        assert!(flux[0].is_nan() && flux[1..].iter().all(|f| f.abs() < 1e-12));
+
+        let filename = std::env::temp_dir().join(format!("landscape_snapshots_{}.dat", std::process::id()));
+        let mut file = BufWriter::new(File::create(&filename).unwrap());
+        data.last_datapoints()[0].save_with_landscape(&mut file).unwrap();
+        data.last_datapoints()[1].save_with_landscape(&mut file).unwrap();
+        drop(file);
+        let contents = std::fs::read_to_string(&filename).unwrap();
+        std::fs::remove_file(&filename).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("#landscape\t100\t0\t0\t0\t"));
+        assert_eq!(lines[1].split('\t').count(), 5 + (1 << L));
+    }
+
+    #[test]
+    fn metadata_header() {
+        const L: usize = 4;
+        const S: usize = 2;
+        let folder = std::env::temp_dir().join(format!("metadata_header_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let parameters = Parameters::<S> {
+            pop_size: vec![100, 1000],
+            mutation_rate_per_locus: 1e-3,
+            model: FitnessModel::new_rmf(vec![0.1, 0.5, 0.1, 0.3, 0.05]),
+            replicates: 3,
+            resources: Vector::from([1., 0.5]),
+            landscapes: [2, 5],
+            null_model: true,
+            folder_name: folder.to_str().unwrap().to_string() + "/",
+            compare_env: Some([Vector::from([1., 0.]), Vector::from([0., 1.])]),
+            target_landscape: Some(7),
+            convergence: Convergence::EntropyThreshold(EntropyThreshold { max_entropy: 0.5, window: 20 }),
+            seed: Some(42),
+            ..Parameters::default()
+        };
+        drop(Data::from_parameters(&parameters, L));
+
+        let mut files: Vec<_> = std::fs::read_dir(&folder).unwrap().map(|f| f.unwrap().path())
+                                                           .filter(|f| f.extension().unwrap() != "summary").collect();
+        files.sort();
+        assert_eq!(files.len(), 2);
+        let summary = std::fs::read_to_string(&files[0]).unwrap();
+        let sidecar = std::fs::read_to_string(&files[1]).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert!(files[1].to_str().unwrap().ends_with(".meta.yaml"));
+
+        let header: String = summary.lines().take_while(|line| line.starts_with("# ")).map(|line| format!("{}\n", line)).collect();
+        assert!(header.starts_with("# git_hash: "));
+        assert!(summary.lines().nth(header.lines().count()).unwrap().starts_with("#n_pop"));
+
+        for yaml in [header, sidecar] {
+            let read = Parameters::<S>::from_yaml(&yaml).unwrap();
+            assert_eq!(read.to_yaml(), parameters.to_yaml());
+            assert_eq!(read.pop_size, parameters.pop_size);
+            assert_eq!(read.model, parameters.model);
+            assert_eq!(read.convergence, parameters.convergence);
+            assert_eq!(read.compare_env.unwrap()[1].to_vec(), vec![0., 1.]);
+        }
+    }
+
+    #[test]
+    fn diversity_summary() {
+        const L: usize = 4;
+        const S: usize = 2;
+        let folder = std::env::temp_dir().join(format!("diversity_summary_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let parameters = Parameters::<S> {
+            model: FitnessModel::new_hoc(vec![0.1, 0.]),
+            folder_name: folder.to_str().unwrap().to_string() + "/",
+            ..Parameters::default()
+        };
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(parameters.model.clone());
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+
+        let mut data = Data::from_parameters(&parameters, L);
+        for r in 0..2 {
+            for t in 0..3 {
+                data.save_datapoint(0, r, &population, &landscape, &parameters.resources, t, true).unwrap();
+            }
+        }
+        drop(data);
+
+        let summary = std::fs::read_dir(&folder).unwrap().map(|f| f.unwrap().path())
+                                                  .find(|f| f.extension().unwrap() == "summary").unwrap();
+        let summary = std::fs::read_to_string(summary).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("#n_pop"));
+        let columns = lines[0].split('\t').count();
+        for (r, line) in lines[1..].iter().enumerate() {
+            let values: Vec<f64> = line.split('\t').map(|v| v.parse().unwrap()).collect();
+            assert_eq!(values.len(), columns);
+            assert_eq!(&values[..4], &[100., 0., r as f64, 3.]);
+            // A monomorphic population has a single strain and no entropy
+            assert_eq!(&values[4..8], &[0., 0., 0., 0.]);
+            assert_eq!(&values[16..20], &[1., 1., 1., 1.]);
+            assert!(values[20] > 0. && values[21].abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn replicate_stats() {
+        const S: usize = 2;
+        let points: Vec<DataPoint> = (0..10).map(|t| {
+            let mut point = DataPoint::empty();
+            (point.size, point.r, point.t) = (100, 3, 1000 + t);
+            point.entropy = t as f64;
+            point.pop_mean_fitness = 1.;
+            point.mean_walk_length = 2.;
+            point.strains = if t < 4 { 2 } else { 1 };
+            point.top_genotypes[0] = if t == 0 { 7 } else { 5 };
+            point
+        }).collect();
+        let stats = EvolutionaryDynamicsStats::from_data_history(&points);
+        assert_eq!((stats.size, stats.l, stats.r, stats.t_convergence), (100, 0, 3, 1009));
+        assert_eq!(stats.mean_entropy_stationary, 4.5);
+        assert_eq!((stats.mean_fitness_stationary, stats.mean_walk_length), (1., 2.));
+        assert_eq!(stats.n_dominant_peaks, 1);
+        assert_eq!(stats.fraction_time_polymorphic, 0.4);
+
+        let folder = std::env::temp_dir().join(format!("replicate_stats_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let parameters = Parameters::<S> {
+            model: FitnessModel::new_hoc(vec![0.1, 0.]),
+            folder_name: folder.to_str().unwrap().to_string() + "/",
+            ..Parameters::default()
+        };
+        let mut data = Data::from_parameters(&parameters, 4);
+        data.write_replicate_stats(&stats).unwrap();
+        data.write_replicate_stats(&EvolutionaryDynamicsStats::from_data_history(&points[..5])).unwrap();
+        drop(data);
+
+        let filename = std::fs::read_dir(&folder).unwrap().map(|f| f.unwrap().path())
+                                                   .find(|f| f.to_str().unwrap().ends_with(".stats.tsv")).unwrap();
+        let loaded = EvolutionaryDynamicsStats::load(filename.to_str().unwrap()).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0], stats);
+        assert_eq!(loaded[1].t_convergence, 1004);
+    }
 }
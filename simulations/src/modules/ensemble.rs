@@ -0,0 +1,140 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    ops::Range,
+    path::Path
+};
+
+use super::{
+    genotype::Genotype,
+    population::FixedSizePopulation,
+    resource_based_landscape::ResourceBasedFitnessLandscape,
+    fitness_landscape::{FitnessLandscape, FitnessType},
+    math::linear_algebra::Vector
+};
+
+/// Replicate landscapes of the same model, for statistics across landscapes
+pub struct LandscapeEnsemble<const L: usize, const S: usize> {
+    landscapes: Vec<ResourceBasedFitnessLandscape<L, S>>
+}
+
+impl<const L: usize, const S: usize> LandscapeEnsemble<L, S> {
+    pub fn from_landscapes(landscapes: Vec<ResourceBasedFitnessLandscape<L, S>>) -> Self {
+        Self { landscapes }
+    }
+
+    /// Loads the landscapes `L{L}_{model_name}_{l}.dat` in `dir` for all l in `range`
+    pub fn from_directory(dir: &str, model_name: &str, range: Range<usize>) -> Result<Self, Box<dyn Error>> {
+        let landscapes = range.map(|l| {
+            let filename = Path::new(dir).join(format!("L{}_{}_{}.dat", L, model_name, l));
+            ResourceBasedFitnessLandscape::try_load(filename.to_str().ok_or("invalid file name")?)
+        }).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { landscapes })
+    }
+
+    #[inline]
+    pub fn landscapes(&self) -> &[ResourceBasedFitnessLandscape<L, S>] {
+        &self.landscapes
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.landscapes.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.landscapes.is_empty()
+    }
+
+    /// Returns the full fitness landscape of each replicate for the population
+    fn fitness_landscapes(&self, resources: &Vector<S>, pop: &FixedSizePopulation<L>) -> Vec<FitnessLandscape<L>> {
+        self.landscapes.iter().map(|landscape| landscape.get_full_fitness_landscape(pop, resources)).collect()
+    }
+
+    /// Returns the fitness of each genotype averaged over all landscapes
+    pub fn mean_landscape(&self, resources: &Vector<S>, pop: &FixedSizePopulation<L>) -> FitnessLandscape<L> {
+        let n = self.len() as f64;
+        let mut mean = HashMap::<Genotype<L>, f64>::new();
+        for fitness_landscape in self.fitness_landscapes(resources, pop) {
+            for (&g, &f) in fitness_landscape.landscape.iter() {
+                *mean.entry(g).or_insert(0.) += f / n;
+            }
+        }
+
+        let mut mean_landscape = FitnessLandscape::new(FitnessType::Multiplicative);
+        for (g, f) in mean {
+            mean_landscape.add_genotype(g, f);
+        }
+        mean_landscape
+    }
+
+    /// Returns the standard deviation of the fitness of each genotype across all landscapes
+    pub fn landscape_std(&self, resources: &Vector<S>, pop: &FixedSizePopulation<L>) -> HashMap<Genotype<L>, f64> {
+        let n = self.len() as f64;
+        let mut moments = HashMap::<Genotype<L>, (f64, f64)>::new();
+        for fitness_landscape in self.fitness_landscapes(resources, pop) {
+            for (&g, &f) in fitness_landscape.landscape.iter() {
+                let (sum, sum_sq) = moments.entry(g).or_insert((0., 0.));
+                *sum    += f;
+                *sum_sq += f * f;
+            }
+        }
+        moments.into_iter().map(|(g, (sum, sum_sq))| {
+            let mean = sum / n;
+            (g, (sum_sq / n - mean * mean).max(0.).sqrt())
+        }).collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        fitness_model::FitnessModel,
+        genotype::landscape_size,
+        population::InitialPopulation
+    };
+
+    #[test]
+    fn ensemble() {
+        const L: usize = 4;
+        const S: usize = 2;
+        let model = FitnessModel::new_hoc(vec![0.1, 0.05]);
+        let resources = Vector::from([1., 1.]);
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+
+        let dir = std::env::temp_dir().join(format!("ensemble_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let landscapes: Vec<_> = (0..3).map(|_| ResourceBasedFitnessLandscape::<L, S>::new(model.clone())).collect();
+        for (l, landscape) in landscapes.iter().enumerate() {
+            let file = std::fs::File::create(dir.join(format!("L{}_{}_{}.dat", L, model.get_name(), l))).unwrap();
+            serde_cbor::to_writer(file, &landscape.to_vec()).unwrap();
+        }
+        let ensemble = LandscapeEnsemble::<L, S>::from_directory(dir.to_str().unwrap(), &model.get_name(), 0..3).unwrap();
+        assert!(LandscapeEnsemble::<L, S>::from_directory(dir.to_str().unwrap(), &model.get_name(), 2..4).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(ensemble.len(), 3);
+
+        let mean = ensemble.mean_landscape(&resources, &population);
+        let std = ensemble.landscape_std(&resources, &population);
+        assert_eq!(mean.landscape.len(), landscape_size::<L>());
+        assert_eq!(std.len(), landscape_size::<L>());
+
+        // The resident genotype has fitness 1 in all landscapes
+        let wildtype = Genotype::new();
+        assert!((mean.get(&wildtype).unwrap() - 1.).abs() < 1e-12);
+        assert!(std[&wildtype] < 1e-6);
+
+        let g = Genotype::from_sequence(&[1, 0, 1, 0]);
+        let fitness: Vec<f64> = landscapes.iter().map(|landscape| {
+            *landscape.get_full_fitness_landscape(&population, &resources).get(&g).unwrap()
+        }).collect();
+        let expected_mean = fitness.iter().sum::<f64>() / 3.;
+        let expected_var  = fitness.iter().map(|f| (f - expected_mean).powi(2)).sum::<f64>() / 3.;
+        assert!((mean.get(&g).unwrap() - expected_mean).abs() < 1e-12);
+        assert!((std[&g] - expected_var.sqrt()).abs() < 1e-9);
+    }
+}
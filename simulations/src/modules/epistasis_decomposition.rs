@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use super::{
+    genotype::Genotype,
+    fitness_landscape::{FitnessLandscape, FitnessType}
+};
+
+/// Returns the additive fitness of all genotypes ordered by their index, or None if the landscape
+/// is incomplete
+fn fitness_by_index<const L: usize>(landscape: &FitnessLandscape<L>) -> Option<Vec<f64>> {
+    (0..1 << L).map(|index| landscape.get_fitness(&Genotype::<L>::from_index(index), FitnessType::Additive)).collect()
+}
+
+/// Returns the Fourier coefficients of the function given by its values ordered by genotype index.
+/// The coefficients of the subsets with and without the last locus follow from those of the
+/// restrictions of the function to the genotypes carrying each of its alleles.
+fn fourier_coefficients(values: &[f64]) -> Vec<f64> {
+    let n = values.len();
+    if n == 1 {
+        return values.to_vec()
+    }
+    let (wildtype, derived) = values.split_at(n / 2);
+    let (c0, c1) = (fourier_coefficients(wildtype), fourier_coefficients(derived));
+
+    let mut coefficients = vec![0_f64; n];
+    for (t, (a, b)) in c0.iter().zip(c1.iter()).enumerate() {
+        coefficients[t] = (a + b) / 2.;
+        coefficients[t + n / 2] = (a - b) / 2.;
+    }
+    coefficients
+}
+
+/// Returns the loci in the subset encoded by the bits of `index`
+fn subset<const L: usize>(index: usize) -> Vec<usize> {
+    (0..L).filter(|&i| index >> i & 1 == 1).collect()
+}
+
+/// Returns the coefficients of the decomposition of the additive (log for multiplicative
+/// landscapes) fitness in the Fourier basis of the hypercube, `f(g) = Σ_T f̂(T) (-1)^(Σ_{i∈T} g_i)`,
+/// where the coefficient of each subset of loci T, given as a sorted list of loci, measures the
+/// interaction of order |T| between them. The coefficients are NaN if the landscape is incomplete.
+pub fn compute_wht_coefficients<const L: usize>(landscape: &FitnessLandscape<L>) -> HashMap<Vec<usize>, f64> {
+    let coefficients = match fitness_by_index(landscape) {
+        Some(values) => fourier_coefficients(&values),
+        None         => vec![f64::NAN; 1 << L]
+    };
+    coefficients.into_iter().enumerate().map(|(index, c)| (subset::<L>(index), c)).collect()
+}
+
+/// Returns the Fourier coefficient of a single subset of loci, or NaN if the landscape is
+/// incomplete
+pub fn coefficient_for_subset<const L: usize>(landscape: &FitnessLandscape<L>, subset: &[usize]) -> f64 {
+    let values = match fitness_by_index(landscape) {
+        Some(values) => values,
+        None         => return f64::NAN
+    };
+    values.iter().enumerate().map(|(index, f)| {
+        let parity = subset.iter().filter(|&&i| index >> i & 1 == 1).count();
+        if parity % 2 == 0 { *f } else { -f }
+    }).sum::<f64>() / values.len() as f64
+}
+
+/// Returns the additive landscape keeping only the terms of order 0 and 1 of the decomposition,
+/// which for a complete landscape is the least squares additive fit. The fitnesses are NaN if
+/// the landscape is incomplete.
+pub fn additive_approximation<const L: usize>(landscape: &FitnessLandscape<L>) -> FitnessLandscape<L> {
+    let coefficients = compute_wht_coefficients(landscape);
+    let mut approximation = FitnessLandscape::new(FitnessType::Additive);
+    for index in 0..1 << L {
+        let g = Genotype::<L>::from_index(index);
+        let f = coefficients[&vec![]] + (0..L).map(|i| {
+            let c = coefficients[&vec![i]];
+            if g[i] == 0 { c } else { -c }
+        }).sum::<f64>();
+        approximation.add_genotype(g, f);
+    }
+    approximation
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::genotype::possible_sequences;
+    use rand_distr::{Distribution, Normal};
+
+    #[test]
+    fn parseval() {
+        const L: usize = 5;
+        let normal = Normal::new(0., 1.).unwrap();
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), normal.sample(&mut rand::thread_rng()));
+        }
+
+        let coefficients = compute_wht_coefficients(&landscape);
+        assert_eq!(coefficients.len(), 1 << L);
+        let power: f64 = coefficients.values().map(|c| c * c).sum();
+        let norm: f64 = landscape.landscape.values().map(|f| f * f).sum::<f64>() / (1 << L) as f64;
+        assert!((power - norm).abs() < 1e-12);
+
+        for subset in [vec![], vec![2], vec![0, 3], vec![1, 2, 4]] {
+            assert!((coefficient_for_subset(&landscape, &subset) - coefficients[&subset]).abs() < 1e-12);
+        }
+        let mean = landscape.landscape.values().sum::<f64>() / (1 << L) as f64;
+        assert!((coefficients[&vec![]] - mean).abs() < 1e-12);
+
+        let mut incomplete = landscape.clone();
+        incomplete.remove_genotype(&Genotype::new());
+        assert!(coefficient_for_subset(&incomplete, &[0]).is_nan());
+    }
+
+    #[test]
+    fn additive_approximation_of_additive_landscape() {
+        const L: usize = 4;
+        let effects = [0.3, -1.2, 0.5, 2.];
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), 1. + seq.iter().zip(&effects).map(|(&a, e)| a as f64 * e).sum::<f64>());
+        }
+
+        for (subset, c) in compute_wht_coefficients(&landscape) {
+            match subset.len() {
+                1 => assert!((c + effects[subset[0]] / 2.).abs() < 1e-12),
+                n if n > 1 => assert!(c.abs() < 1e-12),
+                _ => {}
+            }
+        }
+        let approximation = additive_approximation(&landscape);
+        for (g, f) in &landscape.landscape {
+            assert!((approximation.get(g).unwrap() - f).abs() < 1e-12);
+        }
+    }
+}
@@ -1,6 +1,7 @@
 
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
     fmt,
     fs::File,
     io::{BufWriter, Write},
@@ -8,16 +9,46 @@ use std::{
     error::Error
 };
 
-use super::genotype::Genotype;
+use rand::{Rng, seq::SliceRandom};
+use rand_distr::{Distribution, Normal, WeightedIndex};
+
+use super::{
+    genotype::{Genotype, possible_sequences},
+    population::FixedSizePopulation,
+    resource_based_landscape::ResourceBasedFitnessLandscape,
+    math::{linear_algebra::{Vector, solve_linear_system}, fwht::fwht_in_place, statistics}
+};
 
 pub type VecLandscape = Vec<(Vec<u8>, f64)>;
 
+/// Largest number of loci for which the 2^L × 2^L transfer matrices are built
+const MAX_TRANSFER_MATRIX_LOCI: usize = 12;
+/// Largest number of landscapes drawn by `with_exactly_k_peaks`
+const MAX_K_PEAK_ATTEMPTS: usize = 10_000;
+/// Smallest absolute mean fitness by which a perturbed landscape is rescaled
+const MIN_RESCALED_MEAN: f64 = 1e-9;
+
 #[derive(Eq, PartialEq, Copy, Clone)]
 pub enum FitnessType {
     Multiplicative,
     Additive
 }
 
+/// Reference fitness to which a landscape is normalized
+#[derive(Eq, PartialEq, Copy, Clone, Debug, Default)]
+pub enum NormalizationMode {
+    /// Mean fitness of the individuals in the population
+    #[default]
+    PopulationMean,
+    /// Mean fitness over all genotypes in the landscape
+    Mean,
+    /// Fitness of the wildtype (all-zeros) genotype
+    Wildtype,
+    /// Largest fitness in the landscape
+    Max
+}
+
+#[derive(Clone)]
 pub struct FitnessLandscape<const L: usize> {
     pub landscape: HashMap<Genotype<L>,f64>,
     tp: FitnessType
@@ -32,12 +63,159 @@ impl<const L: usize> FitnessLandscape<L> {
         }
     }
 
+    /// Creates a landscape with only the genotypes present in the population, e.g., to study the
+    /// inference of landscape properties from partial observations
+    pub fn from_population_sample<const S: usize>(
+        population: &FixedSizePopulation<L>,
+        landscape:  &ResourceBasedFitnessLandscape<L, S>,
+        resources:  &Vector<S>
+    ) -> Self {
+        let full_landscape = landscape.get_full_fitness_landscape(population, resources);
+        let mut sample = Self::new(full_landscape.tp);
+        for g in population.keys() {
+            if let Some(&f) = full_landscape.get(g) {
+                sample.add_genotype(*g, f);
+            }
+        }
+        sample
+    }
+
+    /// Adds the genotypes missing from the landscape with the fitness given by the model for the
+    /// population from which the landscape was sampled
+    pub fn complete_from_model<const S: usize>(
+        &mut self,
+        population: &FixedSizePopulation<L>,
+        landscape:  &ResourceBasedFitnessLandscape<L, S>,
+        resources:  &Vector<S>
+    ) {
+        let full_landscape = landscape.get_full_fitness_landscape(population, resources);
+        for (g, f) in full_landscape.landscape {
+            self.landscape.entry(g).or_insert(f);
+        }
+    }
+
+    /// Returns a House of Cards landscape, with independent standard normal additive fitnesses,
+    /// with exactly `k` local maxima, found by rejection sampling. Since maxima cannot be
+    /// neighbors, `k` must be between 1 and 2^(L-1).
+    ///
+    /// Returns None if none of the first MAX_K_PEAK_ATTEMPTS landscapes has `k` maxima. Values of
+    /// `k` far from the expected 2^L/(L+1) maxima, e.g., a single maximum for L = 8, are so
+    /// rarely sampled that always returning a landscape could take practically forever;
+    /// `k_peak_approx` always returns a landscape with close to `k` maxima instead.
+    pub fn with_exactly_k_peaks(k: usize, rng: &mut impl Rng) -> Option<Self> {
+        assert!(k >= 1 && k <= (1 << L.max(1)) / 2, "a landscape with {} loci cannot have {} maxima", L, k);
+        let normal = Normal::new(0., 1.).unwrap();
+        for _ in 0..MAX_K_PEAK_ATTEMPTS {
+            let mut landscape = Self::new(FitnessType::Additive);
+            for seq in possible_sequences::<L>() {
+                landscape.add_genotype(Genotype::from_sequence(&seq), normal.sample(rng));
+            }
+            if landscape.n_maxima_bound(k) == k {
+                return Some(landscape)
+            }
+        }
+        None
+    }
+
+    /// Returns a Rough Mount Fuji landscape, the sum of random normal additive effects and House
+    /// of Cards noise, whose number of maxima is close to `k`. The ratio of the noise to the
+    /// additive effects starts at `ruggedness` and is tuned between draws, using that the number
+    /// of maxima grows with it, until a landscape with `k` maxima is found; after 100 draws the
+    /// closest one is returned.
+    pub fn k_peak_approx(k: usize, ruggedness: f64, rng: &mut impl Rng) -> Self {
+        let normal = Normal::new(0., 1.).unwrap();
+        let mut noise = ruggedness.max(f64::MIN_POSITIVE);
+        let mut best: Option<(usize, Self)> = None;
+        for _ in 0..100 {
+            let effects: Vec<f64> = (0..L).map(|_| normal.sample(rng)).collect();
+            let mut landscape = Self::new(FitnessType::Additive);
+            for seq in possible_sequences::<L>() {
+                let additive: f64 = seq.iter().zip(&effects).map(|(&a, e)| a as f64 * e).sum();
+                landscape.add_genotype(Genotype::from_sequence(&seq), additive + noise * normal.sample(rng));
+            }
+
+            let n = landscape.maxima().len();
+            if n == k {
+                return landscape
+            }
+            if best.as_ref().map_or(true, |(distance, _)| n.abs_diff(k) < *distance) {
+                best = Some((n.abs_diff(k), landscape));
+            }
+            noise *= (k as f64 / n as f64).sqrt();
+        }
+        best.unwrap().1
+    }
+
+    /// Returns the number of local maxima if it is at most `limit`, and `limit + 1` otherwise,
+    /// stopping as soon as the limit is exceeded
+    fn n_maxima_bound(&self, limit: usize) -> usize {
+        let mut n = 0;
+        for (g, &f) in &self.landscape {
+            if (0..L).all(|i| self.get(&g.cmutate(i)).map_or(true, |&fi| f > fi)) {
+                n += 1;
+                if n > limit { break }
+            }
+        }
+        n
+    }
+
     /// Adds a genotype fitness pair
     #[inline]
     pub fn add_genotype(&mut self, g: Genotype<L>, f: f64) {
         self.landscape.insert(g, f);
     }
 
+    /// Returns the landscape of the alleles at the given loci, averaging the fitness over the
+    /// alleles at the remaining loci
+    pub fn sublandscape<const K: usize>(&self, loci: [usize; K]) -> FitnessLandscape<K> {
+        let mut sums = HashMap::<Genotype<K>, (f64, usize)>::new();
+        for seq in possible_sequences::<L>() {
+            if let Some(&f) = self.get(&Genotype::from_sequence(&seq)) {
+                let entry = sums.entry(Genotype::from_sequence(&loci.map(|i| seq[i]))).or_insert((0., 0));
+                entry.0 += f;
+                entry.1 += 1;
+            }
+        }
+
+        let mut sublandscape = FitnessLandscape::<K>::new(self.tp);
+        sublandscape.extend(sums.into_iter().map(|(g, (sum, n))| (g, sum / n as f64)));
+        sublandscape
+    }
+
+    /// Returns the landscape of the alleles at the given loci with the alleles at the remaining
+    /// loci fixed to those in `background`
+    pub fn sublandscape_fixed<const K: usize>(&self, loci: [usize; K], background: &Genotype<L>) -> FitnessLandscape<K> {
+        let mut sublandscape = FitnessLandscape::<K>::new(self.tp);
+        for seq in possible_sequences::<K>() {
+            let g = loci.iter().zip(seq.iter()).fold(*background, |mut g, (&i, &allele)| {
+                if g[i] != allele { g.mutate(i); }
+                g
+            });
+            if let Some(&f) = self.get(&g) {
+                sublandscape.add_genotype(Genotype::from_sequence(&seq), f);
+            }
+        }
+        sublandscape
+    }
+
+    /// Removes a genotype from the landscape, returning its fitness if it was present
+    #[inline]
+    pub fn remove_genotype(&mut self, g: &Genotype<L>) -> Option<f64> {
+        self.landscape.remove(g)
+    }
+
+    /// Adds several genotype fitness pairs, overwriting the fitness of genotypes already present
+    pub fn extend<I: IntoIterator<Item = (Genotype<L>, f64)>>(&mut self, iter: I) {
+        self.landscape.extend(iter);
+    }
+
+    /// Returns the sub-landscape formed by the genotypes present in the population
+    pub fn restrict_to_population(&self, pop: &FixedSizePopulation<L>) -> Self {
+        let mut restricted = Self::new(self.tp);
+        restricted.extend(pop.keys().filter_map(|g| self.get(g).map(|&f| (*g, f))));
+        restricted
+    }
+
     /// Returns the fitness of genotype g using fitness type tp
     #[inline]
     pub fn get_fitness(&self, g: &Genotype<L>, tp: FitnessType) -> Option<f64> {
@@ -68,6 +246,94 @@ impl<const L: usize> FitnessLandscape<L> {
         }).collect();
     }
 
+    /// Normalizes the landscape so that the wildtype (all-zeros) genotype has fitness 1
+    pub fn normalize_to_wildtype(&mut self) {
+        let norm = self.get(&Genotype::new()).copied().unwrap_or(1.0);
+        self.normalize(norm);
+    }
+
+    /// Normalizes the landscape so that the fittest genotype has fitness 1
+    pub fn normalize_to_max(&mut self) {
+        if let Some((_, &norm)) = self.max() {
+            self.normalize(norm);
+        }
+    }
+
+    /// Normalizes the landscape so that the mean fitness over all genotypes is 1
+    pub fn normalize_to_mean(&mut self) {
+        let (mean, _) = self.mean_var();
+        self.normalize(mean);
+    }
+
+    /// Returns the genotypes in the landscape in a deterministic order, so that runs with a seeded
+    /// random number generator are reproducible
+    fn sorted_genotypes(&self) -> Vec<Genotype<L>> {
+        let mut genotypes: Vec<Genotype<L>> = self.landscape.keys().copied().collect();
+        genotypes.sort_unstable();
+        genotypes
+    }
+
+    /// Returns a hash of the fitness of all genotypes, which identifies the landscape, e.g., to
+    /// reuse the statistics computed from it
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for g in self.sorted_genotypes() {
+            (g, self.landscape[&g].to_bits()).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Adds independent gaussian noise with standard deviation `noise_sd` to the fitness of each
+    /// genotype, rescaling the landscape afterwards to keep the mean fitness (or shifting it, if
+    /// the mean is close to zero)
+    pub fn perturb(&mut self, noise_sd: f64, rng: &mut impl Rng) {
+        if noise_sd == 0. { return }
+        let (mean, _) = self.mean_var();
+        let noise = Normal::new(0., noise_sd).unwrap();
+        for g in self.sorted_genotypes() {
+            *self.landscape.get_mut(&g).unwrap() += noise.sample(rng);
+        }
+        let (perturbed_mean, _) = self.mean_var();
+        if mean.abs() > MIN_RESCALED_MEAN && perturbed_mean.abs() > MIN_RESCALED_MEAN {
+            self.normalize(perturbed_mean / mean);
+        } else {
+            // Rescaling around a mean close to zero would blow up the fitness, so shift it instead
+            self.landscape.values_mut().for_each(|f| *f -= perturbed_mean - mean);
+        }
+    }
+
+    /// Multiplies the fitness of each genotype by independent log-normal noise with unit mean,
+    /// `exp(N(0, noise_cv²) - noise_cv²/2)`
+    pub fn perturb_multiplicative(&mut self, noise_cv: f64, rng: &mut impl Rng) {
+        if noise_cv == 0. { return }
+        let noise = Normal::new(-noise_cv * noise_cv / 2., noise_cv).unwrap();
+        for g in self.sorted_genotypes() {
+            *self.landscape.get_mut(&g).unwrap() *= noise.sample(rng).exp();
+        }
+    }
+
+    /// Returns a landscape with the same genotypes and fitness values, randomly reassigned to the
+    /// genotypes. The permutation keeps the distribution of fitnesses but destroys any genotypic
+    /// structure, providing a null model for statistical tests.
+    pub fn randomize_fitnesses(&self, rng: &mut impl Rng) -> Self {
+        let genotypes = self.sorted_genotypes();
+        let mut fitnesses: Vec<f64> = genotypes.iter().map(|g| self.landscape[g]).collect();
+        fitnesses.shuffle(rng);
+        let mut randomized = Self::new(self.tp);
+        randomized.extend(genotypes.into_iter().zip(fitnesses));
+        randomized
+    }
+
+    /// Returns the root mean square difference between the fitness of the genotypes in both
+    /// landscapes
+    pub fn rms_difference(&self, other: &FitnessLandscape<L>) -> f64 {
+        let sum: f64 = self.landscape.iter().map(|(g, f)| {
+            let d = f - other.get(g).unwrap_or(&f64::NAN);
+            d * d
+        }).sum();
+        (sum / self.landscape.len() as f64).sqrt()
+    }
+
     /// Returns the gamma statistics of epistasis
     pub fn gamma(&self) -> f64 {
         let (mut cov, mut var) = (0., 0.);
@@ -91,6 +357,152 @@ impl<const L: usize> FitnessLandscape<L> {
         cov / var
     }
 
+    /// Returns the matrix of average pairwise epistasis, where the entry (i, j) is the change in
+    /// the fitness effect of mutation i caused by mutation j, averaged over all the backgrounds
+    /// carrying the wildtype allele at both loci. For multiplicative fitness, the change is
+    /// measured in log scale. The diagonal is zero.
+    pub fn pairwise_epistasis_matrix(&self, tp: FitnessType) -> [[f64; L]; L] {
+        let mut matrix = [[0_f64; L]; L];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                if i == j { continue }
+                let (mut sum, mut n) = (0., 0);
+                for g in self.landscape.keys().filter(|g| g[i] == 0 && g[j] == 0) {
+                    let si  = match self.get_fitness_effect(g, i, tp)              { Some(s) => s, None => continue };
+                    let sij = match self.get_fitness_effect(&g.cmutate(j), i, tp) { Some(s) => s, None => continue };
+                    sum += match tp {
+                        FitnessType::Additive       => sij - si,
+                        FitnessType::Multiplicative => (sij / si).ln()
+                    };
+                    n += 1;
+                }
+                *entry = if n > 0 { sum / n as f64 } else { f64::NAN };
+            }
+        }
+        matrix
+    }
+
+    /// Returns the additive epistasis `f_ij - f_i - f_j + f_wt` between loci i and j, where the
+    /// four genotypes carry the alleles of `background` at all other loci and the wildtype (wt) or
+    /// derived (i, j) alleles at loci i and j. Returns None if any of the genotypes is missing.
+    pub fn double_mutant_epistasis(&self, i: usize, j: usize, background: &Genotype<L>) -> Option<f64> {
+        let mut wt = *background;
+        if wt[i] == 1 { wt.mutate(i); }
+        if wt[j] == 1 { wt.mutate(j); }
+        let f = |g: Genotype<L>| self.get_fitness(&g, FitnessType::Additive);
+        Some(f(wt.cmutate(i).cmutate(j))? - f(wt.cmutate(i))? - f(wt.cmutate(j))? + f(wt)?)
+    }
+
+    /// Returns the matrix of double mutant epistasis between all pairs of loci on a background,
+    /// with NaN for missing genotypes and zeros in the diagonal
+    pub fn epistasis_matrix_on_background(&self, background: &Genotype<L>) -> [[f64; L]; L] {
+        let mut matrix = [[0_f64; L]; L];
+        for (i, row) in matrix.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate().filter(|&(j, _)| j != i) {
+                *entry = self.double_mutant_epistasis(i, j, background).unwrap_or(f64::NAN);
+            }
+        }
+        matrix
+    }
+
+    /// Returns the double mutant epistasis between all pairs of loci averaged over the backgrounds
+    /// in the landscape
+    pub fn mean_epistasis_matrix(&self) -> [[f64; L]; L] {
+        let mut sum = [[0_f64; L]; L];
+        let mut n = [[0_usize; L]; L];
+        for g in self.landscape.keys() {
+            let matrix = self.epistasis_matrix_on_background(g);
+            for i in 0..L {
+                for j in 0..L {
+                    if !matrix[i][j].is_nan() {
+                        sum[i][j] += matrix[i][j];
+                        n[i][j] += 1;
+                    }
+                }
+            }
+        }
+        for i in 0..L {
+            for j in 0..L {
+                sum[i][j] = if n[i][j] > 0 { sum[i][j] / n[i][j] as f64 } else { f64::NAN };
+            }
+        }
+        sum
+    }
+
+    /// Returns the Frobenius norm of the mean double mutant epistasis matrix
+    pub fn epistasis_strength(&self) -> f64 {
+        self.mean_epistasis_matrix().iter().flatten().map(|e| e * e).sum::<f64>().sqrt()
+    }
+
+    /// Classifies the effect of every mutation (locus and background genotype) by comparing it with
+    /// the effects of the same mutation on the backgrounds one mutation away. It has sign
+    /// epistasis if any of these effects has the opposite sign, magnitude epistasis if any of
+    /// them differs otherwise, and no epistasis if all are equal up to a relative precision of
+    /// 1e-9. Returns the counts of (no, magnitude, sign) epistasis, which add up to `L 2^L` for a
+    /// complete landscape.
+    pub fn classify_epistasis(&self) -> (usize, usize, usize) {
+        let (min, max) = match (self.min(), self.max()) {
+            (Some((_, &min)), Some((_, &max))) => (min, max),
+            _ => return (0, 0, 0)
+        };
+        let precision = 1e-9 * (max - min).abs().max(f64::MIN_POSITIVE);
+        let effect = |g: &Genotype<L>, i: usize| self.get_fitness_effect(g, i, FitnessType::Additive);
+
+        let (mut none, mut magnitude, mut sign) = (0, 0, 0);
+        for g in self.landscape.keys() {
+            for i in 0..L {
+                let e = match effect(g, i) {
+                    Some(e) => e,
+                    None    => continue
+                };
+                let others: Vec<f64> = (0..L).filter(|&j| j != i).filter_map(|j| effect(&g.cmutate(j), i)).collect();
+                if others.iter().any(|&ej| (e > precision && ej < -precision) || (e < -precision && ej > precision)) {
+                    sign += 1;
+                } else if others.iter().any(|&ej| (ej - e).abs() > precision) {
+                    magnitude += 1;
+                } else {
+                    none += 1;
+                }
+            }
+        }
+        (none, magnitude, sign)
+    }
+
+    /// Returns the number of squares of the hypercube (pairs of loci on a background) where both
+    /// mutations change the sign of the effect of the other one
+    pub fn reciprocal_sign_epistasis_count(&self) -> usize {
+        let f = |g: Genotype<L>| self.get_fitness(&g, FitnessType::Additive);
+        let mut count = 0;
+        for g in self.landscape.keys() {
+            for i in (0..L).filter(|&i| g[i] == 0) {
+                for j in (i + 1..L).filter(|&j| g[j] == 0) {
+                    let (gi, gj, gij) = (g.cmutate(i), g.cmutate(j), g.cmutate(i).cmutate(j));
+                    if let (Some(f0), Some(fi), Some(fj), Some(fij)) = (f(*g), f(gi), f(gj), f(gij)) {
+                        if (fi - f0) * (fij - fj) < 0. && (fj - f0) * (fij - fi) < 0. {
+                            count += 1;
+                        }
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Returns the pairs of loci (i < j) whose additive epistasis is larger than `threshold` in
+    /// absolute value, together with the epistasis coefficient
+    pub fn epistasis_network(&self, threshold: f64) -> Vec<(usize, usize, f64)> {
+        let matrix = self.pairwise_epistasis_matrix(FitnessType::Additive);
+        let mut network = Vec::new();
+        for (i, row) in matrix.iter().enumerate() {
+            for (j, &e) in row.iter().enumerate().skip(i+1) {
+                if e.abs() > threshold {
+                    network.push((i, j, e));
+                }
+            }
+        }
+        network
+    }
+
     /// Returns the maximum fitness in the landscape
     pub fn max(&self) -> Option<(&Genotype<L>, &f64)> {
         self.landscape.iter().reduce(|(g_a, f_a), (g_b, f_b)| {
@@ -113,10 +525,8 @@ impl<const L: usize> FitnessLandscape<L> {
     }
     // Returns a tuple with the mean and the variance of the fitness landscape
     pub fn mean_var(&self) -> (f64, f64) {
-        let size = self.landscape.len() as f64;
-        let mean = self.landscape.iter().fold(0_f64, |acc, (_, &f)| acc + f)   / size;
-        let var  = self.landscape.iter().fold(0_f64, |acc, (_, &f)| acc + f*f) / size - mean * mean;
-        (mean, var)
+        let fitness: Vec<f64> = self.landscape.values().copied().collect();
+        (statistics::mean(&fitness), statistics::variance(&fitness))
     }
 
     // Returns a vector with the additive fitness effects
@@ -150,18 +560,23 @@ impl<const L: usize> FitnessLandscape<L> {
         vec.iter().map(|(g, _)| *g).collect()
     }
 
-    // Requires all fitnesses to be distinct
+    /// Returns the Spearman rank correlation between the fitnesses of the genotypes in both
+    /// landscapes, with tied fitnesses sharing their average rank
     pub fn spearman_rho(&self, other: &FitnessLandscape<L>) -> f64 {
-        let r1 = self.rank_order();
-        let r2 = other.rank_order();
-        let n = r1.len();
+        let (f1, f2): (Vec<f64>, Vec<f64>) = self.landscape.iter().filter_map(|(g, &f)| {
+            other.get(g).map(|&fo| (f, fo))
+        }).unzip();
+        statistics::spearman(&f1, &f2)
+    }
 
-        let mut d2 = 0;
-        for i in 0..n {
-            let j = r2.iter().position(|&g| g == r1[i]).unwrap();
-            d2 += (i - j) * (i - j);
-        }
-        1. - 6. / ((n * (n*n - 1)) as f64) * (d2 as f64)
+    /// Returns Kendall's τ-b rank correlation between the fitnesses of the genotypes in both
+    /// landscapes. Pairs of genotypes with tied fitness are neither concordant nor discordant, so
+    /// it is better suited than `spearman_rho` for small landscapes with many ties.
+    pub fn kendall_tau(&self, other: &FitnessLandscape<L>) -> f64 {
+        let (f1, f2): (Vec<f64>, Vec<f64>) = self.landscape.iter().filter_map(|(g, &f)| {
+            other.get(g).map(|&fo| (f, fo))
+        }).unzip();
+        statistics::kendall_tau(&f1, &f2)
     }
 
     /// Returns a vector listing all local maxima genotypes in the landscape
@@ -190,50 +605,1801 @@ impl<const L: usize> FitnessLandscape<L> {
             }) { Some(g) } else { None }
       }).collect()
     }
-    pub fn strains_selected(&self) -> Vec<Genotype<L>> {
-        self.landscape.iter().filter_map(|(&g, &f)| {
-            if f > 1. { Some(g) } else { None }
-        }).collect()
+    /// Returns the fittest neighbor of the genotype if it is fitter than the genotype itself
+    fn fittest_neighbor(&self, g: &Genotype<L>) -> Option<Genotype<L>> {
+        let mut best = (*g, *self.get(g)?);
+        for i in 0..L {
+            let gi = g.cmutate(i);
+            if let Some(&fi) = self.get(&gi) {
+                if fi > best.1 { best = (gi, fi); }
+            }
+        }
+        if best.0 != *g { Some(best.0) } else { None }
     }
 
-    pub fn to_vec(&self) -> VecLandscape {
-        let mut v = Vec::with_capacity(self.landscape.len());
-        for &g in self.landscape.keys() {
-            let f = self.get_fitness(&g, FitnessType::Additive).unwrap_or(f64::NAN);
-            let g = g.to_vec();
-            v.push((g, f));
+    /// Returns the genotypes visited by a greedy adaptive walk, which always moves to the fittest
+    /// neighbor, from `start` to a local maximum
+    pub fn greedy_walk(&self, start: Genotype<L>) -> Vec<Genotype<L>> {
+        let mut walk = vec![start];
+        while let Some(next) = self.fittest_neighbor(walk.last().unwrap()) {
+            walk.push(next);
         }
-        v
+        walk
     }
-    fn to_vec_raw(&self) -> Vec<(Genotype<L>, f64)> {
-        let mut v = Vec::with_capacity(self.landscape.len());
+
+    /// Returns the number of steps of the greedy adaptive walk from each genotype to a local
+    /// maximum, which is 0 for the local maxima themselves
+    fn walk_lengths(&self) -> HashMap<Genotype<L>, usize> {
+        let mut lengths = HashMap::<Genotype<L>, usize>::with_capacity(self.landscape.len());
         for &g in self.landscape.keys() {
-            let f = self.get(&g).unwrap_or(&f64::NAN);
-            v.push((g, *f));
+            // Follows the walk until reaching a maximum or a genotype with a known walk length
+            let mut walk = vec![g];
+            let mut length = loop {
+                let current = *walk.last().unwrap();
+                if let Some(&length) = lengths.get(&current) { walk.pop(); break length }
+                match self.fittest_neighbor(&current) {
+                    Some(next) => walk.push(next),
+                    None       => { walk.pop(); lengths.insert(current, 0); break 0 }
+                }
+            };
+            for &gi in walk.iter().rev() {
+                length += 1;
+                lengths.insert(gi, length);
+            }
         }
-        v
+        lengths
     }
-    pub fn from_vec(&self, v: VecLandscape) -> Self {
-        let mut landscape = Self::new(FitnessType::Additive);
-        for (g, f) in &v {
-            landscape.add_genotype(Genotype::<L>::from_sequence(g), *f);
+
+    /// Returns the number of genotypes whose greedy adaptive walk reaches a local maximum in
+    /// exactly `i + 1` steps at index `i`. The local maxima, which need no steps, are not counted.
+    pub fn walk_length_distribution(&self) -> Vec<usize> {
+        let lengths = self.walk_lengths();
+        let mut distribution = vec![0; lengths.values().max().copied().unwrap_or(0)];
+        for &length in lengths.values().filter(|&&length| length > 0) {
+            distribution[length - 1] += 1;
+        }
+        distribution
+    }
+
+    /// Returns the mean and the variance of the number of steps of the greedy adaptive walks over
+    /// all starting genotypes, including the local maxima, running the walks once
+    pub fn walk_length_moments(&self) -> (f64, f64) {
+        let lengths = self.walk_lengths();
+        let n = lengths.len() as f64;
+        let mean = lengths.values().sum::<usize>() as f64 / n;
+        let var = lengths.values().map(|&length| (length as f64 - mean).powi(2)).sum::<f64>() / n;
+        (mean, var)
+    }
+
+    /// Returns the mean number of steps of the greedy adaptive walks over all starting genotypes
+    pub fn mean_walk_length(&self) -> f64 {
+        self.walk_length_moments().0
+    }
+
+    /// Returns the variance of the number of steps of the greedy adaptive walks over all starting
+    /// genotypes
+    pub fn walk_length_variance(&self) -> f64 {
+        self.walk_length_moments().1
+    }
+
+    /// Runs `n_trials` adaptive walks following Gillespie's model, where each beneficial neighbor
+    /// is chosen with probability proportional to its fitness advantage, from random starting
+    /// genotypes. Returns the number of walks with `i + 1` steps at index `i`, as
+    /// `walk_length_distribution`, so that walks starting at a local maximum are not counted.
+    pub fn gillespie_walk_length_distribution(&self, rng: &mut impl Rng, n_trials: usize) -> Vec<usize> {
+        let genotypes = self.sorted_genotypes();
+
+        let mut distribution = Vec::new();
+        for _ in 0..n_trials {
+            let mut g = match genotypes.choose(rng) {
+                Some(&g) => g,
+                None      => break
+            };
+            let mut length = 0;
+            loop {
+                let f = self.landscape[&g];
+                let (neighbors, advantages): (Vec<Genotype<L>>, Vec<f64>) = (0..L).filter_map(|i| {
+                    let gi = g.cmutate(i);
+                    match self.get(&gi) {
+                        Some(&fi) if fi > f => Some((gi, fi - f)),
+                        _ => None
+                    }
+                }).unzip();
+                if neighbors.is_empty() { break }
+
+                g = neighbors[WeightedIndex::new(&advantages).unwrap().sample(rng)];
+                length += 1;
+            }
+            if length == 0 { continue }
+            if distribution.len() < length { distribution.resize(length, 0); }
+            distribution[length - 1] += 1;
+        }
+        distribution
+    }
+
+    /// Returns the fitter single-step neighbors of each genotype, i.e., the directed graph of
+    /// beneficial mutations
+    pub fn fitness_graph(&self) -> HashMap<Genotype<L>, Vec<Genotype<L>>> {
+        self.landscape.iter().map(|(&g, &f)| {
+            let fitter = (0..L).map(|i| g.cmutate(i))
+                               .filter(|gi| matches!(self.get(gi), Some(&fi) if fi > f))
+                               .collect();
+            (g, fitter)
+        }).collect()
+    }
+
+    /// Returns the edges of the fitness graph as (source, target, fitness difference) triples
+    pub fn fitness_graph_edges(&self) -> Vec<(Genotype<L>, Genotype<L>, f64)> {
+        let mut edges = Vec::new();
+        for g in self.sorted_genotypes() {
+            let f = self.landscape[&g];
+            for i in 0..L {
+                let gi = g.cmutate(i);
+                if let Some(&fi) = self.get(&gi) {
+                    if fi > f { edges.push((g, gi, fi - f)); }
+                }
+            }
+        }
+        edges
+    }
+
+    /// Returns the connected components of the undirected graph underlying the fitness graph,
+    /// computed with Tarjan's algorithm. Although named after the strongly connected components
+    /// of the directed fitness graph, in the undirected graph they are its connected components,
+    /// which is also available as `connected_components`.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<Genotype<L>>> {
+        let genotypes = self.sorted_genotypes();
+        let index_of: HashMap<Genotype<L>, usize> = genotypes.iter().enumerate().map(|(i, &g)| (g, i)).collect();
+
+        // Neighbors connected by an edge of the fitness graph in either direction
+        let adjacency: Vec<Vec<usize>> = genotypes.iter().map(|g| {
+            let f = self.landscape[g];
+            (0..L).filter_map(|i| {
+                let gi = g.cmutate(i);
+                match self.get(&gi) {
+                    Some(&fi) if fi != f => Some(index_of[&gi]),
+                    _ => None
+                }
+            }).collect()
+        }).collect();
+
+        let n = genotypes.len();
+        let mut index   = vec![usize::MAX; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..n {
+            if index[root] != usize::MAX { continue }
+
+            // Iterative depth-first search keeping the position in the adjacency list of each node
+            let mut call_stack = vec![(root, 0)];
+            while let Some(&mut (v, ref mut next_neighbor)) = call_stack.last_mut() {
+                if *next_neighbor == 0 && index[v] == usize::MAX {
+                    index[v] = next_index;
+                    lowlink[v] = next_index;
+                    next_index += 1;
+                    stack.push(v);
+                    on_stack[v] = true;
+                }
+
+                if let Some(&w) = adjacency[v].get(*next_neighbor) {
+                    *next_neighbor += 1;
+                    if index[w] == usize::MAX {
+                        call_stack.push((w, 0));
+                    } else if on_stack[w] {
+                        lowlink[v] = lowlink[v].min(index[w]);
+                    }
+                    continue
+                }
+
+                call_stack.pop();
+                if let Some(&(parent, _)) = call_stack.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[v]);
+                }
+                if lowlink[v] == index[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(genotypes[w]);
+                        if w == v { break }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Returns the connected components of the undirected graph underlying the fitness graph, as
+    /// `strongly_connected_components`
+    pub fn connected_components(&self) -> Vec<Vec<Genotype<L>>> {
+        self.strongly_connected_components()
+    }
+
+    /// Returns the number of mutational steps from `start` to each genotype that can be reached
+    /// through paths of non-decreasing fitness of at most `max_steps` steps
+    fn accessible_distances(&self, start: &Genotype<L>, max_steps: usize) -> HashMap<Genotype<L>, usize> {
+        let mut distances = HashMap::new();
+        if self.get(start).is_none() { return distances }
+
+        let mut queue = VecDeque::from([(*start, 0)]);
+        distances.insert(*start, 0);
+        while let Some((g, d)) = queue.pop_front() {
+            if d == max_steps { continue }
+            let f = self.landscape[&g];
+            for neighbor in g.neighbors() {
+                if distances.contains_key(&neighbor) { continue }
+                if let Some(&fi) = self.get(&neighbor) {
+                    if fi >= f {
+                        distances.insert(neighbor, d + 1);
+                        queue.push_back((neighbor, d + 1));
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    /// Returns the genotypes that can be reached from `start` in at most `steps` mutational steps
+    /// without decreasing fitness, including `start` itself
+    pub fn reachable_from(&self, start: &Genotype<L>, steps: usize) -> HashSet<Genotype<L>> {
+        self.accessible_distances(start, steps).into_keys().collect()
+    }
+
+    /// Returns the fraction of the genotypes in the landscape that are reachable from `start`
+    pub fn reachable_fraction(&self, start: &Genotype<L>, steps: usize) -> f64 {
+        self.reachable_from(start, steps).len() as f64 / self.landscape.len() as f64
+    }
+
+    /// Returns the smallest number of steps of non-decreasing fitness from `start` to the global
+    /// maximum, or `usize::MAX` if the global maximum is not accessible
+    pub fn evolutionary_horizon(&self, start: &Genotype<L>) -> usize {
+        let maximum = match self.max() {
+            Some((&g, _)) => g,
+            None          => return usize::MAX
+        };
+        *self.accessible_distances(start, usize::MAX).get(&maximum).unwrap_or(&usize::MAX)
+    }
+
+    /// Returns the mean fitness of the offspring of the population when each offspring carries,
+    /// with probability `mu`, a mutation at a locus chosen uniformly at random
+    pub fn expected_fitness_after_mutation(&self, population: &FixedSizePopulation<L>, mu: f64) -> f64 {
+        let size = population.size() as f64;
+        population.iter().map(|(g, &n)| {
+            let f = *self.get(g).expect("genotype missing from the fitness landscape");
+            let mutants: Vec<f64> = (0..L).filter_map(|i| self.get(&g.cmutate(i)).copied()).collect();
+            let f_mutant = if mutants.is_empty() { f } else { mutants.iter().sum::<f64>() / mutants.len() as f64 };
+            (n as f64 / size) * (mu * f_mutant + (1. - mu) * f)
+        }).sum()
+    }
+
+    /// Returns the fraction of one-step mutants of the genotype that are fitter than it
+    pub fn evolvability(&self, g: &Genotype<L>) -> f64 {
+        let f = match self.get(g) {
+            Some(&f) => f,
+            None     => return f64::NAN
+        };
+        let (fitter, total) = (0..L).filter_map(|i| self.get(&g.cmutate(i))).fold((0, 0), |(fitter, total), &fi| {
+            (fitter + (fi > f) as usize, total + 1)
+        });
+        fitter as f64 / total as f64
+    }
+
+    /// Returns the edges of the fitness graph weighted by the beneficial mutation flux,
+    /// `mu (f_target - f_source) / L`, as (source, target, weight) triples
+    pub fn mutation_network_weight(&self, mu: f64) -> Vec<(Genotype<L>, Genotype<L>, f64)> {
+        self.fitness_graph_edges().into_iter().map(|(source, target, df)| {
+            (source, target, mu * df / L as f64)
+        }).collect()
+    }
+
+    /// Returns the total beneficial mutation flux from the less fit neighbors into the genotype
+    pub fn incoming_flux(&self, g: &Genotype<L>, mu: f64) -> f64 {
+        self.mutation_flux(g, mu, |f, fi| f - fi)
+    }
+
+    /// Returns the total beneficial mutation flux from the genotype to its fitter neighbors, which
+    /// is 0 at local maxima
+    pub fn outgoing_flux(&self, g: &Genotype<L>, mu: f64) -> f64 {
+        self.mutation_flux(g, mu, |f, fi| fi - f)
+    }
+
+    /// Returns the sum over the neighbors of the genotype of `mu max(0, gain(f, f_i)) / L`
+    fn mutation_flux(&self, g: &Genotype<L>, mu: f64, gain: impl Fn(f64, f64) -> f64) -> f64 {
+        let f = match self.get(g) {
+            Some(&f) => f,
+            None     => return f64::NAN
+        };
+        (0..L).filter_map(|i| self.get(&g.cmutate(i))).map(|&fi| mu * gain(f, fi).max(0.) / L as f64).sum()
+    }
+
+    /// Returns the evolvability averaged over all genotypes in the landscape
+    pub fn mean_evolvability(&self) -> f64 {
+        self.landscape.keys().map(|g| self.evolvability(g)).sum::<f64>() / self.landscape.len() as f64
+    }
+
+    /// Returns the fitness flux of the population, i.e., the sum over genotypes of their frequency
+    /// times their evolvability times the mean fitness gain of their beneficial mutants
+    pub fn fitness_flux(&self, population: &FixedSizePopulation<L>) -> f64 {
+        let size = population.size() as f64;
+        population.iter().filter_map(|(g, &n)| {
+            let f = *self.get(g)?;
+            let gains: Vec<f64> = (0..L).filter_map(|i| self.get(&g.cmutate(i)))
+                                        .filter(|&&fi| fi > f)
+                                        .map(|&fi| fi - f)
+                                        .collect();
+            if gains.is_empty() { return Some(0.) }
+            let mean_gain = gains.iter().sum::<f64>() / gains.len() as f64;
+            Some((n as f64 / size) * self.evolvability(g) * mean_gain)
+        }).sum()
+    }
+
+    /// Returns the selection coefficients of the one-step mutants of each genotype in the
+    /// population, together with the frequency of the genotype. The selection coefficient is
+    /// `f(g_mut) / f(g) - 1` for multiplicative fitness and `f(g_mut) - f(g)` for additive fitness.
+    fn weighted_selection_coefficients(&self, pop: &FixedSizePopulation<L>, tp: FitnessType) -> Vec<(f64, f64)> {
+        let mut distribution: Vec<(Genotype<L>, f64)> = pop.distribution().into_iter().collect();
+        distribution.sort_unstable_by_key(|&(g, _)| g);
+        distribution.iter().flat_map(|&(g, freq)| {
+            (0..L).filter_map(move |i| self.get_fitness_effect(&g, i, tp)).map(move |effect| {
+                let s = match tp {
+                    FitnessType::Additive       => effect,
+                    FitnessType::Multiplicative => effect - 1.
+                };
+                (s, freq)
+            })
+        }).collect()
+    }
+
+    /// Returns the selection coefficients of all one-step mutants of the genotypes present in the
+    /// population, ordered by genotype. Mutants missing from the landscape are skipped.
+    pub fn selection_coefficient_distribution(&self, pop: &FixedSizePopulation<L>, tp: FitnessType) -> Vec<f64> {
+        self.weighted_selection_coefficients(pop, tp).into_iter().map(|(s, _)| s).collect()
+    }
+
+    /// Returns the mean selection coefficient of a random mutation in a random individual of the
+    /// population
+    pub fn mean_selection_coefficient(&self, pop: &FixedSizePopulation<L>, tp: FitnessType) -> f64 {
+        let coefficients = self.weighted_selection_coefficients(pop, tp);
+        let total: f64 = coefficients.iter().map(|&(_, w)| w).sum();
+        coefficients.iter().map(|&(s, w)| s * w).sum::<f64>() / total
+    }
+
+    /// Returns the probability that a random mutation in a random individual of the population is
+    /// beneficial
+    pub fn fraction_beneficial(&self, pop: &FixedSizePopulation<L>, tp: FitnessType) -> f64 {
+        let coefficients = self.weighted_selection_coefficients(pop, tp);
+        let total: f64 = coefficients.iter().map(|&(_, w)| w).sum();
+        coefficients.iter().filter(|&&(s, _)| s > 0.).map(|&(_, w)| w).sum::<f64>() / total
+    }
+
+    /// Returns the mean of the fitness raised to `power` over the individuals in the population.
+    /// Genotypes missing from the landscape are ignored.
+    fn fitness_moment(&self, population: &FixedSizePopulation<L>, power: i32) -> f64 {
+        population.distribution().iter().filter_map(|(g, &freq)| {
+            self.get(g).map(|f| freq * f.powi(power))
+        }).sum()
+    }
+
+    /// Fits `f(g) = α + Σ_i β_i g_i` to the additive fitness of the genotypes in the landscape by
+    /// ordinary least squares and returns `[α, β_0, ..., β_{L-1}]`, or None if some locus is
+    /// monomorphic among the genotypes
+    fn additive_fit(&self) -> Option<Vec<f64>> {
+        let mut xtx = vec![vec![0_f64; L + 1]; L + 1];
+        let mut xty = vec![vec![0_f64]; L + 1];
+        for g in self.landscape.keys() {
+            let f = self.get_fitness(g, FitnessType::Additive).unwrap();
+            let x: Vec<f64> = std::iter::once(1.).chain(g.iter().map(|&a| a as f64)).collect();
+            for (i, &xi) in x.iter().enumerate() {
+                for (j, &xj) in x.iter().enumerate() {
+                    xtx[i][j] += xi * xj;
+                }
+                xty[i][0] += xi * f;
+            }
         }
+        Some(solve_linear_system(xtx, xty)?.into_iter().map(|row| row[0]).collect())
+    }
+
+    /// Returns the additive landscape that best fits the additive fitness of the genotypes in this
+    /// landscape by least squares. The residuals are the epistatic component of the fitness. The
+    /// fitnesses are NaN if some locus is monomorphic among the genotypes.
+    pub fn locally_additive_component(&self) -> FitnessLandscape<L> {
+        let coefficients = self.additive_fit().unwrap_or_else(|| vec![f64::NAN; L + 1]);
+        let mut additive = FitnessLandscape::new(FitnessType::Additive);
+        additive.extend(self.landscape.keys().map(|&g| {
+            (g, coefficients[0] + g.iter().zip(&coefficients[1..]).map(|(&a, b)| a as f64 * b).sum::<f64>())
+        }));
+        additive
+    }
+
+    /// Creates an additive landscape with the genotypes of `epistasis` and fitness
+    /// `Σ_i a_i g_i + scale ε(g)`, where ε is the additive fitness in `epistasis`
+    pub fn from_additive_and_epistatic(additive_effects: &[f64; L], epistasis: &FitnessLandscape<L>, scale: f64) -> FitnessLandscape<L> {
+        let mut landscape = FitnessLandscape::new(FitnessType::Additive);
+        landscape.extend(epistasis.landscape.keys().map(|&g| {
+            let additive: f64 = g.iter().zip(additive_effects).map(|(&a, e)| a as f64 * e).sum();
+            (g, additive + scale * epistasis.get_fitness(&g, FitnessType::Additive).unwrap())
+        }));
         landscape
     }
 
-    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
-        let file = File::create(filename).unwrap();
-        write!(BufWriter::new(file), "{}", self)?;
-        Ok(())
+    /// Splits the additive fitness into the effects of the best additive fit and the residual
+    /// epistatic landscape, which includes the intercept of the fit, so that
+    /// `from_additive_and_epistatic(&effects, &epistasis, 1.)` recovers the landscape. The effects
+    /// are NaN if some locus is monomorphic among the genotypes.
+    pub fn split_into_additive_and_epistatic(&self) -> ([f64; L], FitnessLandscape<L>) {
+        let coefficients = self.additive_fit().unwrap_or_else(|| vec![f64::NAN; L + 1]);
+        let effects: [f64; L] = coefficients[1..].try_into().unwrap();
+        let mut epistasis = FitnessLandscape::new(FitnessType::Additive);
+        epistasis.extend(self.landscape.keys().map(|&g| {
+            let additive: f64 = g.iter().zip(&effects).map(|(&a, e)| a as f64 * e).sum();
+            (g, self.get_fitness(&g, FitnessType::Additive).unwrap() - additive)
+        }));
+        (effects, epistasis)
     }
-}
 
-impl<const L: usize> fmt::Display for FitnessLandscape<L> {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (&g, &fitness) in &self.landscape {
-            write!(f, "{}\t{}\n", g, fitness)?
+    /// Returns the fraction of the variance of the additive fitness explained by the best additive
+    /// fit
+    pub fn additive_r_squared(&self) -> f64 {
+        let additive = self.locally_additive_component();
+        let (fitness, fitted): (Vec<f64>, Vec<f64>) = additive.landscape.iter().map(|(g, &fitted)| {
+            (self.get_fitness(g, FitnessType::Additive).unwrap(), fitted)
+        }).unzip();
+        let residuals: Vec<f64> = fitness.iter().zip(&fitted).map(|(f, fi)| f - fi).collect();
+        let ss_res: f64 = residuals.iter().map(|r| r * r).sum();
+        1. - ss_res / (statistics::variance(&fitness) * fitness.len() as f64)
+    }
+
+    /// Returns the fraction of the variance of the additive fitness not explained by the best
+    /// additive fit
+    pub fn epistatic_content(&self) -> f64 {
+        1. - self.additive_r_squared()
+    }
+
+    /// Returns how predictable the fitness change of a step of a random walk is from the locus
+    /// that mutates and the direction of the mutation, `I = 1 - H(Δf | locus, direction) / H_max`.
+    /// The conditional entropy is computed exactly for the stationary walk, which crosses every
+    /// mutation with the same probability, and `H_max` is its value when all fitness changes are
+    /// distinct. Fitness changes equal up to a relative precision of 1e-9 are merged, so that
+    /// additive landscapes give I = 1 and House of Cards landscapes give I = 0. Returns NaN if
+    /// no locus has two mutations in the same direction.
+    ///
+    /// This differs from `I = 1 - H(Δf) / log2(number of distinct fitnesses)`: an additive
+    /// landscape with distinct effects has 2L equally likely changes, which gives
+    /// `1 - log2(2L) / L` instead of 1, and a House of Cards landscape has more distinct changes
+    /// than fitnesses, which gives a negative value instead of 0.
+    pub fn information_content(&self) -> f64 {
+        let (min, max) = match (self.min(), self.max()) {
+            (Some((_, &min)), Some((_, &max))) => (min, max),
+            _ => return f64::NAN
+        };
+        let precision = 1e-9 * (max - min).max(f64::MIN_POSITIVE);
+
+        // Binned fitness changes of the mutations at each locus, from the 0 to the 1 allele
+        let mut changes: Vec<HashMap<i64, usize>> = vec![HashMap::new(); L];
+        for (g, &f) in self.landscape.iter() {
+            for (i, locus_changes) in changes.iter_mut().enumerate() {
+                if g[i] == 1 { continue }
+                if let Some(&fi) = self.get(&g.cmutate(i)) {
+                    *locus_changes.entry(((fi - f) / precision).round() as i64).or_insert(0) += 1;
+                }
+            }
+        }
+
+        // The reverse mutations have the opposite changes and the same entropy
+        let (mut entropy, mut max_entropy) = (0_f64, 0_f64);
+        for locus_changes in &changes {
+            let n: usize = locus_changes.values().sum();
+            if n == 0 { continue }
+            entropy -= locus_changes.values().map(|&k| {
+                let p = k as f64 / n as f64;
+                k as f64 * p.log2()
+            }).sum::<f64>();
+            max_entropy += n as f64 * (n as f64).log2();
+        }
+        1. - entropy / max_entropy
+    }
+
+    /// Returns the Shannon entropy (in nats) of the multiplicative fitnesses normalized to sum to 1,
+    /// which measures how evenly the weight of the landscape is spread across the genotypes. It
+    /// ranges from 0, if a single genotype has a nonzero fitness, to `ln(n)` for a flat landscape
+    /// of n genotypes. Returns NaN if the landscape is empty.
+    pub fn fitness_entropy(&self) -> f64 {
+        if self.landscape.is_empty() { return f64::NAN }
+        let fitnesses: Vec<f64> = self.landscape.keys().map(|g| self.get_fitness(g, FitnessType::Multiplicative).unwrap()).collect();
+        let total: f64 = fitnesses.iter().sum();
+        -fitnesses.iter().filter(|&&f| f > 0.).map(|f| {
+            let p = f / total;
+            p * p.ln()
+        }).sum::<f64>()
+    }
+
+    /// Returns the effective number of genotypes carrying the weight of the landscape, `exp(H)`
+    /// with H the fitness entropy, analogous to Jost's D for the diversity of a population. It is
+    /// 2^L for a flat complete landscape and 1 if a single genotype has a nonzero fitness. For a
+    /// complete House of Cards landscape with log-fitnesses of standard deviation σ it is close to
+    /// `2^L exp(-σ²/2)`, so that landscapes with small fitness differences, e.g., most additive
+    /// landscapes with small effects, give values close to 2^L.
+    pub fn effective_number_of_peaks(&self) -> f64 {
+        self.fitness_entropy().exp()
+    }
+
+    /// Returns the mean fitness of the individuals in the population
+    pub fn expected_fitness(&self, population: &FixedSizePopulation<L>) -> f64 {
+        self.fitness_moment(population, 1)
+    }
+
+    /// Returns the mean squared fitness of the individuals in the population
+    pub fn expected_fitness_squared(&self, pop: &FixedSizePopulation<L>) -> f64 {
+        self.fitness_moment(pop, 2)
+    }
+
+    /// Returns the variance of the fitness of the individuals in the population
+    pub fn fitness_variance_in_pop(&self, pop: &FixedSizePopulation<L>) -> f64 {
+        self.expected_fitness_squared(pop) - self.expected_fitness(pop).powi(2)
+    }
+
+    /// Returns, for each pair of the `targets` connected in the landscape, the largest minimum
+    /// fitness along the paths joining them. Genotypes are added in decreasing order of fitness,
+    /// joining the clusters of their neighbors, and a pair is recorded when its clusters merge.
+    fn saddles(&self, targets: &[Genotype<L>]) -> Vec<((Genotype<L>, Genotype<L>), f64)> {
+        let mut genotypes: Vec<(Genotype<L>, f64)> = self.landscape.iter().map(|(&g, &f)| (g, f)).collect();
+        genotypes.sort_unstable_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        let index: HashMap<Genotype<L>, usize> = genotypes.iter().enumerate().map(|(i, &(g, _))| (g, i)).collect();
+
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+
+        let mut parent: Vec<usize> = (0..genotypes.len()).collect();
+        let mut members: Vec<Vec<Genotype<L>>> = genotypes.iter().map(|(g, _)| {
+            if targets.contains(g) { vec![*g] } else { Vec::new() }
+        }).collect();
+        let mut saddles = Vec::new();
+        for (i, &(g, f)) in genotypes.iter().enumerate() {
+            for neighbor in g.neighbors() {
+                let j = match index.get(&neighbor) { Some(&j) if j < i => j, _ => continue };
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                if ri == rj { continue }
+                for &a in &members[ri] {
+                    for &b in &members[rj] {
+                        saddles.push(((a.min(b), a.max(b)), f));
+                    }
+                }
+                let moved = std::mem::take(&mut members[rj]);
+                members[ri].extend(moved);
+                parent[rj] = ri;
+            }
+        }
+        saddles
+    }
+
+    /// Returns, for each pair of local maxima, the largest minimum fitness along the paths joining
+    /// them, i.e., the fitness at the bottom of the valley that must be crossed to go from one
+    /// maximum to the other, sorted by pair
+    pub fn all_peak_valleys(&self) -> Vec<((Genotype<L>, Genotype<L>), f64)> {
+        let mut valleys = self.saddles(&self.maxima());
+        valleys.sort_unstable_by_key(|&(pair, _)| pair);
+        valleys
+    }
+
+    /// Returns the lowest valley fitness among all pairs of local maxima, or NaN if there is a
+    /// single maximum
+    pub fn deepest_valley(&self) -> f64 {
+        self.all_peak_valleys().iter().map(|&(_, f)| f).fold(f64::NAN, f64::min)
+    }
+
+    /// Returns the correlation between the fitness of genotypes at Hamming distance d, computed over
+    /// all pairs of genotypes in the landscape
+    pub fn autocorrelation(&self, d: usize) -> f64 {
+        let (mean, var) = self.mean_var();
+        let masks: Vec<usize> = (0..(1_usize << L)).filter(|m| m.count_ones() as usize == d).collect();
+        let (mut cov, mut n) = (0., 0);
+        for (g, &f) in self.landscape.iter() {
+            for &mask in &masks {
+                if let Some(&fi) = self.get(&Genotype::from_index(g.index() ^ mask)) {
+                    cov += (f - mean) * (fi - mean);
+                    n += 1;
+                }
+            }
+        }
+        cov / n as f64 / var
+    }
+
+    /// Returns the correlation length -1/ln(ρ(1)), where ρ(1) is the fitness correlation between
+    /// neighboring genotypes, or NaN if ρ(1) is not positive
+    pub fn autocorrelation_length(&self) -> f64 {
+        let rho = self.autocorrelation(1);
+        if rho > 0. { -1. / rho.ln() } else { f64::NAN }
+    }
+
+    /// Returns the number of genotypes whose greedy adaptive walk ends at each local maximum, in
+    /// decreasing order
+    pub fn basin_sizes(&self) -> Vec<usize> {
+        let mut basins = HashMap::<Genotype<L>, usize>::new();
+        for &g in self.landscape.keys() {
+            let maximum = *self.greedy_walk(g).last().unwrap();
+            *basins.entry(maximum).or_insert(0) += 1;
+        }
+        let mut sizes: Vec<usize> = basins.into_values().collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes
+    }
+
+    /// Returns the number of shortest mutational paths from `from` to `to` along which the fitness
+    /// strictly increases
+    pub fn accessible_paths(&self, from: &Genotype<L>, to: &Genotype<L>) -> usize {
+        let loci: Vec<usize> = (0..L).filter(|&i| from[i] != to[i]).collect();
+        // Genotypes in the paths are given by the subset of the loci in which they differ from `from`
+        let genotype = |subset: usize| {
+            loci.iter().enumerate().filter(|&(k, _)| subset & (1 << k) != 0).fold(*from, |g, (_, &i)| g.cmutate(i))
+        };
+        let fitness: Vec<Option<f64>> = (0..(1_usize << loci.len())).map(|subset| self.get(&genotype(subset)).copied()).collect();
+
+        let mut subsets: Vec<usize> = (0..(1_usize << loci.len())).collect();
+        subsets.sort_by_key(|s| s.count_ones());
+        let mut paths = vec![0_usize; subsets.len()];
+        paths[0] = fitness[0].is_some() as usize;
+        for &subset in &subsets[1..] {
+            let f = match fitness[subset] { Some(f) => f, None => continue };
+            paths[subset] = (0..loci.len()).filter(|&k| subset & (1 << k) != 0).map(|k| {
+                let previous = subset ^ (1 << k);
+                match fitness[previous] {
+                    Some(fp) if fp < f => paths[previous],
+                    _                  => 0
+                }
+            }).sum();
+        }
+        paths[subsets.len() - 1]
+    }
+
+    /// Returns the number of shortest mutational paths from the global minimum to the global
+    /// maximum along which the fitness strictly increases
+    pub fn monotone_path_count(&self) -> u64 {
+        match (self.min(), self.max()) {
+            (Some((min, _)), Some((max, _))) => self.accessible_paths(min, max) as u64,
+            _ => 0
+        }
+    }
+
+    /// Returns the number of monotone paths from the global minimum to the global maximum divided
+    /// by L!, the number of shortest paths between antipodal genotypes, or NaN if the landscape
+    /// is empty
+    pub fn monotone_path_fraction(&self) -> f64 {
+        if self.landscape.is_empty() {
+            return f64::NAN
+        }
+        self.monotone_path_count() as f64 / (1..=L).map(|k| k as f64).product::<f64>()
+    }
+
+    /// Returns the 2^L × 2^L matrix whose entry (i, j) is the probability that an offspring of the
+    /// genotype with index j carries the genotype with index i, `mu^d (1 - mu)^(L - d)` with d
+    /// their Hamming distance. Fails for L > 12, where the matrix is too large.
+    pub fn mutation_matrix(&self, mu: f64) -> Result<Vec<Vec<f64>>, &'static str> {
+        if L > MAX_TRANSFER_MATRIX_LOCI {
+            return Err("the transfer matrix is too large for more than 12 loci")
+        }
+        let probabilities: Vec<f64> = (0..=L).map(|d| mu.powi(d as i32) * (1. - mu).powi((L - d) as i32)).collect();
+        Ok((0..1_usize << L).map(|i| {
+            (0..1_usize << L).map(|j| probabilities[(i ^ j).count_ones() as usize]).collect()
+        }).collect())
+    }
+
+    /// Returns the matrix Q = M W / w̄ of a generation of mutation and selection, where M is the
+    /// mutation matrix, W the diagonal matrix of multiplicative fitnesses and w̄ the mean fitness
+    /// over all genotypes, so that the diagonal entries for mu = 0 are the relative fitnesses
+    /// 1 + s. The quasispecies distribution is the dominant eigenvector of Q, `x ∝ Q x`. Fails for
+    /// L > 12 or if the landscape is incomplete.
+    pub fn mutation_selection_matrix(&self, mu: f64) -> Result<Vec<Vec<f64>>, &'static str> {
+        let mut matrix = self.mutation_matrix(mu)?;
+        let fitness: Vec<f64> = (0..1_usize << L).map(|index| {
+            self.get_fitness(&Genotype::<L>::from_index(index), FitnessType::Multiplicative)
+        }).collect::<Option<_>>().ok_or("the landscape is incomplete")?;
+        let mean = fitness.iter().sum::<f64>() / fitness.len() as f64;
+        for row in matrix.iter_mut() {
+            for (q, w) in row.iter_mut().zip(&fitness) {
+                *q *= w / mean;
+            }
+        }
+        Ok(matrix)
+    }
+
+    /// Returns the fitness of all genotypes in the landscape with exactly `hamming_class` derived
+    /// alleles, in the order of the genotype index
+    pub fn conditional_fitness_distribution(&self, hamming_class: usize) -> Vec<f64> {
+        let mut class: Vec<(usize, f64)> = self.landscape.iter()
+                                                         .filter(|(g, _)| g.sum() == hamming_class)
+                                                         .map(|(g, &f)| (g.index(), f))
+                                                         .collect();
+        class.sort_unstable_by_key(|&(index, _)| index);
+        class.into_iter().map(|(_, f)| f).collect()
+    }
+
+    /// Returns the mean fitness of the genotypes with k derived alleles, or NaN if there are none
+    pub fn conditional_mean_fitness(&self, k: usize) -> f64 {
+        let fitness = self.conditional_fitness_distribution(k);
+        fitness.iter().sum::<f64>() / fitness.len() as f64
+    }
+
+    /// Returns the largest fitness of the genotypes with k derived alleles, or NaN if there are none
+    pub fn conditional_max_fitness(&self, k: usize) -> f64 {
+        self.conditional_fitness_distribution(k).into_iter().reduce(f64::max).unwrap_or(f64::NAN)
+    }
+
+    /// Returns the multilinear extension of the fitness landscape at a point x in [0, 1]^L, which
+    /// equals the fitness of the genotype g at x = g. Genotypes missing from the landscape do not
+    /// contribute. Takes O(2^L) operations.
+    pub fn multilinear_extension(&self, x: &[f64; L]) -> f64 {
+        self.landscape.iter().map(|(g, &f)| {
+            f * g.iter().zip(x.iter()).map(|(&gi, &xi)| if gi == 1 { xi } else { 1. - xi }).product::<f64>()
+        }).sum()
+    }
+
+    /// Returns the gradient of the multilinear extension of the fitness landscape at x
+    pub fn multilinear_gradient(&self, x: &[f64; L]) -> [f64; L] {
+        let mut gradient = [0_f64; L];
+        for (g, &f) in self.landscape.iter() {
+            let weights: Vec<f64> = g.iter().zip(x.iter()).map(|(&gi, &xi)| if gi == 1 { xi } else { 1. - xi }).collect();
+            for (i, d) in gradient.iter_mut().enumerate() {
+                let sign = if g[i] == 1 { 1. } else { -1. };
+                let others: f64 = weights.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, w)| w).product();
+                *d += sign * f * others;
+            }
+        }
+        gradient
+    }
+
+    /// Returns the power of the Walsh-Hadamard spectrum of the additive (log for multiplicative
+    /// landscapes) fitness at each interaction order 0..=L, i.e., the sum of the squared
+    /// normalized coefficients of all subsets of k loci. Returns NaN if the landscape is incomplete.
+    pub fn power_spectrum(&self) -> Vec<f64> {
+        let n = 1 << L;
+        let mut coefficients = vec![0_f64; n];
+        for (index, c) in coefficients.iter_mut().enumerate() {
+            *c = match self.get_fitness(&Genotype::from_index(index), FitnessType::Additive) {
+                Some(f) => f,
+                None    => return vec![f64::NAN; L+1]
+            };
+        }
+        fwht_in_place(&mut coefficients);
+
+        let mut spectrum = vec![0_f64; L+1];
+        for (index, c) in coefficients.iter().enumerate() {
+            spectrum[index.count_ones() as usize] += (c / n as f64).powi(2);
+        }
+        spectrum
+    }
+
+    /// Returns the entropy of the normalized power spectrum over the orders 1..=L, divided by its
+    /// maximum ln(L): 0 if all the variance is at a single order and 1 if it is spread evenly
+    pub fn roughness_index(&self) -> f64 {
+        let spectrum = self.power_spectrum();
+        let total: f64 = spectrum[1..].iter().sum();
+        let entropy: f64 = spectrum[1..].iter().filter(|&&p| p > 0.).map(|&p| {
+            let p = p / total;
+            -p * p.ln()
+        }).sum();
+        entropy / (L as f64).ln()
+    }
+
+    pub fn strains_selected(&self) -> Vec<Genotype<L>> {
+        self.landscape.iter().filter_map(|(&g, &f)| {
+            if f > 1. { Some(g) } else { None }
+        }).collect()
+    }
+
+    pub fn to_vec(&self) -> VecLandscape {
+        let mut v = Vec::with_capacity(self.landscape.len());
+        for &g in self.landscape.keys() {
+            let f = self.get_fitness(&g, FitnessType::Additive).unwrap_or(f64::NAN);
+            let g = g.to_vec();
+            v.push((g, f));
+        }
+        v
+    }
+    fn to_vec_raw(&self) -> Vec<(Genotype<L>, f64)> {
+        let mut v = Vec::with_capacity(self.landscape.len());
+        for &g in self.landscape.keys() {
+            let f = self.get(&g).unwrap_or(&f64::NAN);
+            v.push((g, *f));
+        }
+        v
+    }
+    pub fn from_vec(&self, v: VecLandscape) -> Self {
+        let mut landscape = Self::new(FitnessType::Additive);
+        for (g, f) in &v {
+            landscape.add_genotype(Genotype::<L>::from_sequence(g), *f);
+        }
+        landscape
+    }
+
+    pub fn save(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let file = File::create(filename).unwrap();
+        write!(BufWriter::new(file), "{}", self)?;
+        Ok(())
+    }
+
+    /// Writes the landscape to a CSV file with header `g0,...,g{L-1},fitness` and one row per
+    /// genotype, sorted by genotype
+    pub fn save_csv(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = BufWriter::new(File::create(filename)?);
+        let header: Vec<String> = (0..L).map(|i| format!("g{}", i)).collect();
+        writeln!(file, "{},fitness", header.join(","))?;
+        for g in self.sorted_genotypes() {
+            let alleles: Vec<String> = g.iter().map(|a| a.to_string()).collect();
+            writeln!(file, "{},{}", alleles.join(","), self.landscape[&g])?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Reads a landscape of fitness type `tp` from a CSV file in the format written by
+    /// `save_csv`, e.g., by `write.csv(..., row.names = FALSE)` in R or `to_csv(index=False)` in
+    /// pandas. The number of loci is given by the number of columns of the header.
+    pub fn load_csv(filename: &str, tp: FitnessType) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(filename)?;
+        let mut rows = contents.lines().enumerate().filter(|(_, row)| !row.trim().is_empty());
+        let split = |row: &str| -> Vec<String> {
+            row.split(',').map(|field| field.trim().trim_matches('"').to_string()).collect()
+        };
+
+        let header = match rows.next() {
+            Some((_, header)) => split(header),
+            None => return Err(format!("{}: empty file", filename).into())
+        };
+        if header.len() != L + 1 {
+            return Err(format!("{}: the header has {} loci but the landscape has {}", filename, header.len() as i64 - 1, L).into())
+        }
+
+        let mut landscape = Self::new(tp);
+        for (i, row) in rows {
+            let fields = split(row);
+            if fields.len() != L + 1 {
+                return Err(format!("{}, line {}: expected {} columns, found {}", filename, i+1, L+1, fields.len()).into())
+            }
+            let mut seq = [0_u8; L];
+            for (allele, field) in seq.iter_mut().zip(&fields) {
+                *allele = match field.as_str() {
+                    "0" => 0,
+                    "1" => 1,
+                    _   => return Err(format!("{}, line {}: invalid allele '{}'", filename, i+1, field).into())
+                };
+            }
+            let fitness = fields[L].parse::<f64>().map_err(|_| {
+                format!("{}, line {}: invalid fitness '{}'", filename, i+1, fields[L])
+            })?;
+            landscape.add_genotype(Genotype::from_sequence(&seq), fitness);
+        }
+        Ok(landscape)
+    }
+}
+
+/// Returns the Spearman rank correlation between the fitnesses of two landscapes
+pub fn landscape_correlation<const L: usize>(l1: &FitnessLandscape<L>, l2: &FitnessLandscape<L>) -> f64 {
+    l1.spearman_rho(l2)
+}
+
+/// Returns the fraction of the pairs of genotypes present in both landscapes whose fitness
+/// ordering is reversed from one landscape to the other. Without ties, Kendall's tau equals
+/// `1 - 2 * rank_inversion_fraction`.
+pub fn rank_inversion_fraction<const L: usize>(l1: &FitnessLandscape<L>, l2: &FitnessLandscape<L>) -> f64 {
+    let common: Vec<(f64, f64)> = l1.landscape.iter().filter_map(|(g, &f1)| {
+        l2.get(g).map(|&f2| (f1, f2))
+    }).collect();
+
+    let (mut inversions, mut pairs) = (0, 0);
+    for (i, &(f1i, f2i)) in common.iter().enumerate() {
+        for &(f1j, f2j) in &common[i + 1..] {
+            if (f1i - f1j) * (f2i - f2j) < 0. {
+                inversions += 1;
+            }
+            pairs += 1;
+        }
+    }
+    inversions as f64 / pairs as f64
+}
+
+/// Returns the mean log fitness of the individuals in the population, ignoring the genotypes
+/// missing from the landscape
+fn mean_log_fitness<const L: usize>(landscape: &FitnessLandscape<L>, pop: &FixedSizePopulation<L>) -> f64 {
+    pop.distribution().iter().filter_map(|(g, &freq)| {
+        landscape.get_fitness(g, FitnessType::Additive).map(|f| freq * f)
+    }).sum()
+}
+
+/// Returns the rate of change of the mean log fitness of the population due to the change of the
+/// landscape over a time `dt`, `(E_after[log f] - E_before[log f]) / dt`, in the spirit of the
+/// fitness flux of Mustonen and Lässig. It vanishes in a constant landscape and is negative if the
+/// landscape turns against the population.
+pub fn fitness_flux<const L: usize>(
+    landscape_before: &FitnessLandscape<L>,
+    landscape_after: &FitnessLandscape<L>,
+    pop: &FixedSizePopulation<L>,
+    dt: f64
+) -> f64 {
+    (mean_log_fitness(landscape_after, pop) - mean_log_fitness(landscape_before, pop)) / dt
+}
+
+/// Returns the fitness flux integrated along a trajectory of landscapes and populations, one per
+/// generation, evaluating each step on the population at its end
+pub fn integrated_fitness_flux<const L: usize>(trajectory: &[(FitnessLandscape<L>, FixedSizePopulation<L>)]) -> f64 {
+    trajectory.windows(2).map(|w| fitness_flux(&w[0].0, &w[1].0, &w[1].1, 1.)).sum()
+}
+
+/// Returns the fraction of single mutations, i.e. pairs of neighboring genotypes present in both
+/// landscapes, that are beneficial in one landscape and deleterious in the other
+pub fn sign_inversion_fraction<const L: usize>(l1: &FitnessLandscape<L>, l2: &FitnessLandscape<L>) -> f64 {
+    let (mut inversions, mut pairs) = (0, 0);
+    for (g, &f1) in l1.landscape.iter() {
+        let f2 = match l2.get(g) { Some(&f2) => f2, None => continue };
+        for n in g.neighbors().filter(|n| n > g) {
+            if let (Some(&n1), Some(&n2)) = (l1.get(&n), l2.get(&n)) {
+                if (n1 - f1) * (n2 - f2) < 0. {
+                    inversions += 1;
+                }
+                pairs += 1;
+            }
+        }
+    }
+    inversions as f64 / pairs as f64
+}
+
+/// Returns the p-value of the number of local maxima of the landscape against random permutations
+/// of its fitnesses, i.e., the fraction of `n_perm` permuted landscapes with at least as many
+/// maxima, or NaN if `n_perm` is 0
+pub fn permutation_test_maxima<const L: usize>(landscape: &FitnessLandscape<L>, n_perm: usize, rng: &mut impl Rng) -> f64 {
+    let n_maxima = landscape.maxima().len();
+    let n_extreme = (0..n_perm).filter(|_| landscape.randomize_fitnesses(rng).maxima().len() >= n_maxima).count();
+    n_extreme as f64 / n_perm as f64
+}
+
+/// Returns the path between two peaks whose lowest fitness is the largest, together with that
+/// fitness, or None if the peaks are not connected in the landscape. The path is found by breadth
+/// first search among the genotypes with fitness above the valley.
+pub fn valley_between<const L: usize>(l: &FitnessLandscape<L>, peak1: Genotype<L>, peak2: Genotype<L>) -> Option<(Vec<Genotype<L>>, f64)> {
+    if peak1 == peak2 {
+        return l.get(&peak1).map(|&f| (vec![peak1], f))
+    }
+    let (_, valley) = *l.saddles(&[peak1, peak2]).first()?;
+
+    let mut previous = HashMap::<Genotype<L>, Genotype<L>>::new();
+    let mut queue = VecDeque::from([peak1]);
+    while let Some(g) = queue.pop_front() {
+        if g == peak2 { break }
+        for neighbor in g.neighbors() {
+            if neighbor != peak1 && !previous.contains_key(&neighbor) && l.get(&neighbor).is_some_and(|&f| f >= valley) {
+                previous.insert(neighbor, g);
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    let mut path = vec![peak2];
+    while *path.last().unwrap() != peak1 {
+        path.push(previous[path.last().unwrap()]);
+    }
+    path.reverse();
+    Some((path, valley))
+}
+
+impl<const L: usize> fmt::Display for FitnessLandscape<L> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (&g, &fitness) in &self.landscape {
+            write!(f, "{}\t{}\n", g, fitness)?
         }
         write!(f, "")
     }
 }
+
+/// Sums the fitness of the genotypes present in both landscapes and keeps the genotypes present in
+/// only one of them. Landscapes with different fitness types are summed in additive form.
+impl<const L: usize> std::ops::Add<FitnessLandscape<L>> for FitnessLandscape<L> {
+    type Output = Self;
+
+    fn add(self, rhs: FitnessLandscape<L>) -> Self::Output {
+        let tp = if self.tp == rhs.tp { self.tp } else { FitnessType::Additive };
+        let mut sum = Self::new(tp);
+        for landscape in [&self, &rhs] {
+            for g in landscape.landscape.keys() {
+                *sum.landscape.entry(*g).or_insert(0.) += landscape.get_fitness(g, tp).unwrap();
+            }
+        }
+        sum
+    }
+}
+
+impl<const L: usize> std::ops::Mul<f64> for FitnessLandscape<L> {
+    type Output = Self;
+
+    fn mul(mut self, rhs: f64) -> Self::Output {
+        for f in self.landscape.values_mut() {
+            *f *= rhs;
+        }
+        self
+    }
+}
+
+impl<const L: usize> std::ops::Neg for FitnessLandscape<L> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self * -1.
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        genotype::{possible_sequences, landscape_size},
+        fitness_model::FitnessModel,
+        population::InitialPopulation
+    };
+
+    #[test]
+    fn epistasis() {
+        const L: usize = 3;
+        let mut additive  = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut epistatic = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            let (g0, g1, g2) = (g[0] as f64, g[1] as f64, g[2] as f64);
+            additive.add_genotype(g, 1. + g0 + 0.5*g1 - 0.2*g2);
+            epistatic.add_genotype(g, 1. + g0 + g1 + 2.*g0*g1);
+        }
+
+        for row in additive.pairwise_epistasis_matrix(FitnessType::Additive) {
+            for e in row {
+                assert!(e.abs() < 1e-12);
+            }
+        }
+        assert!(additive.epistasis_network(1e-9).is_empty());
+
+        let background = Genotype::from_sequence(&[1, 0, 1]);
+        assert!(additive.double_mutant_epistasis(0, 2, &background).unwrap().abs() < 1e-12);
+        assert!(additive.mean_epistasis_matrix().iter().flatten().all(|e| e.abs() < 1e-12));
+        assert!(additive.epistasis_strength() < 1e-12);
+
+        for g in epistatic.landscape.keys() {
+            let matrix = epistatic.epistasis_matrix_on_background(g);
+            assert!((matrix[0][1] - 2.).abs() < 1e-12 && (matrix[1][0] - 2.).abs() < 1e-12);
+            assert!(matrix[0][2].abs() < 1e-12 && matrix[1][2].abs() < 1e-12);
+        }
+        assert!((epistatic.epistasis_strength() - 8_f64.sqrt()).abs() < 1e-12);
+
+        let network = epistatic.epistasis_network(1e-9);
+        assert_eq!(network.len(), 1);
+        assert_eq!((network[0].0, network[0].1), (0, 1));
+        assert!((network[0].2 - 2.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn population_sample() {
+        const L: usize = 5;
+        const S: usize = 2;
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+        let resources = Vector::from([1., 1.]);
+
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        population.mutation(0.1);
+
+        let mut sample = FitnessLandscape::from_population_sample(&population, &landscape, &resources);
+        assert_eq!(sample.landscape.len(), population.n_genotypes());
+
+        sample.complete_from_model(&population, &landscape, &resources);
+        assert_eq!(sample.landscape.len(), landscape_size::<L>());
+    }
+
+    #[test]
+    fn adaptive_walks() {
+        const L: usize = 6;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.1 * g.sum() as f64);
+        }
+
+        let walk = landscape.greedy_walk(Genotype::new());
+        assert_eq!(walk.len(), L + 1);
+        assert_eq!(walk[L].sum(), L);
+
+        // Every walk goes straight to the all-ones genotype
+        assert_eq!(landscape.walk_length_distribution(), vec![6, 15, 20, 15, 6, 1]);
+        assert!((landscape.mean_walk_length() - L as f64 / 2.).abs() < 1e-12);
+        assert!((landscape.walk_length_variance() - L as f64 / 4.).abs() < 1e-12);
+        assert_eq!(landscape.walk_length_moments(), (landscape.mean_walk_length(), landscape.walk_length_variance()));
+
+        let n_trials = 1000;
+        let distribution = landscape.gillespie_walk_length_distribution(&mut rand::thread_rng(), n_trials);
+        assert!(distribution.iter().sum::<usize>() <= n_trials);
+        assert!(distribution.len() <= L);
+    }
+
+    #[test]
+    fn perturbation() {
+        const L: usize = 6;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.1 * g.sum() as f64);
+        }
+        let original = landscape.clone();
+        let mut rng = rand::thread_rng();
+
+        landscape.perturb(0., &mut rng);
+        landscape.perturb_multiplicative(0., &mut rng);
+        assert_eq!(landscape.landscape, original.landscape);
+        assert_eq!(landscape.rms_difference(&original), 0.);
+        assert_eq!(landscape.fingerprint(), original.fingerprint());
+
+        landscape.perturb(0.01, &mut rng);
+        assert!((landscape.mean_var().0 - original.mean_var().0).abs() < 1e-12);
+        assert!(landscape.rms_difference(&original) > 0.);
+        assert_ne!(landscape.fingerprint(), original.fingerprint());
+
+        // A landscape with zero mean fitness is shifted instead of rescaled
+        landscape.normalize_to_mean();
+        landscape.landscape.values_mut().for_each(|f| *f -= 1.);
+        landscape.perturb(0.01, &mut rng);
+        assert!(landscape.landscape.values().all(|f| f.is_finite()));
+        assert!(landscape.mean_var().0.abs() < 1e-12);
+    }
+
+    #[test]
+    fn normalization() {
+        const L: usize = 4;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 2. + 0.5 * g.sum() as f64);
+        }
+
+        landscape.normalize_to_wildtype();
+        assert_eq!(landscape.get(&Genotype::new()), Some(&1.0));
+
+        landscape.normalize_to_max();
+        assert_eq!(landscape.max().map(|(_, &f)| f), Some(1.0));
+
+        landscape.normalize_to_mean();
+        assert!((landscape.mean_var().0 - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fitness_graph() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.1 * g.sum() as f64);
+        }
+
+        let graph = landscape.fitness_graph();
+        assert_eq!(graph[&Genotype::new()].len(), L);
+        assert!(graph[&Genotype::from_sequence(&[1, 1, 1])].is_empty());
+
+        let edges = landscape.fitness_graph_edges();
+        assert_eq!(edges.len(), L * (1 << (L - 1)));
+        assert!(edges.iter().all(|&(_, _, df)| (df - 0.1).abs() < 1e-12));
+
+        let components = landscape.strongly_connected_components();
+        assert_eq!(components, landscape.connected_components());
+        assert_eq!(components.len(), 1);
+        assert_eq!(components[0].len(), 1 << L);
+
+        // Removing the genotypes with one derived allele disconnects the wildtype
+        for i in 0..L {
+            landscape.landscape.remove(&Genotype::new().cmutate(i));
+        }
+        let mut sizes: Vec<usize> = landscape.strongly_connected_components().iter().map(|c| c.len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![1, 4]);
+    }
+
+    #[test]
+    fn evolvability() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.1 * g.sum() as f64);
+        }
+        let wildtype = Genotype::<L>::new();
+        assert_eq!(landscape.evolvability(&wildtype), 1.);
+        assert_eq!(landscape.evolvability(&Genotype::from_sequence(&[1, 1, 0])), 1. / 3.);
+        assert_eq!(landscape.evolvability(&Genotype::from_sequence(&[1, 1, 1])), 0.);
+        assert!((landscape.mean_evolvability() - 0.5).abs() < 1e-12);
+
+        let mut population = FixedSizePopulation::<L>::new(10);
+        population.initialize(InitialPopulation::SingleGenotype(wildtype));
+        assert!((landscape.expected_fitness_after_mutation(&population, 0.) - 1.).abs() < 1e-12);
+        assert!((landscape.expected_fitness_after_mutation(&population, 0.5) - 1.05).abs() < 1e-12);
+        assert!((landscape.fitness_flux(&population) - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn power_spectrum() {
+        const L: usize = 4;
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut epistatic = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            additive.add_genotype(g, 1. + 0.1 * g[0] as f64 - 0.3 * g[2] as f64);
+            epistatic.add_genotype(g, 1. + 0.2 * (g[0] * g[1]) as f64);
+        }
+
+        let spectrum = additive.power_spectrum();
+        assert_eq!(spectrum.len(), L+1);
+        assert!(spectrum[1] > 0.);
+        assert!(spectrum[2..].iter().all(|&p| p.abs() < 1e-12));
+        assert!(additive.roughness_index().abs() < 1e-12);
+
+        // The total power equals the mean squared fitness (Parseval)
+        let spectrum = epistatic.power_spectrum();
+        let mean_square = epistatic.landscape.values().map(|f| f*f).sum::<f64>() / landscape_size::<L>() as f64;
+        assert!((spectrum.iter().sum::<f64>() - mean_square).abs() < 1e-12);
+        assert!(spectrum[2] > 0. && spectrum[3..].iter().all(|&p| p.abs() < 1e-12));
+        assert!(epistatic.roughness_index() > 0.);
+    }
+
+    #[test]
+    fn hamming_classes() {
+        const L: usize = 4;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.1 * g[0] as f64 + 0.2 * g[1] as f64 + 0.3 * g[2] as f64 + 0.4 * g[3] as f64);
+        }
+
+        assert_eq!(landscape.conditional_fitness_distribution(0), vec![1.]);
+        assert_eq!(landscape.conditional_fitness_distribution(2).len(), 6);
+        for k in 0..=L {
+            assert!((landscape.conditional_mean_fitness(k) - (1. + 0.25 * k as f64)).abs() < 1e-12);
+        }
+        assert!((landscape.conditional_max_fitness(1) - 1.4).abs() < 1e-12);
+        assert!(landscape.conditional_mean_fitness(L+1).is_nan());
+        assert!(landscape.conditional_max_fitness(L+1).is_nan());
+    }
+
+    #[test]
+    fn accessibility() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, g.sum() as f64);
+        }
+        let wildtype = Genotype::<L>::new();
+        assert_eq!(landscape.reachable_from(&wildtype, 0), HashSet::from([wildtype]));
+        assert_eq!(landscape.reachable_from(&wildtype, 1).len(), 4);
+        assert_eq!(landscape.reachable_fraction(&wildtype, 3), 1.);
+        assert_eq!(landscape.evolutionary_horizon(&wildtype), 3);
+        assert_eq!(landscape.evolutionary_horizon(&Genotype::from_sequence(&[1, 1, 1])), 0);
+
+        // A fitness valley around the maximum makes it inaccessible from the wildtype
+        let mut valley = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            let f = match g.sum() { 0 => 1., 1 => 1.5, 2 => 0.5, _ => 2. };
+            valley.add_genotype(g, f);
+        }
+        assert_eq!(valley.reachable_from(&wildtype, 3).len(), 4);
+        assert_eq!(valley.reachable_fraction(&wildtype, 3), 0.5);
+        assert_eq!(valley.evolutionary_horizon(&wildtype), usize::MAX);
+        assert_eq!(valley.evolutionary_horizon(&Genotype::from_sequence(&[1, 1, 0])), 1);
+    }
+
+    #[test]
+    fn multilinear_extension() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.3 * g[0] as f64 - 0.2 * g[1] as f64 + 0.5 * (g[1] * g[2]) as f64);
+        }
+
+        for seq in possible_sequences::<L>() {
+            let x = seq.map(|a| a as f64);
+            assert_eq!(landscape.multilinear_extension(&x), *landscape.get(&Genotype::from_sequence(&seq)).unwrap());
+        }
+        assert!((landscape.multilinear_extension(&[0.5, 0.5, 0.5]) - (1. + 0.15 - 0.1 + 0.125)).abs() < 1e-12);
+
+        // The gradient of 1 + 0.3 x0 - 0.2 x1 + 0.5 x1 x2
+        let gradient = landscape.multilinear_gradient(&[0.2, 0.4, 0.6]);
+        let expected = [0.3, -0.2 + 0.5 * 0.6, 0.5 * 0.4];
+        for (d, e) in gradient.iter().zip(expected.iter()) {
+            assert!((d - e).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn arithmetic() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + 0.1 * g.sum() as f64);
+        }
+        let zero = landscape.clone() + (-landscape.clone());
+        assert_eq!(zero.landscape.len(), landscape_size::<L>());
+        assert!(zero.landscape.values().all(|&f| f == 0.));
+
+        let doubled = landscape.clone() * 2.;
+        assert_eq!(*doubled.get(&Genotype::new()).unwrap(), 2.);
+
+        // Genotypes present in a single landscape are kept, and multiplicative fitness is
+        // converted to additive form
+        let mut partial = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        partial.add_genotype(Genotype::new(), 1_f64.exp());
+        let sum = landscape.clone() + partial;
+        assert!(sum.tp == FitnessType::Additive);
+        assert!((sum.get(&Genotype::new()).unwrap() - 2.).abs() < 1e-12);
+        assert_eq!(sum.get(&Genotype::from_sequence(&[1, 1, 1])), landscape.get(&Genotype::from_sequence(&[1, 1, 1])));
+    }
+
+    #[test]
+    fn landscape_structure() {
+        const L: usize = 3;
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut rugged = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            additive.add_genotype(g, g.sum() as f64);
+            rugged.add_genotype(g, match g.sum() { 0 => 1., 1 => 0.5, 2 => 0.2, _ => 2. });
+        }
+        for d in 0..=L {
+            assert!((additive.autocorrelation(d) - (1. - 2. * d as f64 / L as f64)).abs() < 1e-12);
+        }
+        assert!((additive.autocorrelation_length() - 1. / 3_f64.ln()).abs() < 1e-12);
+
+        let (wildtype, top) = (Genotype::new(), Genotype::from_sequence(&[1, 1, 1]));
+        assert_eq!(additive.basin_sizes(), vec![8]);
+        assert_eq!(additive.accessible_paths(&wildtype, &top), 6);
+        assert_eq!(additive.accessible_paths(&top, &wildtype), 0);
+        assert_eq!(additive.accessible_paths(&wildtype, &wildtype), 1);
+
+        assert_eq!(rugged.basin_sizes(), vec![4, 4]);
+        assert_eq!(rugged.accessible_paths(&wildtype, &top), 0);
+        assert_eq!(rugged.accessible_paths(&Genotype::from_sequence(&[1, 1, 0]), &top), 1);
+    }
+
+    #[test]
+    fn rank_inversions() {
+        const L: usize = 4;
+        let mut l1 = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut reversed = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            l1.add_genotype(g, g.index() as f64);
+            reversed.add_genotype(g, -(g.index() as f64));
+        }
+        assert_eq!(rank_inversion_fraction(&l1, &l1), 0.);
+        assert_eq!(sign_inversion_fraction(&l1, &l1), 0.);
+        assert_eq!(rank_inversion_fraction(&l1, &reversed), 1.);
+        assert_eq!(sign_inversion_fraction(&l1, &reversed), 1.);
+
+        assert_eq!(l1.kendall_tau(&l1), 1.);
+        assert_eq!(l1.kendall_tau(&reversed), -1.);
+        let mut tied = FitnessLandscape::<L>::new(FitnessType::Additive);
+        tied.extend(l1.landscape.keys().map(|&g| (g, g.sum() as f64)));
+        let tau = l1.kendall_tau(&tied);
+        assert!(tau > 0. && tau < 1.);
+    }
+
+    #[test]
+    fn incremental_construction() {
+        const L: usize = 4;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        landscape.extend(possible_sequences::<L>().iter().map(|seq| (Genotype::from_sequence(seq), 1.)));
+        assert_eq!(landscape.landscape.len(), 1 << L);
+
+        let g = Genotype::<L>::from_sequence(&[1, 0, 1, 0]);
+        landscape.extend([(g, 2.)]);
+        assert_eq!(landscape.get(&g), Some(&2.));
+        assert_eq!(landscape.landscape.len(), 1 << L);
+
+        assert_eq!(landscape.remove_genotype(&g), Some(2.));
+        assert_eq!(landscape.remove_genotype(&g), None);
+
+        let mut population = FixedSizePopulation::<L>::new(10);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        let restricted = landscape.restrict_to_population(&population);
+        assert_eq!(restricted.landscape.len(), 1);
+        assert_eq!(restricted.get(&Genotype::new()), Some(&1.));
+        assert!(landscape.restrict_to_population(&FixedSizePopulation::<L>::new(0)).landscape.is_empty());
+    }
+
+    #[test]
+    fn sublandscapes() {
+        let mut landscape = FitnessLandscape::<3>::new(FitnessType::Additive);
+        for seq in possible_sequences::<3>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), seq[0] as f64 + 2. * seq[1] as f64 + 4. * seq[2] as f64);
+        }
+
+        let marginal = landscape.sublandscape([2, 0]);
+        assert_eq!(marginal.landscape.len(), 4);
+        assert_eq!(marginal.get(&Genotype::from_sequence(&[1, 0])), Some(&5.));
+        assert_eq!(marginal.get(&Genotype::from_sequence(&[0, 1])), Some(&2.));
+
+        let background = Genotype::<3>::from_sequence(&[0, 1, 0]);
+        let fixed = landscape.sublandscape_fixed([2, 0], &background);
+        assert_eq!(fixed.landscape.len(), 4);
+        assert_eq!(fixed.get(&Genotype::from_sequence(&[1, 0])), Some(&6.));
+        assert_eq!(fixed.get(&Genotype::from_sequence(&[1, 1])), Some(&7.));
+    }
+
+    #[test]
+    fn expected_fitness() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + g.sum() as f64);
+        }
+
+        let g = Genotype::<L>::from_sequence(&[0, 1, 1]);
+        let mut population = FixedSizePopulation::<L>::new(10);
+        population.initialize(InitialPopulation::SingleGenotype(g));
+        assert_eq!(landscape.expected_fitness(&population), *landscape.get(&g).unwrap());
+        assert_eq!(landscape.fitness_variance_in_pop(&population), 0.);
+
+        let mut population = FixedSizePopulation::<L>::new(4);
+        population.add_genotype(Genotype::new(), 2);
+        population.add_genotype(g, 2);
+        assert_eq!(landscape.expected_fitness(&population), 2.);
+        assert_eq!(landscape.expected_fitness_squared(&population), 5.);
+        assert_eq!(landscape.fitness_variance_in_pop(&population), 1.);
+    }
+
+    #[test]
+    fn selection_coefficients() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            landscape.add_genotype(g, 1. + g.sum() as f64);
+        }
+
+        // Every mutation away from the global maximum is deleterious
+        let top = Genotype::<L>::from_sequence(&[1, 1, 1]);
+        let mut population = FixedSizePopulation::<L>::new(10);
+        population.initialize(InitialPopulation::SingleGenotype(top));
+        for tp in [FitnessType::Multiplicative, FitnessType::Additive] {
+            let coefficients = landscape.selection_coefficient_distribution(&population, tp);
+            assert_eq!(coefficients.len(), L);
+            assert!(coefficients.iter().all(|&s| s <= 0.));
+            assert_eq!(landscape.fraction_beneficial(&population, tp), 0.);
+        }
+        assert!((landscape.mean_selection_coefficient(&population, FitnessType::Multiplicative) + 0.25).abs() < 1e-12);
+
+        let mut population = FixedSizePopulation::<L>::new(4);
+        population.add_genotype(Genotype::new(), 1);
+        population.add_genotype(top, 3);
+        assert_eq!(landscape.selection_coefficient_distribution(&population, FitnessType::Multiplicative).len(), 2 * L);
+        assert_eq!(landscape.fraction_beneficial(&population, FitnessType::Multiplicative), 0.25);
+        assert!((landscape.mean_selection_coefficient(&population, FitnessType::Multiplicative) - 0.0625).abs() < 1e-12);
+    }
+
+    #[test]
+    fn valleys() {
+        const L: usize = 3;
+        // Peaks at 000 and 111, separated by a valley at fitness 0.5 along the path through 001
+        // and 011
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for (seq, f) in [([0, 0, 0], 2.), ([1, 0, 0], 0.1), ([0, 1, 0], 0.2), ([0, 0, 1], 0.6),
+                         ([1, 1, 0], 0.3), ([1, 0, 1], 0.4), ([0, 1, 1], 0.5), ([1, 1, 1], 3.)] {
+            landscape.add_genotype(Genotype::from_sequence(&seq), f);
+        }
+        let (peak1, peak2) = (Genotype::new(), Genotype::from_sequence(&[1, 1, 1]));
+
+        let (path, valley) = valley_between(&landscape, peak1, peak2).unwrap();
+        assert_eq!(valley, 0.5);
+        assert_eq!(path, vec![peak1, Genotype::from_sequence(&[0, 0, 1]), Genotype::from_sequence(&[0, 1, 1]), peak2]);
+
+        assert_eq!(landscape.all_peak_valleys(), vec![((peak1, peak2), 0.5)]);
+        assert_eq!(landscape.deepest_valley(), 0.5);
+
+        // Single peak at 111
+        landscape.add_genotype(peak1, 0.05);
+        landscape.add_genotype(Genotype::from_sequence(&[0, 0, 1]), 0.45);
+        assert!(landscape.all_peak_valleys().is_empty() && landscape.deepest_valley().is_nan());
+    }
+
+    #[test]
+    fn additive_component() {
+        const L: usize = 8;
+        let mut rng = rand::thread_rng();
+        let normal = Normal::new(0., 1.).unwrap();
+        let effects: Vec<f64> = (0..L).map(|_| normal.sample(&mut rng)).collect();
+
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut hoc = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            additive.add_genotype(g, 1. + seq.iter().zip(&effects).map(|(&a, e)| a as f64 * e).sum::<f64>());
+            hoc.add_genotype(g, normal.sample(&mut rng));
+        }
+
+        let fitted = additive.locally_additive_component();
+        for (g, f) in &additive.landscape {
+            assert!((fitted.get(g).unwrap() - f).abs() < 1e-9);
+        }
+        assert!((additive.additive_r_squared() - 1.).abs() < 1e-9);
+        assert!(hoc.additive_r_squared() < 0.15);
+
+        let mut monomorphic = FitnessLandscape::<L>::new(FitnessType::Additive);
+        monomorphic.add_genotype(Genotype::new(), 1.);
+        assert!(monomorphic.locally_additive_component().get(&Genotype::new()).unwrap().is_nan());
+    }
+
+    #[test]
+    fn information_content() {
+        const L: usize = 8;
+        let mut rng = rand::thread_rng();
+        let normal = Normal::new(0., 1.).unwrap();
+        let effects: Vec<f64> = (0..L).map(|_| normal.sample(&mut rng)).collect();
+
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut hoc = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            additive.add_genotype(g, 1. + seq.iter().zip(&effects).map(|(&a, e)| a as f64 * e).sum::<f64>());
+            hoc.add_genotype(g, normal.sample(&mut rng));
+        }
+        assert!((additive.information_content() - 1.).abs() < 1e-12);
+        assert!(additive.epistatic_content().abs() < 1e-9);
+        assert!(hoc.information_content().abs() < 1e-12);
+        assert!(hoc.epistatic_content() > 0.85);
+
+        let mut single = FitnessLandscape::<L>::new(FitnessType::Additive);
+        single.add_genotype(Genotype::new(), 1.);
+        assert!(single.information_content().is_nan());
+    }
+
+    #[test]
+    fn csv() {
+        const L: usize = 4;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        let normal = Normal::new(1., 0.1).unwrap();
+        for seq in possible_sequences::<L>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), normal.sample(&mut rand::thread_rng()));
+        }
+
+        let filename = std::env::temp_dir().join(format!("landscape_{}.csv", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        landscape.save_csv(filename).unwrap();
+        let contents = std::fs::read_to_string(filename).unwrap();
+        assert!(contents.starts_with("g0,g1,g2,g3,fitness\n0,0,0,0,"));
+
+        let loaded = FitnessLandscape::<L>::load_csv(filename, FitnessType::Multiplicative).unwrap();
+        assert_eq!(loaded.landscape, landscape.landscape);
+        assert!(FitnessLandscape::<3>::load_csv(filename, FitnessType::Multiplicative).is_err());
+
+        std::fs::write(filename, "\"g0\",\"g1\",\"fitness\"\n1,0,0.5\n").unwrap();
+        let quoted = FitnessLandscape::<2>::load_csv(filename, FitnessType::Additive).unwrap();
+        assert_eq!(quoted.get(&Genotype::from_sequence(&[1, 0])), Some(&0.5));
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn monotone_paths() {
+        const L: usize = 8;
+        let mut rng = rand::thread_rng();
+        let normal = Normal::new(0., 1.).unwrap();
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut hoc = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            additive.add_genotype(g, seq.iter().enumerate().map(|(i, &a)| a as f64 * (i + 1) as f64).sum());
+            hoc.add_genotype(g, normal.sample(&mut rng));
+        }
+        assert_eq!(additive.monotone_path_count(), 40320);
+        assert_eq!(additive.monotone_path_fraction(), 1.);
+        assert!(hoc.monotone_path_fraction() < 0.05);
+        assert!(FitnessLandscape::<L>::new(FitnessType::Additive).monotone_path_fraction().is_nan());
+    }
+
+    #[test]
+    fn transfer_matrix() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for index in 0..1 << L {
+            landscape.add_genotype(Genotype::from_index(index), 1. + 0.1 * index as f64);
+        }
+        let mean = 1.35;
+
+        let selection = landscape.mutation_selection_matrix(0.).unwrap();
+        for (i, row) in selection.iter().enumerate() {
+            for (j, q) in row.iter().enumerate() {
+                let expected = if i == j { (1. + 0.1 * j as f64) / mean } else { 0. };
+                assert!((q - expected).abs() < 1e-12);
+            }
+        }
+
+        let mutation = landscape.mutation_matrix(0.1).unwrap();
+        assert!((mutation[0][7] - 1e-3).abs() < 1e-15);
+        assert!((mutation[5][4] - 0.081).abs() < 1e-15);
+        let matrix = landscape.mutation_selection_matrix(0.1).unwrap();
+        for j in 0..1 << L {
+            assert!(((0..1 << L).map(|i| mutation[i][j]).sum::<f64>() - 1.).abs() < 1e-12);
+            assert!(((0..1 << L).map(|i| matrix[i][j]).sum::<f64>() - (1. + 0.1 * j as f64) / mean).abs() < 1e-12);
+        }
+
+        landscape.remove_genotype(&Genotype::new());
+        assert!(landscape.mutation_selection_matrix(0.1).is_err());
+        assert!(FitnessLandscape::<13>::new(FitnessType::Multiplicative).mutation_matrix(0.1).is_err());
+    }
+
+    #[test]
+    fn epistasis_classes() {
+        const L: usize = 4;
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            additive.add_genotype(Genotype::from_sequence(&seq), seq.iter().enumerate().map(|(i, &a)| (i + 1) as f64 * a as f64).sum());
+        }
+        assert_eq!(additive.classify_epistasis(), (L << L, 0, 0));
+        assert_eq!(additive.reciprocal_sign_epistasis_count(), 0);
+
+        // Two peaks at the wildtype and at the genotype carrying all derived alleles
+        let mut two_peaks = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let d = seq.iter().map(|&a| a as f64).sum::<f64>();
+            two_peaks.add_genotype(Genotype::from_sequence(&seq), (d - 2.).powi(2));
+        }
+        let (none, magnitude, sign) = two_peaks.classify_epistasis();
+        assert_eq!(none + magnitude + sign, L << L);
+        assert!(sign > 0);
+        assert!(two_peaks.reciprocal_sign_epistasis_count() > 0);
+
+        let mut multiplicative = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        multiplicative.extend(additive.landscape.iter().map(|(g, f)| (*g, f.exp())));
+        assert_eq!(multiplicative.classify_epistasis().0, L << L);
+    }
+
+    #[test]
+    fn additive_and_epistatic() {
+        const L: usize = 4;
+        let effects = [0.5, -1., 0.25, 2.];
+        let normal = Normal::new(0., 1.).unwrap();
+        let mut epistasis = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            epistasis.add_genotype(Genotype::from_sequence(&seq), normal.sample(&mut rand::thread_rng()));
+        }
+
+        let additive = FitnessLandscape::from_additive_and_epistatic(&effects, &epistasis, 0.);
+        assert_eq!(additive.landscape.len(), 1 << L);
+        for (g, f) in &additive.landscape {
+            assert!((f - g.iter().zip(&effects).map(|(&a, e)| a as f64 * e).sum::<f64>()).abs() < 1e-12);
+        }
+        let (fitted, residuals) = additive.split_into_additive_and_epistatic();
+        assert!(fitted.iter().zip(&effects).all(|(a, b)| (a - b).abs() < 1e-9));
+        assert!(residuals.landscape.values().all(|r| r.abs() < 1e-9));
+
+        let full = FitnessLandscape::from_additive_and_epistatic(&effects, &epistasis, 1.);
+        let (fitted, residuals) = full.split_into_additive_and_epistatic();
+        let recovered = FitnessLandscape::from_additive_and_epistatic(&fitted, &residuals, 1.);
+        for (g, f) in &full.landscape {
+            assert!((recovered.get(g).unwrap() - f).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn log_fitness_flux() {
+        const L: usize = 4;
+        let normal = Normal::new(0_f64, 1.).unwrap();
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        for seq in possible_sequences::<L>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), normal.sample(&mut rand::thread_rng()).exp());
+        }
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::Binomial(0.5));
+
+        // A stationary population on an unchanging landscape
+        assert!(fitness_flux(&landscape, &landscape, &population, 1.).abs() < 1e-12);
+        let trajectory = vec![(landscape.clone(), population.clone()); 5];
+        assert!(integrated_fitness_flux(&trajectory).abs() < 1e-12);
+
+        // Halving the fitness of all genotypes in two generations
+        let mut worse = landscape.clone();
+        worse.normalize(2.);
+        let flux = fitness_flux(&landscape, &worse, &population, 2.);
+        assert!((flux + 2_f64.ln() / 2.).abs() < 1e-12);
+        let trajectory = vec![(landscape.clone(), population.clone()), (worse.clone(), population.clone()), (worse, population)];
+        assert!((integrated_fitness_flux(&trajectory) + 2_f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn permutations() {
+        const L: usize = 6;
+        let mut rng = rand::thread_rng();
+        let mut flat = FitnessLandscape::<L>::new(FitnessType::Additive);
+        let mut additive = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            flat.add_genotype(g, 1.);
+            additive.add_genotype(g, seq.iter().enumerate().map(|(i, &a)| a as f64 * (i + 1) as f64).sum());
+        }
+
+        // Permuting equal fitnesses changes nothing, and ties are never maxima
+        assert_eq!(flat.randomize_fitnesses(&mut rng).landscape, flat.landscape);
+        assert_eq!(permutation_test_maxima(&flat, 10, &mut rng), 1.);
+
+        let randomized = additive.randomize_fitnesses(&mut rng);
+        let mut values: Vec<f64> = randomized.landscape.values().copied().collect();
+        let mut original: Vec<f64> = additive.landscape.values().copied().collect();
+        values.sort_by(f64::total_cmp);
+        original.sort_by(f64::total_cmp);
+        assert_eq!(values, original);
+        assert!(randomized.maxima().len() > 1);
+        assert_eq!(permutation_test_maxima(&additive, 20, &mut rng), 1.);
+        assert!(permutation_test_maxima(&additive, 0, &mut rng).is_nan());
+    }
+
+    #[test]
+    fn mutation_flux() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for seq in possible_sequences::<L>() {
+            landscape.add_genotype(Genotype::from_sequence(&seq), seq.iter().enumerate().map(|(i, &a)| a as f64 * (i + 1) as f64).sum());
+        }
+        let (mu, top, wildtype) = (0.03, Genotype::<L>::from_sequence(&[1; L]), Genotype::<L>::new());
+        assert_eq!(landscape.outgoing_flux(&top, mu), 0.);
+        assert!((landscape.incoming_flux(&top, mu) - 0.06).abs() < 1e-12);
+        assert!((landscape.outgoing_flux(&wildtype, mu) - 0.06).abs() < 1e-12);
+        assert_eq!(landscape.incoming_flux(&wildtype, mu), 0.);
+
+        let edges = landscape.mutation_network_weight(mu);
+        assert_eq!(edges.len(), L << (L - 1));
+        for g in landscape.sorted_genotypes() {
+            let outgoing: f64 = edges.iter().filter(|(source, _, _)| *source == g).map(|(_, _, w)| w).sum();
+            let incoming: f64 = edges.iter().filter(|(_, target, _)| *target == g).map(|(_, _, w)| w).sum();
+            assert!((outgoing - landscape.outgoing_flux(&g, mu)).abs() < 1e-12);
+            assert!((incoming - landscape.incoming_flux(&g, mu)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn k_peaks() {
+        const L: usize = 6;
+        let mut rng = rand::thread_rng();
+        for k in [5, 9, 12] {
+            assert_eq!(FitnessLandscape::<L>::with_exactly_k_peaks(k, &mut rng).unwrap().maxima().len(), k);
+        }
+        // A single maximum is too rare in a House of Cards landscape with 8 loci
+        assert!(FitnessLandscape::<8>::with_exactly_k_peaks(1, &mut rng).is_none());
+        let landscape = FitnessLandscape::<L>::k_peak_approx(3, 0.1, &mut rng);
+        assert_eq!(landscape.landscape.len(), 1 << L);
+        assert!(landscape.maxima().len().abs_diff(3) <= 1);
+    }
+    #[test]
+    fn effective_number_of_peaks() {
+        const L: usize = 10;
+        let mut flat = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        flat.extend(possible_sequences::<L>().iter().map(|seq| (Genotype::from_sequence(seq), 1.3)));
+        assert!((flat.fitness_entropy() - (L as f64) * 2_f64.ln()).abs() < 1e-9);
+        assert!((flat.effective_number_of_peaks() - (1 << L) as f64).abs() < 1e-6);
+
+        let mut delta = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        delta.extend(possible_sequences::<L>().iter().map(|seq| (Genotype::from_sequence(seq), 0.)));
+        delta.add_genotype(Genotype::from_index(17), 2.);
+        assert_eq!(delta.fitness_entropy(), 0.);
+        assert_eq!(delta.effective_number_of_peaks(), 1.);
+        assert!(FitnessLandscape::<L>::new(FitnessType::Additive).effective_number_of_peaks().is_nan());
+
+        // House of Cards landscape with log-fitnesses of unit variance
+        let normal = Normal::new(0., 1.).unwrap();
+        let mut hoc = FitnessLandscape::<L>::new(FitnessType::Additive);
+        hoc.extend(possible_sequences::<L>().iter().map(|seq| (Genotype::from_sequence(seq), normal.sample(&mut rand::thread_rng()))));
+        let expected = (1 << L) as f64 * (-hoc.mean_var().1 / 2.).exp();
+        assert!((hoc.effective_number_of_peaks() / expected - 1.).abs() < 0.15);
+    }
+}
@@ -1,6 +1,20 @@
-use super::math::linear_algebra::{SquareMatrix, Vector};
+use super::{
+    math::linear_algebra::{SquareMatrix, Vector},
+    parameters::Parameters
+};
 
-#[derive(Copy, Clone, Debug)]
+use clap::{ArgMatches, ErrorKind, value_t, values_t};
+use rand::Rng;
+
+use std::{
+    fmt,
+    str::FromStr,
+    error::Error
+};
+
+use serde::{Serialize, Deserialize};
+
+#[derive(Clone, Debug, PartialEq)]
 pub enum FitnessModel<const S: usize> {
     HoC{
         cb: SquareMatrix<S>
@@ -13,6 +27,26 @@ pub enum FitnessModel<const S: usize> {
         mu: Vector<S>,
         ca: SquareMatrix<S>,
         cb: SquareMatrix<S>
+    },
+    /// Epistasis restricted to an interaction network: the phenotype is a sum over loci of random
+    /// contributions that depend on the allele at the locus and at the loci in `adjacency[i]`
+    NetworkEpistasis {
+        adjacency: Vec<Vec<usize>>,
+        noise_sd: f64
+    },
+    /// Phenotypes read from a landscape file in the format written by
+    /// `ResourceBasedFitnessLandscape::save`, in CBOR or JSON
+    Empirical {
+        path: String
+    },
+    /// Single peak at the `optimum` with House of Cards noise: each component of the phenotype is
+    /// `-a d(g, optimum)` plus N(0, noise_sd²) noise, so that the uptake rates decay exponentially
+    /// with the Hamming distance to the optimum. An empty optimum stands for the genotype
+    /// carrying the derived allele at every locus.
+    MountFuji {
+        a: f64,
+        optimum: Vec<u8>,
+        noise_sd: f64
     }
 }
 
@@ -20,18 +54,8 @@ impl<const S: usize> FitnessModel<S> {
     pub fn new_hoc(params: Vec<f64>) -> Self {
         let cb_diagonal    = params[0];
         let cb_offdiagonal = params[1];
-        let mut cb = [[0_f64; S]; S];
-        for i in 0..S {
-            for j in 0..S {
-                if i == j {
-                    cb[i][i] = cb_diagonal;
-                } else {
-                    cb[i][j] = cb_offdiagonal;
-                }
-            }
-        }
         FitnessModel::HoC {
-            cb: SquareMatrix::from(cb)
+            cb: SquareMatrix::from_diagonal_and_off_diagonal(cb_diagonal, cb_offdiagonal)
         }
     }
 
@@ -40,19 +64,17 @@ impl<const S: usize> FitnessModel<S> {
 
         let ca_diagonal    = params[1];
         let ca_offdiagonal = params[2];
-
-        let mut ca = [[0_f64; S]; S];
-        for i in 0..S {
-            for j in 0..S {
-                if i == j {
-                    ca[i][i] = ca_diagonal;
-                } else {
-                    ca[i][j] = ca_offdiagonal;
-                }
-            }
-        }
         FitnessModel::Additive {
-            mu, ca: SquareMatrix::from(ca)
+            mu, ca: SquareMatrix::from_diagonal_and_off_diagonal(ca_diagonal, ca_offdiagonal)
+        }
+    }
+
+    /// Creates a Mount Fuji model whose optimum carries the derived allele at every locus
+    pub fn new_mf(params: Vec<f64>) -> Self {
+        FitnessModel::MountFuji {
+            a: params[0],
+            optimum: Vec::new(),
+            noise_sd: params[1]
         }
     }
 
@@ -65,33 +87,13 @@ impl<const S: usize> FitnessModel<S> {
         let cb_offdiagonal = params[4];
 
         let ca = if ca_diagonal > 0. {
-            let mut ca = [[0_f64; S]; S];
-            for i in 0..S {
-                for j in 0..S {
-                    if i == j {
-                        ca[i][i] = ca_diagonal;
-                    } else {
-                        ca[i][j] = ca_offdiagonal;
-                    }
-                }
-            }
-            SquareMatrix::from(ca)
+            SquareMatrix::from_diagonal_and_off_diagonal(ca_diagonal, ca_offdiagonal)
         } else {
             SquareMatrix::<S>::Null
         };
 
         let cb = if cb_diagonal > 0. {
-            let mut cb = [[0_f64; S]; S];
-            for i in 0..S {
-                for j in 0..S {
-                    if i == j {
-                        cb[i][i] = cb_diagonal;
-                    } else {
-                        cb[i][j] = cb_offdiagonal;
-                    }
-                }
-            }
-            SquareMatrix::from(cb)
+            SquareMatrix::from_diagonal_and_off_diagonal(cb_diagonal, cb_offdiagonal)
         } else {
             SquareMatrix::<S>::Null
         };
@@ -100,6 +102,36 @@ impl<const S: usize> FitnessModel<S> {
             mu, ca, cb
         }
     }
+
+    /// Returns a copy of the model with independent N(0, noise_sd²) noise added to each entry of
+    /// mu and to each entry on or below the diagonal of ca and cb, which is mirrored above it so
+    /// that the covariance matrices stay symmetric, e.g., to study the sensitivity of the results
+    /// to uncertain parameter estimates. Null matrices, i.e., missing components, are kept. Returns
+    /// an error if a perturbed covariance matrix is not positive-definite. Models without these
+    /// parameters are returned unchanged.
+    pub fn with_parameter_noise(&self, noise_sd: f64, rng: &mut impl Rng) -> Result<Self, String> {
+        let perturb = |name: &str, m: &SquareMatrix<S>, rng: &mut _| -> Result<SquareMatrix<S>, String> {
+            if m.is_null() { return Ok(*m) }
+            let perturbed = m.add_symmetric_noise(noise_sd, rng);
+            match perturbed.cholesky() {
+                Ok(_)  => Ok(perturbed),
+                Err(_) => Err(format!("{} is not positive-definite after adding noise with standard deviation {}", name, noise_sd))
+            }
+        };
+        Ok(match self {
+            Self::HoC {cb} => Self::HoC { cb: perturb("cb", cb, rng)? },
+            Self::Additive {mu, ca} => {
+                let ca = perturb("ca", ca, rng)?;
+                Self::Additive { mu: mu.add_noise(noise_sd, rng), ca }
+            },
+            Self::RoughMountFuji {mu, ca, cb} => {
+                let (ca, cb) = (perturb("ca", ca, rng)?, perturb("cb", cb, rng)?);
+                Self::RoughMountFuji { mu: mu.add_noise(noise_sd, rng), ca, cb }
+            },
+            Self::NetworkEpistasis {..} | Self::Empirical {..} | Self::MountFuji {..} => self.clone()
+        })
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut r = Vec::<u8>::new();
         match self {
@@ -118,6 +150,18 @@ impl<const S: usize> FitnessModel<S> {
                 r.extend(mu.to_bytes());
                 r.push(2);
             }
+            Self::NetworkEpistasis {adjacency, noise_sd} => {
+                r.extend(serde_cbor::to_vec(&(adjacency, noise_sd)).unwrap());
+                r.push(3);
+            }
+            Self::Empirical {path} => {
+                r.extend(serde_cbor::to_vec(path).unwrap());
+                r.push(4);
+            }
+            Self::MountFuji {a, optimum, noise_sd} => {
+                r.extend(serde_cbor::to_vec(&(a, optimum, noise_sd)).unwrap());
+                r.push(5);
+            }
         }
         r
     }
@@ -140,11 +184,147 @@ impl<const S: usize> FitnessModel<S> {
                     mu: Vector::<S>::from_bytes(&vec[2*(S*S*8+1)..(vec.len()-1)]).unwrap()
                 }
             }
+            Some(&3) => {
+                let (adjacency, noise_sd) = serde_cbor::from_slice(&vec[..(vec.len()-1)]).unwrap();
+                Self::NetworkEpistasis { adjacency, noise_sd }
+            }
+            Some(&4) => Self::Empirical {
+                path: serde_cbor::from_slice(&vec[..(vec.len()-1)]).unwrap()
+            },
+            Some(&5) => {
+                let (a, optimum, noise_sd) = serde_cbor::from_slice(&vec[..(vec.len()-1)]).unwrap();
+                Self::MountFuji { a, optimum, noise_sd }
+            }
             Some(&_) => panic!("Model type not recognized"),
             None     => panic!("Could not load fitness model: empty vector")
         }
     }
 
+    /// Creates a model from its name (HoC, additive, RMF or MF) and parameters, checking that the
+    /// number of parameters is the one required by the model
+    pub fn from_name_and_params(name: &str, params: &[f64]) -> Result<Self, String> {
+        let (n_params, constructor): (usize, fn(Vec<f64>) -> Self) = match name {
+            "HoC"      => (2, Self::new_hoc),
+            "additive" => (3, Self::new_additive),
+            "RMF"      => (5, Self::new_rmf),
+            "MF"       => (2, Self::new_mf),
+            _ => return Err(format!("unknown model {}, expected one of HoC, additive, RMF or MF", name))
+        };
+        if params.len() != n_params {
+            return Err(format!("the {} model requires {} parameters but {} were given", name, n_params, params.len()))
+        }
+        Ok(constructor(params.to_vec()))
+    }
+
+    /// Creates the model given in the command line by the arguments HoC, additive, RMF, MF, network
+    /// or empirical, drawing the noise of the parameters given by model_noise from `rng`
+    pub fn from_clap_matches(matches: &ArgMatches, rng: &mut impl Rng) -> Result<Self, clap::Error> {
+        if let Some(path) = matches.value_of("empirical") {
+            return Ok(Self::Empirical { path: path.to_string() })
+        }
+        if let Some(values) = matches.values_of("network") {
+            let values: Vec<&str> = values.collect();
+            let adjacency = Parameters::<S>::from_adjacency_file(values[0]).map_err(|e| {
+                clap::Error::with_description(&format!("could not read {}: {}", values[0], e), ErrorKind::InvalidValue)
+            })?;
+            let noise_sd = values[1].parse::<f64>().map_err(|_| {
+                clap::Error::with_description(&format!("invalid noise_sd {}", values[1]), ErrorKind::InvalidValue)
+            })?;
+            return Ok(Self::NetworkEpistasis { adjacency, noise_sd })
+        }
+
+        let name = ["HoC", "additive", "RMF", "MF"].into_iter()
+                                               .find(|&name| matches.is_present(name))
+                                               .ok_or_else(|| clap::Error::with_description(
+                                                   "no fitness model was given", ErrorKind::MissingRequiredArgument
+                                               ))?;
+        let params = values_t!(matches.values_of(name), f64)?;
+        let model = Self::from_name_and_params(name, &params).map_err(|e| {
+            clap::Error::with_description(&e, ErrorKind::WrongNumberOfValues)
+        })?;
+        if matches.value_of("model_noise").is_none() {
+            return Ok(model)
+        }
+        let noise_sd = value_t!(matches.value_of("model_noise"), f64)?;
+        if !(noise_sd >= 0. && noise_sd.is_finite()) {
+            return Err(clap::Error::with_description(
+                &format!("the model noise must be a non-negative number, got {}", noise_sd), ErrorKind::InvalidValue
+            ))
+        }
+        model.with_parameter_noise(noise_sd, rng).map_err(|e| clap::Error::with_description(&e, ErrorKind::InvalidValue))
+    }
+
+    /// Returns the model as a JSON object with the full mean vectors and covariance matrices, e.g.,
+    /// `{"type":"HoC","cb":[[0.1,0.05],[0.05,0.1]]}`, where a Null matrix is written as `[]`
+    pub fn to_json(&self) -> String {
+        let config = match self {
+            Self::HoC {cb} => {
+                FitnessModelConfig::HoC { cb: Some(Self::rows(cb)), cb_diag: None, cb_offdiag: None }
+            },
+            Self::Additive {mu, ca} => {
+                FitnessModelConfig::Additive {
+                    mu: MeanConfig::Full(mu.to_vec()),
+                    ca: Some(Self::rows(ca)), ca_diag: None, ca_offdiag: None
+                }
+            },
+            Self::RoughMountFuji {mu, ca, cb} => {
+                FitnessModelConfig::RoughMountFuji {
+                    mu: MeanConfig::Full(mu.to_vec()),
+                    ca: Some(Self::rows(ca)), ca_diag: None, ca_offdiag: None,
+                    cb: Some(Self::rows(cb)), cb_diag: None, cb_offdiag: None
+                }
+            },
+            Self::NetworkEpistasis {adjacency, noise_sd} => {
+                FitnessModelConfig::NetworkEpistasis { adjacency: adjacency.clone(), noise_sd: *noise_sd }
+            }
+            Self::Empirical {path} => {
+                FitnessModelConfig::Empirical { path: path.clone() }
+            }
+            Self::MountFuji {a, optimum, noise_sd} => {
+                FitnessModelConfig::MountFuji { a: *a, optimum: optimum.clone(), noise_sd: *noise_sd }
+            }
+        };
+        serde_json::to_string(&config).unwrap()
+    }
+
+    /// Returns the rows of a covariance matrix, with no rows for the Null matrix
+    fn rows(m: &SquareMatrix<S>) -> Vec<Vec<f64>> {
+        match m {
+            SquareMatrix::Null       => Vec::new(),
+            SquareMatrix::NonNull(m) => m.iter().map(|row| row.to_vec()).collect()
+        }
+    }
+
+    /// Returns the covariance matrix with the given rows, the Null matrix if there are none
+    fn from_rows(rows: Vec<Vec<f64>>) -> Result<SquareMatrix<S>, ParseError> {
+        if rows.is_empty() {
+            return Ok(SquareMatrix::Null)
+        }
+        if rows.len() != S || rows.iter().any(|row| row.len() != S) {
+            return Err(ParseError(format!("expected a {}x{} covariance matrix", S, S)))
+        }
+        let mut m = [[0_f64; S]; S];
+        for (i, row) in rows.into_iter().enumerate() {
+            m[i].copy_from_slice(&row);
+        }
+        Ok(SquareMatrix::from(m))
+    }
+
+    /// Returns the covariance matrix given either by its rows or by its diagonal and off-diagonal
+    /// entries, using `compact` to build it in the second case
+    fn covariance(
+        name: &str,
+        rows: Option<Vec<Vec<f64>>>,
+        diag_offdiag: (Option<f64>, Option<f64>),
+        compact: impl Fn(f64, f64) -> SquareMatrix<S>
+    ) -> Result<SquareMatrix<S>, ParseError> {
+        match (rows, diag_offdiag) {
+            (Some(rows), (None, None))      => Self::from_rows(rows),
+            (None, (Some(diag), Some(off))) => Ok(compact(diag, off)),
+            _ => Err(ParseError(format!("expected either {0} or {0}_diag and {0}_offdiag", name)))
+        }
+    }
+
     fn t(a: f64) -> f64 {
         (a * 100_000.).trunc() / 100_000.
     }
@@ -169,6 +349,22 @@ impl<const S: usize> FitnessModel<S> {
                     S, Self::t(mu[0]), Self::t(ca[(0, 0)]), Self::t(ca[(0, 1)]), Self::t(cb[(0, 0)]), Self::t(cb[(0, 1)])
                 )
             }
+            Self::NetworkEpistasis {adjacency, noise_sd} => {
+                format!(
+                    "network_S{}_sd{:.5}_e{}",
+                    S, Self::t(*noise_sd), adjacency.iter().map(|a| a.len()).sum::<usize>()
+                )
+            }
+            Self::Empirical {path} => {
+                let stem = std::path::Path::new(path).file_stem().map_or(path.clone(), |s| s.to_string_lossy().into_owned());
+                format!("empirical_S{}_{}", S, stem)
+            }
+            Self::MountFuji {a, optimum, noise_sd} => {
+                format!(
+                    "MF_S{}_a{:.5}_sd{:.5}_o{}",
+                    S, Self::t(*a), Self::t(*noise_sd), optimum.iter().map(|x| x.to_string()).collect::<String>()
+                )
+            }
         }
     }
 }
@@ -208,3 +404,223 @@ impl<const S: usize> ::std::convert::From<&str> for FitnessModel<S> {
         }
     }
 }
+
+/// Mean of the additive effects as written in JSON, either the same for all resources or one value
+/// per resource
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum MeanConfig {
+    Uniform(f64),
+    Full(Vec<f64>)
+}
+
+impl MeanConfig {
+    fn to_vector<const S: usize>(&self) -> Result<Vector<S>, ParseError> {
+        match self {
+            Self::Uniform(mu) => Ok(Vector::from([*mu; S])),
+            Self::Full(mu) if mu.len() == S => Ok(Vector::from_vec(mu)),
+            Self::Full(mu) => Err(ParseError(format!("expected {} values of mu, found {}", S, mu.len())))
+        }
+    }
+}
+
+/// Parameters of a fitness model as written in JSON, with the model given by the field `type`.
+/// Each covariance matrix is given either by its rows, e.g., `cb`, or, with the same value for
+/// all resources, by its diagonal and off-diagonal entries, e.g., `cb_diag` and `cb_offdiag`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+enum FitnessModelConfig {
+    HoC {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cb: Option<Vec<Vec<f64>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cb_diag: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cb_offdiag: Option<f64>
+    },
+    Additive {
+        mu: MeanConfig,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca: Option<Vec<Vec<f64>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca_diag: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca_offdiag: Option<f64>
+    },
+    #[serde(rename = "RMF")]
+    RoughMountFuji {
+        mu: MeanConfig,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca: Option<Vec<Vec<f64>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca_diag: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ca_offdiag: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cb: Option<Vec<Vec<f64>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cb_diag: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cb_offdiag: Option<f64>
+    },
+    #[serde(rename = "network")]
+    NetworkEpistasis {
+        adjacency: Vec<Vec<usize>>,
+        noise_sd: f64
+    },
+    #[serde(rename = "empirical")]
+    Empirical {
+        path: String
+    },
+    #[serde(rename = "MF")]
+    MountFuji {
+        a: f64,
+        optimum: Vec<u8>,
+        noise_sd: f64
+    }
+}
+
+/// Error returned when a fitness model can not be parsed
+#[derive(Debug)]
+pub struct ParseError(String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not parse the fitness model: {}", self.0)
+    }
+}
+
+impl Error for ParseError {}
+
+impl<const S: usize> FromStr for FitnessModel<S> {
+    type Err = ParseError;
+
+    /// Parses a fitness model from a JSON object, e.g.,
+    /// `{"type":"RMF","mu":0.1,"ca_diag":0.5,"ca_offdiag":0.1,"cb_diag":0.3,"cb_offdiag":0.05}`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let config: FitnessModelConfig = serde_json::from_str(s).map_err(|e| ParseError(e.to_string()))?;
+        Ok(match config {
+            FitnessModelConfig::HoC { cb, cb_diag, cb_offdiag } => Self::HoC {
+                cb: Self::covariance("cb", cb, (cb_diag, cb_offdiag), SquareMatrix::from_diagonal_and_off_diagonal)?
+            },
+            FitnessModelConfig::Additive { mu, ca, ca_diag, ca_offdiag } => Self::Additive {
+                mu: mu.to_vector()?,
+                ca: Self::covariance("ca", ca, (ca_diag, ca_offdiag), SquareMatrix::from_diagonal_and_off_diagonal)?
+            },
+            FitnessModelConfig::RoughMountFuji { mu, ca, ca_diag, ca_offdiag, cb, cb_diag, cb_offdiag } => {
+                // As in new_rmf, a component with no variance is missing
+                let compact = |diag: f64, off: f64| if diag > 0. {
+                    SquareMatrix::from_diagonal_and_off_diagonal(diag, off)
+                } else {
+                    SquareMatrix::Null
+                };
+                Self::RoughMountFuji {
+                    mu: mu.to_vector()?,
+                    ca: Self::covariance("ca", ca, (ca_diag, ca_offdiag), compact)?,
+                    cb: Self::covariance("cb", cb, (cb_diag, cb_offdiag), compact)?
+                }
+            },
+            FitnessModelConfig::NetworkEpistasis { adjacency, noise_sd } => {
+                Self::NetworkEpistasis { adjacency, noise_sd }
+            }
+            FitnessModelConfig::Empirical { path } => {
+                Self::Empirical { path }
+            }
+            FitnessModelConfig::MountFuji { a, optimum, noise_sd } => {
+                Self::MountFuji { a, optimum, noise_sd }
+            }
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let models = [
+            FitnessModel::<2>::new_hoc(vec![0.1, 0.05]),
+            FitnessModel::<2>::new_additive(vec![0.1, 0.5, 0.1]),
+            FitnessModel::<2>::new_rmf(vec![0.1, 0.5, 0.1, 0.3, 0.05]),
+            FitnessModel::<2>::new_rmf(vec![0.1, 0., 0., 0.3, 0.05]),
+            FitnessModel::<2>::NetworkEpistasis { adjacency: vec![vec![1], vec![0, 2], vec![1]], noise_sd: 0.1 },
+            FitnessModel::<2>::Empirical { path: "landscapes/measured.json".to_string() },
+            FitnessModel::<2>::MountFuji { a: 0.5, optimum: vec![1, 0, 1], noise_sd: 0.1 }
+        ];
+        for model in &models {
+            assert_eq!(&model.to_json().parse::<FitnessModel<2>>().unwrap(), model);
+            assert_eq!(&FitnessModel::<2>::from_bytes(&model.to_bytes()), model);
+        }
+
+        let rmf: FitnessModel<2> = r#"{"type":"RMF","mu":0.1,"ca_diag":0.5,"ca_offdiag":0.1,"cb_diag":0.3,"cb_offdiag":0.05}"#.parse().unwrap();
+        assert_eq!(rmf, models[2]);
+        assert_eq!(models[0].to_json(), r#"{"type":"HoC","cb":[[0.1,0.05],[0.05,0.1]]}"#);
+
+        // Noisy parameters differ between resources and are kept in full
+        let noisy = models[2].with_parameter_noise(0.01, &mut rand::thread_rng()).unwrap();
+        assert_eq!(noisy.to_json().parse::<FitnessModel<2>>().unwrap(), noisy);
+
+        let error = r#"{"type":"HoC","cb":[[0.1,0.05]]}"#.parse::<FitnessModel<2>>().unwrap_err();
+        assert!(error.to_string().contains("2x2"));
+        assert!(r#"{"type":"HoC","cb_diag":0.1}"#.parse::<FitnessModel<2>>().is_err());
+
+        let error = r#"{"type":"NK","k":2}"#.parse::<FitnessModel<2>>().unwrap_err();
+        assert!(error.to_string().contains("NK"));
+    }
+
+    #[test]
+    fn from_name_and_params() {
+        assert_eq!(
+            FitnessModel::<2>::from_name_and_params("HoC", &[0.1, 0.05]),
+            Ok(FitnessModel::<2>::new_hoc(vec![0.1, 0.05]))
+        );
+        assert_eq!(
+            FitnessModel::<2>::from_name_and_params("RMF", &[0.1, 0.5, 0.1, 0.3, 0.05]),
+            Ok(FitnessModel::<2>::new_rmf(vec![0.1, 0.5, 0.1, 0.3, 0.05]))
+        );
+        assert!(FitnessModel::<2>::from_name_and_params("HoC", &[0.1]).is_err());
+        assert!(FitnessModel::<2>::from_name_and_params("additive", &[0.1, 0.5, 0.1, 0.3]).is_err());
+        assert_eq!(
+            FitnessModel::<2>::from_name_and_params("MF", &[1., 0.1]),
+            Ok(FitnessModel::<2>::MountFuji { a: 1., optimum: vec![], noise_sd: 0.1 })
+        );
+        assert!(FitnessModel::<2>::from_name_and_params("NK", &[0.1]).is_err());
+    }
+
+    #[test]
+    fn parameter_noise() {
+        use crate::modules::{
+            genotype::Genotype,
+            multidimensional_rough_mount_fuji::MultidimensionalRoughMountFuji,
+            math::statistics
+        };
+        let mut rng = rand::thread_rng();
+        let rmf = FitnessModel::<2>::new_rmf(vec![0.1, 0., 0., 0.3, 0.05]);
+        assert_eq!(rmf.with_parameter_noise(0., &mut rng), Ok(rmf.clone()));
+        match rmf.with_parameter_noise(0.01, &mut rng).unwrap() {
+            FitnessModel::RoughMountFuji { mu, ca, cb } => {
+                assert!(ca.is_null() && mu != Vector::from([0.1; 2]));
+                assert!((cb.get(0, 0) - 0.3).abs() < 0.1 && cb != SquareMatrix::from_diagonal_and_off_diagonal(0.3, 0.05));
+                assert_eq!(cb.get(0, 1), cb.get(1, 0));
+            },
+            _ => panic!("the model type changed")
+        }
+
+        // Landscapes dominated by the additive component are ordered by the number of derived
+        // alleles with or without noise
+        const L: usize = 6;
+        let additive = FitnessModel::<2>::new_additive(vec![1., 0.01, 0.]);
+        let original = MultidimensionalRoughMountFuji::<L, 2>::new(additive.clone());
+        let perturbed = MultidimensionalRoughMountFuji::<L, 2>::new(additive.with_parameter_noise(0.001, &mut rng).unwrap());
+        let mut genotypes: Vec<Genotype<L>> = original.phenotype_map().keys().copied().collect();
+        genotypes.sort_unstable();
+        let (x, y): (Vec<f64>, Vec<f64>) = genotypes.iter().map(|&g| (original[g][0], perturbed[g][0])).unzip();
+        assert!(statistics::spearman(&x, &y) > 0.);
+
+        // Noise much larger than the variances gives covariance matrices that are not positive-definite
+        let hoc = FitnessModel::<2>::new_hoc(vec![1e-6, 0.]);
+        assert!((0..10).any(|_| hoc.with_parameter_noise(1., &mut rng).is_err()));
+    }
+}
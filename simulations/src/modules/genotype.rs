@@ -2,7 +2,7 @@ use std::{
     ops::Index,
     fmt::{self, Write}
 };
-use rand::Rng;
+use rand::{Rng, seq::IteratorRandom};
 
 /// Returns the number of genotypes in a landscape with L biallelic loci
 pub const fn landscape_size<const L: usize>() -> usize {
@@ -52,7 +52,11 @@ impl<const L: usize> Genotype<L> {
     /// Creates a new genotype with all alleles set to zero.
     #[inline]
     pub fn random() -> Self {
-        let mut rng = rand::thread_rng();
+        Self::random_with_rng(&mut rand::thread_rng())
+    }
+
+    /// Creates a new random genotype using the given random number generator.
+    pub fn random_with_rng<R: Rng>(rng: &mut R) -> Self {
         let side = rand::distributions::Uniform::new(0u8, 2u8);
 
         let mut seq = [0u8; L];
@@ -62,6 +66,22 @@ impl<const L: usize> Genotype<L> {
         Genotype { seq }
     }
 
+    /// Creates a genotype that differs from `anchor` in `d` loci chosen uniformly at random.
+    /// Returns None if `d` is larger than the number of loci.
+    pub fn random_at_distance(anchor: &Genotype<L>, d: usize, rng: &mut impl Rng) -> Option<Self> {
+        if d > L { return None }
+        let mut g = *anchor;
+        for i in (0..L).choose_multiple(rng, d) {
+            g.mutate(i);
+        }
+        Some(g)
+    }
+
+    /// Creates a random genotype with exactly `d` derived alleles
+    pub fn random_in_hamming_class(d: usize, rng: &mut impl Rng) -> Self {
+        Self::random_at_distance(&Self::new(), d, rng).expect("the Hamming class is larger than the number of loci")
+    }
+
     pub fn from_index(index: usize) -> Self {
         let mut seq = [0u8; L];
         for i in 0..L {
@@ -83,6 +103,11 @@ impl<const L: usize> Genotype<L> {
         *self.clone().mutate(i)
     }
 
+    /// Returns an iterator over the L genotypes that differ from this one at a single locus
+    pub fn neighbors(&self) -> impl Iterator<Item = Genotype<L>> + '_ {
+        (0..L).map(move |i| self.cmutate(i))
+    }
+
     #[inline]
     pub fn iter(&self) -> std::slice::Iter<u8> {
         self.seq.iter()
@@ -101,6 +126,24 @@ impl<const L: usize> Genotype<L> {
         self.iter().zip(g2.iter()).map(|(l1, l2)| (*l1 as i16 - *l2 as i16).abs() as usize).sum()
     }
 
+    /// Returns the Hamming distance to each of the genotypes, sorted by distance and then by
+    /// genotype index
+    pub fn hamming_distance_to_all(&self, genotypes: &[Genotype<L>]) -> Vec<(usize, Genotype<L>)> {
+        let mut distances: Vec<(usize, Genotype<L>)> = genotypes.iter().map(|g| (self.n_differences(g), *g)).collect();
+        distances.sort_by_key(|(d, g)| (*d, g.index()));
+        distances
+    }
+
+    /// Returns the `k` genotypes, other than this one, closest to it in Hamming distance, with
+    /// ties broken by genotype index
+    pub fn k_nearest_neighbors(&self, k: usize, genotypes: &[Genotype<L>]) -> Vec<Genotype<L>> {
+        self.hamming_distance_to_all(genotypes).into_iter()
+                                               .filter(|&(d, _)| d > 0)
+                                               .take(k)
+                                               .map(|(_, g)| g)
+                                               .collect()
+    }
+
     pub fn index(&self) -> usize {
         self.iter().enumerate().fold(0, |acc, (i, s)| acc + 2_usize.pow(i as u32)*(*s as usize))
     }
@@ -151,4 +194,34 @@ mod tests {
 
         assert_eq!(genotype1, genotype2);
     }
+
+    #[test]
+    fn hamming_distance() {
+        let mut rng = rand::thread_rng();
+        let anchor = Genotype::<8>::from_sequence(&[0, 1, 0, 1, 1, 0, 0, 1]);
+        for d in 0..=8 {
+            let g = Genotype::random_at_distance(&anchor, d, &mut rng).unwrap();
+            assert_eq!(g.n_differences(&anchor), d);
+            assert_eq!(Genotype::<8>::random_in_hamming_class(d, &mut rng).sum(), d);
+        }
+        assert_eq!(Genotype::random_at_distance(&anchor, 9, &mut rng), None);
+
+        assert_eq!(anchor.neighbors().count(), 8);
+        assert!(anchor.neighbors().all(|g| g.n_differences(&anchor) == 1));
+    }
+
+    #[test]
+    fn nearest_neighbors() {
+        let genotypes: Vec<Genotype<3>> = possible_sequences::<3>().iter().map(|seq| Genotype::from_sequence(seq)).collect();
+        let wildtype = Genotype::<3>::new();
+
+        let distances = wildtype.hamming_distance_to_all(&genotypes);
+        assert_eq!(distances.iter().map(|&(d, _)| d).collect::<Vec<usize>>(), vec![0, 1, 1, 1, 2, 2, 2, 3]);
+        assert_eq!(distances[1].1, Genotype::from_sequence(&[1, 0, 0]));
+
+        let nearest = wildtype.k_nearest_neighbors(3, &genotypes);
+        assert_eq!(nearest, wildtype.neighbors().collect::<Vec<Genotype<3>>>());
+        assert_eq!(wildtype.k_nearest_neighbors(1, &genotypes), vec![Genotype::from_sequence(&[1, 0, 0])]);
+        assert_eq!(wildtype.k_nearest_neighbors(10, &genotypes).len(), 7);
+    }
 }
@@ -0,0 +1,118 @@
+/// Coefficients of the Lanczos approximation of the gamma function with g = 7
+const LANCZOS_G: f64 = 7.;
+const LANCZOS_COEFFICIENTS: [f64; 9] = [
+    0.999_999_999_999_809_9,
+    676.520_368_121_885_1,
+    -1_259.139_216_722_402_8,
+    771.323_428_777_653_1,
+    -176.615_029_162_140_6,
+    12.507_343_278_686_905,
+    -0.138_571_095_265_720_12,
+    9.984_369_578_019_572e-6,
+    1.505_632_735_149_311_6e-7
+];
+
+/// Returns the number of ways of choosing k elements out of n, computed with the multiplicative
+/// formula so that it only panics if the result itself does not fit in a u64
+pub fn binomial(n: u64, k: u64) -> u64 {
+    if k > n { return 0 }
+    let k = k.min(n - k);
+    let mut result: u128 = 1;
+    for i in 0..k as u128 {
+        // The partial products are binomial coefficients, so the division is exact
+        result = result.checked_mul(n as u128 - i).expect("binomial coefficient overflows") / (i + 1);
+    }
+    u64::try_from(result).unwrap_or_else(|_| panic!("binomial coefficient C({}, {}) overflows u64", n, k))
+}
+
+/// Returns n!, panicking if it does not fit in a u64 (n > 20)
+pub fn factorial(n: u64) -> u64 {
+    (1..=n).try_fold(1_u64, |acc, i| acc.checked_mul(i)).unwrap_or_else(|| panic!("{}! overflows u64", n))
+}
+
+/// Returns the number of ways of splitting n elements into groups of the given sizes, which must
+/// add up to n
+pub fn multinomial(n: u64, ks: &[u64]) -> u64 {
+    assert_eq!(ks.iter().sum::<u64>(), n, "the group sizes must add up to {}", n);
+    let mut remaining = n;
+    ks.iter().fold(1_u64, |acc, &k| {
+        let ways = binomial(remaining, k);
+        remaining -= k;
+        acc.checked_mul(ways).unwrap_or_else(|| panic!("multinomial coefficient of {} overflows u64", n))
+    })
+}
+
+/// Returns the logarithm of the gamma function for positive x
+fn ln_gamma(x: f64) -> f64 {
+    if x < 0.5 {
+        // Reflection formula
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1. - x)
+    }
+    let x = x - 1.;
+    let t = x + LANCZOS_G + 0.5;
+    let series = LANCZOS_COEFFICIENTS[1..].iter().enumerate()
+        .fold(LANCZOS_COEFFICIENTS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.));
+    0.5 * (2. * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Returns the logarithm of the binomial coefficient, generalized to real arguments through the
+/// gamma function, which is accurate for values far too large for `binomial`
+pub fn log_binomial(n: f64, k: f64) -> f64 {
+    ln_gamma(n + 1.) - ln_gamma(k + 1.) - ln_gamma(n - k + 1.)
+}
+
+/// Returns all subsets of k elements of {0, ..., n-1}, each sorted, in lexicographic order
+pub fn n_choose_k_list(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k > n { return Vec::new() }
+    let mut subsets = Vec::new();
+    let mut subset: Vec<usize> = (0..k).collect();
+    loop {
+        subsets.push(subset.clone());
+        // Advance the last element that is not at its largest possible value
+        let i = match (0..k).rev().find(|&i| subset[i] < n - k + i) {
+            Some(i) => i,
+            None    => return subsets
+        };
+        subset[i] += 1;
+        for j in i + 1..k {
+            subset[j] = subset[j - 1] + 1;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coefficients() {
+        assert_eq!(binomial(5, 2), 10);
+        assert_eq!(binomial(10, 0), 1);
+        assert_eq!(binomial(10, 10), 1);
+        assert_eq!(binomial(3, 4), 0);
+        assert_eq!(binomial(64, 32), 1_832_624_140_942_590_534);
+        assert_eq!((0..=20).map(|k| binomial(20, k)).sum::<u64>(), 1 << 20);
+
+        assert_eq!(factorial(0), 1);
+        assert_eq!(factorial(20), 2_432_902_008_176_640_000);
+        assert!(std::panic::catch_unwind(|| factorial(21)).is_err());
+        assert!(std::panic::catch_unwind(|| binomial(100, 50)).is_err());
+
+        assert_eq!(multinomial(4, &[2, 1, 1]), 12);
+        assert_eq!(multinomial(10, &[10]), 1);
+
+        for (n, k) in [(5, 2), (30, 7), (64, 32)] {
+            assert!((log_binomial(n as f64, k as f64) - (binomial(n, k) as f64).ln()).abs() < 1e-9);
+        }
+        assert!((log_binomial(1000., 500.) - 689.467_2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn subsets() {
+        assert_eq!(n_choose_k_list(4, 2), vec![vec![0, 1], vec![0, 2], vec![0, 3], vec![1, 2], vec![1, 3], vec![2, 3]]);
+        assert_eq!(n_choose_k_list(3, 0), vec![Vec::<usize>::new()]);
+        assert!(n_choose_k_list(2, 3).is_empty());
+        assert_eq!(n_choose_k_list(10, 4).len() as u64, binomial(10, 4));
+    }
+}
@@ -0,0 +1,42 @@
+/// Computes the unnormalized Fast Walsh-Hadamard Transform of `data` in place, in O(N log N)
+/// operations. The length of `data` must be a power of two. Applying the transform twice
+/// multiplies the original data by its length.
+pub fn fwht_in_place(data: &mut [f64]) {
+    let n = data.len();
+    assert!(n.is_power_of_two(), "the length of the data must be a power of two");
+
+    let mut h = 1;
+    while h < n {
+        for block in data.chunks_mut(2*h) {
+            let (left, right) = block.split_at_mut(h);
+            for (a, b) in left.iter_mut().zip(right.iter_mut()) {
+                let (x, y) = (*a, *b);
+                *a = x + y;
+                *b = x - y;
+            }
+        }
+        h *= 2;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fwht() {
+        let mut data = [1., 0., 0., 0.];
+        fwht_in_place(&mut data);
+        assert_eq!(data, [1., 1., 1., 1.]);
+
+        let original = [0.3, -1.2, 2.5, 0.7, 1.1, -0.4, 0.0, 3.2];
+        let mut data = original;
+        fwht_in_place(&mut data);
+        assert!((data[0] - original.iter().sum::<f64>()).abs() < 1e-12);
+        fwht_in_place(&mut data);
+        for (x, y) in data.iter().zip(original.iter()) {
+            assert!((x / original.len() as f64 - y).abs() < 1e-12);
+        }
+    }
+}
@@ -3,7 +3,10 @@ use std::{
     ops::{Index, IndexMut}
 };
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
+
+#[derive(Copy, Clone, PartialEq)]
 pub enum SquareMatrix<const S: usize> {
     Null,
     NonNull([[f64; S]; S])
@@ -15,6 +18,29 @@ impl<const S: usize> SquareMatrix<S> {
         Self::NonNull(matrix)
     }
 
+    /// Returns the identity matrix
+    pub fn identity() -> Self {
+        Self::diagonal([1.; S])
+    }
+
+    /// Returns a diagonal matrix with the given diagonal entries
+    pub fn diagonal(v: [f64; S]) -> Self {
+        let mut m = [[0_f64; S]; S];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = v[i];
+        }
+        Self::NonNull(m)
+    }
+
+    /// Returns a matrix with `diag` on the diagonal and `off` in all other entries
+    pub fn from_diagonal_and_off_diagonal(diag: f64, off: f64) -> Self {
+        let mut m = [[off; S]; S];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = diag;
+        }
+        Self::NonNull(m)
+    }
+
     #[inline]
     pub fn get(&self, i: usize, j: usize) -> f64 {
         match self {
@@ -23,6 +49,177 @@ impl<const S: usize> SquareMatrix<S> {
         }
     }
 
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        matches!(self, SquareMatrix::Null)
+    }
+
+    /// Returns the entries of the matrix, with the Null matrix given as a zero matrix
+    #[inline]
+    pub fn as_full(&self) -> [[f64; S]; S] {
+        match self {
+            SquareMatrix::Null       => [[0.; S]; S],
+            SquareMatrix::NonNull(m) => *m
+        }
+    }
+
+    /// Returns a copy of the matrix with independent N(0, sd²) noise added to each entry. The Null
+    /// matrix is perturbed as a zero matrix.
+    pub fn add_noise(&self, sd: f64, rng: &mut impl Rng) -> Self {
+        if sd == 0. {
+            return *self
+        }
+        let noise = Normal::new(0., sd).unwrap();
+        let mut m = self.as_full();
+        for x in m.iter_mut().flatten() {
+            *x += noise.sample(rng);
+        }
+        Self::NonNull(m)
+    }
+
+    /// Returns a copy of the matrix with independent N(0, sd²) noise added to each entry on or
+    /// below the diagonal and mirrored above it, so that symmetric matrices, e.g., covariance
+    /// matrices, stay symmetric. The Null matrix is perturbed as a zero matrix.
+    pub fn add_symmetric_noise(&self, sd: f64, rng: &mut impl Rng) -> Self {
+        if sd == 0. {
+            return *self
+        }
+        let noise = Normal::new(0., sd).unwrap();
+        let mut lower = [[0.; S]; S];
+        for (i, row) in lower.iter_mut().enumerate() {
+            row[..=i].iter_mut().for_each(|x| *x = noise.sample(rng));
+        }
+        let mut m = self.as_full();
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x += lower[i.max(j)][i.min(j)];
+            }
+        }
+        Self::NonNull(m)
+    }
+
+    /// Returns the lower triangular matrix L such that L L^T is this matrix, computed with the
+    /// Cholesky–Banachiewicz algorithm, or an error if the matrix is not positive-definite
+    pub fn cholesky(&self) -> Result<Self, &'static str> {
+        let matrix = match self {
+            Self::Null       => return Ok(Self::Null),
+            Self::NonNull(m) => m
+        };
+        let mut l_matrix = [[0.; S]; S];
+        for i in 0..S {
+            for j in 0..=i {
+                let sum = (0..j).fold(0., |acc, k| {
+                    acc + l_matrix[i][k] * l_matrix[j][k]
+                });
+
+                l_matrix[i][j] = if i == j {
+                    (matrix[i][i] - sum).sqrt()
+                } else {
+                    (matrix[i][j] - sum) / l_matrix[j][j]
+                };
+
+                if l_matrix[i][j].is_nan() {
+                    return Err("the matrix is not positive-definite")
+                }
+            }
+        }
+        Ok(Self::NonNull(l_matrix))
+    }
+
+    /// Returns the Cholesky factor of `C + v v^T` given the factor of C, in O(S²) operations. The
+    /// factor must have a positive diagonal.
+    pub fn cholesky_update(&self, v: Vector<S>) -> Self {
+        Self::NonNull(self.cholesky_rank_one(v, 1.).unwrap())
+    }
+
+    /// Returns the Cholesky factor of `C - v v^T` given the factor of C, in O(S²) operations, or
+    /// an error if `C - v v^T` is not positive-definite
+    pub fn cholesky_downdate(&self, v: Vector<S>) -> Result<Self, &'static str> {
+        Ok(Self::NonNull(self.cholesky_rank_one(v, -1.)?))
+    }
+
+    /// Applies a rank-1 update (`sign` = 1) or downdate (`sign` = -1) to the Cholesky factor
+    fn cholesky_rank_one(&self, v: Vector<S>, sign: f64) -> Result<[[f64; S]; S], &'static str> {
+        let mut l = self.as_full();
+        let mut x = v;
+        for k in 0..S {
+            let r = (l[k][k] * l[k][k] + sign * x[k] * x[k]).sqrt();
+            if r.is_nan() || r == 0. {
+                return Err("the matrix is not positive-definite")
+            }
+            let (c, s) = (r / l[k][k], x[k] / l[k][k]);
+            l[k][k] = r;
+            for i in (k+1)..S {
+                l[i][k] = (l[i][k] + sign * s * x[i]) / c;
+                x[i] = c * x[i] - s * l[i][k];
+            }
+        }
+        Ok(l)
+    }
+
+    /// Returns the sum of the diagonal entries
+    pub fn trace(&self) -> f64 {
+        (0..S).map(|i| self.get(i, i)).sum()
+    }
+
+    /// Diagonalizes a symmetric matrix with the cyclic Jacobi method. Returns the eigenvalues in
+    /// decreasing order and the corresponding normalized eigenvectors, one per row.
+    pub fn eigenvalues_symmetric(&self) -> ([f64; S], [[f64; S]; S]) {
+        let mut a = self.as_full();
+        let mut v = [[0_f64; S]; S];
+        for (i, row) in v.iter_mut().enumerate() {
+            row[i] = 1.;
+        }
+
+        for _ in 0..100 {
+            let off_diagonal: f64 = (0..S).flat_map(|i| (0..S).filter(move |&j| j != i).map(move |j| (i, j)))
+                                          .map(|(i, j)| a[i][j] * a[i][j])
+                                          .sum();
+            if off_diagonal < 1e-24 { break }
+
+            for p in 0..S {
+                for q in (p+1)..S {
+                    if a[p][q] == 0. { continue }
+
+                    // Rotation that annihilates a[p][q]
+                    let theta = (a[q][q] - a[p][p]) / (2. * a[p][q]);
+                    let t = theta.signum() / (theta.abs() + (theta * theta + 1.).sqrt());
+                    let c = 1. / (t * t + 1.).sqrt();
+                    let s = t * c;
+
+                    for k in 0..S {
+                        let (akp, akq) = (a[k][p], a[k][q]);
+                        a[k][p] = c * akp - s * akq;
+                        a[k][q] = s * akp + c * akq;
+
+                        let (vkp, vkq) = (v[k][p], v[k][q]);
+                        v[k][p] = c * vkp - s * vkq;
+                        v[k][q] = s * vkp + c * vkq;
+                    }
+                    let (upper, lower) = a.split_at_mut(q);
+                    for (apk, aqk) in upper[p].iter_mut().zip(lower[0].iter_mut()) {
+                        let (ap, aq) = (*apk, *aqk);
+                        *apk = c * ap - s * aq;
+                        *aqk = s * ap + c * aq;
+                    }
+                }
+            }
+        }
+
+        let mut order: Vec<usize> = (0..S).collect();
+        order.sort_by(|&i, &j| a[j][j].partial_cmp(&a[i][i]).unwrap());
+
+        let mut eigenvalues  = [0_f64; S];
+        let mut eigenvectors = [[0_f64; S]; S];
+        for (k, &i) in order.iter().enumerate() {
+            eigenvalues[k] = a[i][i];
+            for j in 0..S {
+                eigenvectors[k][j] = v[j][i];
+            }
+        }
+        (eigenvalues, eigenvectors)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut r = Vec::<u8>::with_capacity(S*S*8+1);
         match self {
@@ -75,19 +272,24 @@ impl<const S: usize> Index<(usize, usize)> for SquareMatrix<S> {
 }
 
 impl<const S: usize> fmt::Display for SquareMatrix<S> {
+    /// Writes the entries as a tab separated table, with the Null matrix written as zeros
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            SquareMatrix::Null       => write!(f, "Null matrix")?,
-            SquareMatrix::NonNull(m) => {
-                for i in 0..S {
-                    for j in 0..S {
-                        write!(f, "\t{}", m[i][j])?;
-                    }
-                    write!(f, "\n")?;
-                }
+        for row in self.as_full() {
+            for x in row {
+                write!(f, "\t{:?}", x)?;
             }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<const S: usize> fmt::Debug for SquareMatrix<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SquareMatrix::Null       => write!(f, "SquareMatrix::Null"),
+            SquareMatrix::NonNull(m) => write!(f, "SquareMatrix::NonNull({:?})", m)
         }
-        write!(f, "")
     }
 }
 
@@ -141,6 +343,33 @@ impl<const S: usize> Vector<S> {
             None => Err("Empty stream".to_string())
         }
     }
+    /// Returns a copy of the vector with independent N(0, sd²) noise added to each entry
+    pub fn add_noise(&self, sd: f64, rng: &mut impl Rng) -> Self {
+        if sd == 0. {
+            return *self
+        }
+        let noise = Normal::new(0., sd).unwrap();
+        let mut v = *self;
+        for x in v.iter_mut() {
+            *x += noise.sample(rng);
+        }
+        v
+    }
+    /// Returns the outer product `v w^T`, with entries `v[i] w[j]`
+    pub fn outer_product(&self, other: &Vector<S>) -> SquareMatrix<S> {
+        let mut m = [[0_f64; S]; S];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, x) in row.iter_mut().enumerate() {
+                *x = self[i] * other[j];
+            }
+        }
+        SquareMatrix::NonNull(m)
+    }
+    /// Returns the outer product of the vector with itself, `v v^T`
+    #[inline]
+    pub fn self_outer_product(&self) -> SquareMatrix<S> {
+        self.outer_product(self)
+    }
     pub fn iter_mut(&mut self) -> impl Iterator<Item=&mut f64> {
         match self {
             Vector::NonNull(v) => v.iter_mut()
@@ -195,6 +424,16 @@ impl<const S: usize> IntoIterator for Vector<S> {
     }
 }
 
+/// Outer product of two vectors
+impl<const S: usize> std::ops::Mul<Vector<S>> for Vector<S> {
+    type Output = SquareMatrix<S>;
+
+    #[inline]
+    fn mul(self, rhs: Vector<S>) -> SquareMatrix<S> {
+        self.outer_product(&rhs)
+    }
+}
+
 
 impl<const S: usize> fmt::Display for Vector<S> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -217,6 +456,37 @@ impl<const S: usize> ::std::convert::From<[f64; S]> for Vector<S> {
     }
 }
 
+/// Solves the linear system `a x = b` by Gaussian elimination with partial pivoting, returning
+/// None if `a` is singular
+pub fn solve_linear_system(mut a: Vec<Vec<f64>>, mut b: Vec<Vec<f64>>) -> Option<Vec<Vec<f64>>> {
+    let n = a.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (pivot_a, pivot_b) = (a[col].clone(), b[col].clone());
+        for row in (col + 1)..n {
+            let factor = a[row][col] / pivot_a[col];
+            for (x, p) in a[row].iter_mut().zip(pivot_a.iter()).skip(col) {
+                *x -= factor * p;
+            }
+            for (x, p) in b[row].iter_mut().zip(pivot_b.iter()) {
+                *x -= factor * p;
+            }
+        }
+    }
+    for col in (0..n).rev() {
+        for k in 0..b[col].len() {
+            let sum: f64 = ((col + 1)..n).map(|j| a[col][j] * b[j][k]).sum();
+            b[col][k] = (b[col][k] - sum) / a[col][col];
+        }
+    }
+    Some(b)
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -238,4 +508,122 @@ mod tests {
         b_mod[7] = 1;
         assert_ne!(b, Vector::<3>::from_bytes(&b_mod).unwrap());
     }
+
+    #[test]
+    fn constructors() {
+        let identity = SquareMatrix::<3>::identity();
+        assert_eq!(identity, SquareMatrix::<3>::from_bytes(&identity.to_bytes()).unwrap());
+        assert_eq!(identity, SquareMatrix::from([[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]]));
+
+        assert_eq!(SquareMatrix::diagonal([1., 2.]), SquareMatrix::from([[1., 0.], [0., 2.]]));
+        assert_eq!(SquareMatrix::<2>::from_diagonal_and_off_diagonal(1., 0.5), SquareMatrix::from([[1., 0.5], [0.5, 1.]]));
+
+        assert_eq!(identity.trace(), 3.);
+        assert_eq!(SquareMatrix::<3>::Null.trace(), 0.);
+    }
+
+    #[test]
+    fn symmetric_noise() {
+        let mut rng = rand::thread_rng();
+        let m = SquareMatrix::<3>::from_diagonal_and_off_diagonal(1., 0.5);
+        assert_eq!(m.add_symmetric_noise(0., &mut rng), m);
+        let noisy = m.add_symmetric_noise(0.1, &mut rng);
+        assert_ne!(noisy, m);
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(noisy.get(i, j), noisy.get(j, i));
+            }
+        }
+    }
+
+    #[test]
+    fn format() {
+        let null = SquareMatrix::<2>::Null;
+        assert!(null.is_null());
+        assert_eq!(null.as_full(), [[0.; 2]; 2]);
+        assert_eq!(format!("{:?}", null), "SquareMatrix::Null");
+        assert_eq!(format!("{}", null), "\t0.0\t0.0\n\t0.0\t0.0\n");
+
+        let m = SquareMatrix::<2>::from([[1., 0.5], [0.5, 2.]]);
+        assert!(!m.is_null());
+        assert_eq!(format!("{:?}", m), "SquareMatrix::NonNull([[1.0, 0.5], [0.5, 2.0]])");
+        assert_eq!(format!("{}", m), "\t1.0\t0.5\n\t0.5\t2.0\n");
+    }
+
+    #[test]
+    fn eigenvalues_symmetric() {
+        let m = [[2., 1., 0.], [1., 3., 1.], [0., 1., 4.]];
+        let (eigenvalues, eigenvectors) = SquareMatrix::<3>::from(m).eigenvalues_symmetric();
+
+        assert!(eigenvalues[0] >= eigenvalues[1] && eigenvalues[1] >= eigenvalues[2]);
+        assert!((eigenvalues.iter().sum::<f64>() - 9.).abs() < 1e-10);
+        for (lambda, v) in eigenvalues.iter().zip(eigenvectors.iter()) {
+            for i in 0..3 {
+                let mv: f64 = (0..3).map(|j| m[i][j] * v[j]).sum();
+                assert!((mv - lambda * v[i]).abs() < 1e-10);
+            }
+        }
+
+        let (eigenvalues, _) = SquareMatrix::<3>::Null.eigenvalues_symmetric();
+        assert_eq!(eigenvalues, [0.; 3]);
+    }
+
+    #[test]
+    fn outer_product() {
+        let basis = |i: usize| {
+            let mut e = Vector::<3>::new();
+            e[i] = 1.;
+            e
+        };
+        for i in 0..3 {
+            for j in 0..3 {
+                let m = basis(i).outer_product(&basis(j));
+                for k in 0..3 {
+                    for l in 0..3 {
+                        assert_eq!(m.get(k, l), if (k, l) == (i, j) { 1. } else { 0. });
+                    }
+                }
+                assert_eq!(basis(i) * basis(j), m);
+            }
+        }
+        let v = Vector::from([1., -2., 3.]);
+        assert_eq!(v.self_outer_product(), SquareMatrix::from([[1., -2., 3.], [-2., 4., -6.], [3., -6., 9.]]));
+    }
+
+    #[test]
+    fn cholesky() {
+        let c = SquareMatrix::<3>::from([[4., 2., 0.4], [2., 5., 1.], [0.4, 1., 3.]]);
+        let l = c.cholesky().unwrap();
+        let product = |l: &SquareMatrix<3>, i: usize, j: usize| (0..3).map(|k| l.get(i, k) * l.get(j, k)).sum::<f64>();
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((product(&l, i, j) - c.get(i, j)).abs() < 1e-12);
+            }
+        }
+        assert!(SquareMatrix::<2>::from([[1., 2.], [2., 1.]]).cholesky().is_err());
+
+        let v = Vector::from([0.5, -1., 2.]);
+        let updated = l.cholesky_update(v);
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((product(&updated, i, j) - c.get(i, j) - v[i] * v[j]).abs() < 1e-12);
+            }
+        }
+        let downdated = updated.cholesky_downdate(v).unwrap();
+        for i in 0..3 {
+            for j in 0..3 {
+                assert!((downdated.get(i, j) - l.get(i, j)).abs() < 1e-12);
+            }
+        }
+        assert!(l.cholesky_downdate(Vector::from([3., 0., 0.])).is_err());
+    }
+
+    #[test]
+    fn linear_system() {
+        let a = vec![vec![2., 1.], vec![1., 3.]];
+        let x = solve_linear_system(a, vec![vec![3., 1.], vec![5., 2.]]).unwrap();
+        assert!((x[0][0] - 0.8).abs() < 1e-12 && (x[1][0] - 1.4).abs() < 1e-12);
+        assert!((x[0][1] - 0.2).abs() < 1e-12 && (x[1][1] - 0.6).abs() < 1e-12);
+        assert!(solve_linear_system(vec![vec![1., 2.], vec![2., 4.]], vec![vec![1.], vec![2.]]).is_none());
+    }
 }
@@ -1,2 +1,5 @@
 pub mod linear_algebra;
 pub mod multivariate_normal;
+pub mod fwht;
+pub mod statistics;
+pub mod combinatorics;
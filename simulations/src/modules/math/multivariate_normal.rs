@@ -1,9 +1,8 @@
 use rand::prelude::*;
 use rand_distr::Normal;
 
-use super::linear_algebra::{SquareMatrix, Vector};
+use super::linear_algebra::{SquareMatrix, Vector, solve_linear_system};
 
-#[allow(dead_code)]
 pub struct MultivariateNormal<const S: usize> {
     mean: Vector<S>,
     covariance_matrix: SquareMatrix<S>,
@@ -35,37 +34,84 @@ impl<const S: usize> MultivariateNormal<S> {
     }
 
     pub fn new(mean: Vector<S>, covariance_matrix: SquareMatrix<S>) -> std::result::Result<Self, &'static str> {
-        let l_matrix = match covariance_matrix {
-            SquareMatrix::Null => SquareMatrix::Null,
-            SquareMatrix::NonNull(covariance_matrix) => {
-                // Cholesky–Banachiewicz algorithm
-                // https://en.wikipedia.org/wiki/Cholesky_decomposition
-                let mut l_matrix = [[0.; S]; S];
-                for i in 0..S {
-                    for j in 0..=i {
-                        let sum = (0..j).fold(0., |acc, k| {
-                            acc + l_matrix[i][k] * l_matrix[j][k]
-                        });
-
-                        l_matrix[i][j] = if i == j {
-                            (covariance_matrix[i][i] - sum).sqrt()
-                        } else {
-                            (covariance_matrix[i][j] - sum) / l_matrix[j][j]
-                        };
-
-                        if l_matrix[i][j].is_nan() {
-                            return Err("covariance_matrix is not positive-definite")
-                        }
-                    }
-                }
-                SquareMatrix::NonNull(l_matrix)
-            }
-        };
+        let l_matrix = covariance_matrix.cholesky().map_err(|_| "covariance_matrix is not positive-definite")?;
         Ok(MultivariateNormal {
             mean,
             covariance_matrix,
             l_matrix
         })
     }
+
+    #[inline]
+    pub fn mean(&self) -> Vector<S> {
+        self.mean
+    }
+
+    #[inline]
+    pub fn covariance_matrix(&self) -> SquareMatrix<S> {
+        self.covariance_matrix
+    }
+
+    /// Returns the distribution of the R = S - K unobserved variables, in their original order,
+    /// given the values of the K observed variables. The mean and covariance are obtained from the
+    /// Schur complement of the covariance matrix of the observed variables.
+    pub fn conditional<const K: usize, const R: usize>(
+        &self,
+        observed_indices: [usize; K],
+        observed_values: [f64; K]
+    ) -> std::result::Result<MultivariateNormal<R>, &'static str> {
+        if K + R != S {
+            return Err("the number of observed and unobserved variables must add up to S")
+        }
+        if observed_indices.iter().enumerate().any(|(k, &i)| i >= S || observed_indices[..k].contains(&i)) {
+            return Err("the observed indices must be distinct and smaller than S")
+        }
+        let free: Vec<usize> = (0..S).filter(|i| !observed_indices.contains(i)).collect();
+        let sigma = self.covariance_matrix.as_full();
+
+        // Solves sigma_22 x = [x2 - mu2 | sigma_21]
+        let sigma_22: Vec<Vec<f64>> = observed_indices.iter().map(|&i| {
+            observed_indices.iter().map(|&j| sigma[i][j]).collect()
+        }).collect();
+        let rhs: Vec<Vec<f64>> = observed_indices.iter().zip(observed_values.iter()).map(|(&i, &x)| {
+            std::iter::once(x - self.mean[i]).chain(free.iter().map(|&j| sigma[i][j])).collect()
+        }).collect();
+        let x = solve_linear_system(sigma_22, rhs).ok_or("the covariance matrix of the observed variables is singular")?;
+
+        let mut mean = Vector::<R>::new();
+        let mut covariance = [[0_f64; R]; R];
+        for (a, &i) in free.iter().enumerate() {
+            mean[a] = self.mean[i] + observed_indices.iter().enumerate().map(|(k, &o)| sigma[i][o] * x[k][0]).sum::<f64>();
+            for (b, &j) in free.iter().enumerate() {
+                covariance[a][b] = sigma[i][j] - observed_indices.iter().enumerate().map(|(k, &o)| sigma[i][o] * x[k][1 + b]).sum::<f64>();
+            }
+        }
+        MultivariateNormal::new(mean, SquareMatrix::from(covariance))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditional() {
+        // With a diagonal covariance matrix the remaining variables are not affected
+        let mvn = MultivariateNormal::new(Vector::from([1., 2., 3.]), SquareMatrix::diagonal([1., 2., 3.])).unwrap();
+        let conditional = mvn.conditional::<1, 2>([1], [5.]).unwrap();
+        assert_eq!(conditional.mean(), Vector::from([1., 3.]));
+        assert!(conditional.covariance_matrix() == SquareMatrix::diagonal([1., 3.]));
+
+        let mvn = MultivariateNormal::new(Vector::from([0., 0.]), SquareMatrix::from([[1., 0.5], [0.5, 1.]])).unwrap();
+        let conditional = mvn.conditional::<1, 1>([1], [1.]).unwrap();
+        assert!((conditional.mean()[0] - 0.5).abs() < 1e-12);
+        assert!((conditional.covariance_matrix().get(0, 0) - 0.75).abs() < 1e-12);
+
+        assert!(mvn.conditional::<1, 2>([1], [1.]).is_err());
+        assert!(mvn.conditional::<1, 1>([2], [1.]).is_err());
+        let degenerate = MultivariateNormal::new(Vector::from([0., 0.]), SquareMatrix::Null).unwrap();
+        assert!(degenerate.conditional::<1, 1>([0], [1.]).is_err());
+    }
 }
 
@@ -0,0 +1,168 @@
+use std::cmp::Ordering;
+
+/// Returns the mean of the data, or NaN if it is empty
+pub fn mean(data: &[f64]) -> f64 {
+    if data.is_empty() {
+        return f64::NAN;
+    }
+    data.iter().sum::<f64>() / data.len() as f64
+}
+
+/// Returns the population variance (normalized by the number of data points) of the data, or NaN
+/// if it is empty
+pub fn variance(data: &[f64]) -> f64 {
+    covariance(data, data)
+}
+
+/// Returns the population covariance of two samples of the same length, or NaN if they are empty
+pub fn covariance(x: &[f64], y: &[f64]) -> f64 {
+    assert_eq!(x.len(), y.len(), "the samples must have the same length");
+    let (mx, my) = (mean(x), mean(y));
+    x.iter().zip(y.iter()).map(|(&xi, &yi)| (xi - mx) * (yi - my)).sum::<f64>() / x.len() as f64
+}
+
+/// Returns the Pearson correlation coefficient of two samples of the same length
+pub fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    covariance(x, y) / (variance(x) * variance(y)).sqrt()
+}
+
+/// Returns the Spearman rank correlation coefficient of two samples of the same length, assigning
+/// tied values their average rank
+pub fn spearman(x: &[f64], y: &[f64]) -> f64 {
+    pearson(&ranks(x), &ranks(y))
+}
+
+/// Returns Kendall's τ-b rank correlation coefficient of two samples of the same length, i.e., the
+/// number of concordant minus discordant pairs normalized with a correction for ties. Returns NaN
+/// if all values of one of the samples are tied.
+pub fn kendall_tau(x: &[f64], y: &[f64]) -> f64 {
+    assert_eq!(x.len(), y.len(), "the samples must have the same length");
+    let (mut concordance, mut untied_x, mut untied_y) = (0_i64, 0_u64, 0_u64);
+    for i in 0..x.len() {
+        for j in (i + 1)..x.len() {
+            let (dx, dy) = (x[i] - x[j], y[i] - y[j]);
+            untied_x += (dx != 0.) as u64;
+            untied_y += (dy != 0.) as u64;
+            if dx * dy > 0. {
+                concordance += 1;
+            } else if dx * dy < 0. {
+                concordance -= 1;
+            }
+        }
+    }
+    concordance as f64 / ((untied_x * untied_y) as f64).sqrt()
+}
+
+/// Returns the rank of each data point, starting at 0, with ties sharing their average rank
+fn ranks(data: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&i, &j| data[i].partial_cmp(&data[j]).unwrap_or(Ordering::Equal));
+
+    let mut ranks = vec![0.; data.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && data[order[end]] == data[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end - 1) as f64 / 2.;
+        for &i in &order[start..end] {
+            ranks[i] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// Returns the two-sample Kolmogorov-Smirnov statistic, the largest absolute difference between
+/// the empirical cumulative distributions of the samples, or NaN if any of them is empty
+pub fn ks_statistic(sample1: &[f64], sample2: &[f64]) -> f64 {
+    if sample1.is_empty() || sample2.is_empty() {
+        return f64::NAN;
+    }
+    let sorted = |sample: &[f64]| {
+        let mut sorted = sample.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        sorted
+    };
+    let (s1, s2) = (sorted(sample1), sorted(sample2));
+    let (n1, n2) = (s1.len() as f64, s2.len() as f64);
+
+    let (mut i, mut j, mut d) = (0, 0, 0_f64);
+    while i < s1.len() && j < s2.len() {
+        let x = s1[i].min(s2[j]);
+        while i < s1.len() && s1[i] <= x { i += 1; }
+        while j < s2.len() && s2[j] <= x { j += 1; }
+        d = d.max((i as f64 / n1 - j as f64 / n2).abs());
+    }
+    d
+}
+
+/// Returns a histogram of the data with `n_bins` bins of equal width between the smallest and the
+/// largest values, as (lower edge, upper edge, count) tuples. The last bin includes its upper edge.
+pub fn histogram(data: &[f64], n_bins: usize) -> Vec<(f64, f64, usize)> {
+    if data.is_empty() || n_bins == 0 {
+        return Vec::new();
+    }
+    let min = data.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = data.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let width = (max - min) / n_bins as f64;
+
+    let mut histogram: Vec<(f64, f64, usize)> = (0..n_bins).map(|k| {
+        (min + k as f64 * width, min + (k + 1) as f64 * width, 0)
+    }).collect();
+    for &x in data {
+        let k = if width > 0. { (((x - min) / width) as usize).min(n_bins - 1) } else { 0 };
+        histogram[k].2 += 1;
+    }
+    histogram
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statistics() {
+        let x = [1., 2., 3., 4.];
+        let y = [2., 4., 6., 8.];
+        assert_eq!(mean(&x), 2.5);
+        assert_eq!(variance(&x), 1.25);
+        assert_eq!(covariance(&x, &y), 2.5);
+        assert!((pearson(&x, &y) - 1.).abs() < 1e-12);
+        assert!((pearson(&x, &[8., 6., 4., 2.]) + 1.).abs() < 1e-12);
+
+        // Spearman's correlation is invariant under monotonic transformations
+        let cubes: Vec<f64> = x.iter().map(|xi| xi * xi * xi).collect();
+        assert!((spearman(&x, &cubes) - 1.).abs() < 1e-12);
+        assert_eq!(ranks(&[3., 1., 3., 2.]), vec![2.5, 0., 2.5, 1.]);
+
+        assert!(mean(&[]).is_nan() && variance(&[]).is_nan() && pearson(&[], &[]).is_nan());
+        assert!(spearman(&[], &[]).is_nan() && ks_statistic(&[], &x).is_nan());
+    }
+
+    #[test]
+    fn kendall() {
+        let x = [1., 2., 3., 4.];
+        assert_eq!(kendall_tau(&x, &x), 1.);
+        assert_eq!(kendall_tau(&x, &[4., 3., 2., 1.]), -1.);
+        // 5 concordant and 1 discordant pairs
+        assert!((kendall_tau(&x, &[1., 3., 2., 4.]) - 2. / 3.).abs() < 1e-12);
+        // One pair tied in y: (4 - 1) / sqrt(6 * 5)
+        assert!((kendall_tau(&x, &[1., 1., 3., 2.]) - 3. / 30_f64.sqrt()).abs() < 1e-12);
+        assert!(kendall_tau(&x, &[1.; 4]).is_nan());
+    }
+
+    #[test]
+    fn ks_and_histogram() {
+        let x = [1., 2., 3., 4.];
+        assert_eq!(ks_statistic(&x, &x), 0.);
+        assert_eq!(ks_statistic(&x, &[5., 6.]), 1.);
+        assert_eq!(ks_statistic(&x, &[2.5, 3.5]), 0.5);
+
+        let bins = histogram(&[0., 0.5, 1., 1.5, 2.], 2);
+        assert_eq!(bins, vec![(0., 1., 2), (1., 2., 3)]);
+        assert_eq!(histogram(&[1., 1.], 3)[0].2, 2);
+    }
+}
@@ -8,3 +8,11 @@ pub mod fitness_model;
 pub mod parameters;
 pub mod data;
 pub mod plot_landscape;
+pub mod coalescence;
+pub mod progress;
+pub mod ensemble;
+pub mod convergence_analysis;
+pub mod serial_transfer;
+pub mod epistasis_decomposition;
+pub mod ode;
+pub mod popgen_stats;
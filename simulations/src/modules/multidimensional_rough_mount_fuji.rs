@@ -1,15 +1,19 @@
 use std::ops::Index;
 use std::collections::HashMap;
+use std::error::Error;
 
 use super::{
     genotype::{Genotype, possible_sequences},
     fitness_model::FitnessModel,
     math::{
         multivariate_normal::MultivariateNormal,
-        linear_algebra::Vector
+        linear_algebra::{SquareMatrix, Vector},
+        statistics
     }
 };
 
+use rand::Rng;
+use rand_distr::{Distribution, Normal};
 use serde::{Serialize, Deserialize};
 
 #[derive(Clone)]
@@ -22,16 +26,16 @@ impl<const L: usize, const S: usize> MultidimensionalRoughMountFuji<L, S> {
     pub fn new(fitness_model: FitnessModel<S>) -> Self {
         let mut phenotype = HashMap::new();
 
-        match fitness_model {
+        match &fitness_model {
             FitnessModel::HoC { cb } => {
-                let mvn_b = MultivariateNormal::new(Vector::new(), cb).unwrap();
+                let mvn_b = MultivariateNormal::new(Vector::new(), *cb).unwrap();
                 for seq in possible_sequences::<L>() {
                     let g = Genotype::<L>::from_sequence(&seq);
                     phenotype.insert(g, mvn_b.generate());
                 }
             },
             FitnessModel::Additive { mu, ca } => {
-                let mvn_a = MultivariateNormal::new(mu, ca).unwrap();
+                let mvn_a = MultivariateNormal::new(*mu, *ca).unwrap();
                 let additive_component: Vec::<Vector<S>> = (0..L).map(|_| mvn_a.generate()).collect();
                 for seq in possible_sequences::<L>() {
                     let g = Genotype::<L>::from_sequence(&seq);
@@ -48,8 +52,8 @@ impl<const L: usize, const S: usize> MultidimensionalRoughMountFuji<L, S> {
                 }
             },
             FitnessModel::RoughMountFuji { mu, ca, cb } => {
-                let mvn_a = MultivariateNormal::new(mu,            ca).unwrap();
-                let mvn_b = MultivariateNormal::new(Vector::new(), cb).unwrap();
+                let mvn_a = MultivariateNormal::new(*mu,           *ca).unwrap();
+                let mvn_b = MultivariateNormal::new(Vector::new(), *cb).unwrap();
 
                 let additive_component: Vec::<Vector<S>> = (0..L).map(|_| mvn_a.generate()).collect();
                 for seq in possible_sequences::<L>() {
@@ -65,6 +69,64 @@ impl<const L: usize, const S: usize> MultidimensionalRoughMountFuji<L, S> {
                     }
                     phenotype.insert(g, p);
                 }
+            },
+            FitnessModel::NetworkEpistasis { adjacency, noise_sd } => {
+                assert!(adjacency.len() <= L && adjacency.iter().flatten().all(|&j| j < L), "the interaction network has more than L loci");
+                let normal = Normal::new(0., *noise_sd).unwrap();
+                let mut rng = rand::thread_rng();
+
+                // Contribution of locus i for each combination of alleles at i and its neighbors
+                let neighbors: Vec<&[usize]> = (0..L).map(|i| adjacency.get(i).map_or(&[][..], |a| &a[..])).collect();
+                let tables: Vec<Vec<Vector<S>>> = neighbors.iter().map(|n| {
+                    (0..1 << (1 + n.len())).map(|_| {
+                        let mut v = Vector::new();
+                        for x in v.iter_mut() {
+                            *x = normal.sample(&mut rng);
+                        }
+                        v
+                    }).collect()
+                }).collect();
+
+                for seq in possible_sequences::<L>() {
+                    let g = Genotype::<L>::from_sequence(&seq);
+
+                    let mut p = Vector::new();
+                    for i in 0..L {
+                        let index = neighbors[i].iter().enumerate().fold(g[i] as usize, |acc, (k, &j)| {
+                            acc + ((g[j] as usize) << (k + 1))
+                        });
+                        let contribution = tables[i][index];
+                        for r in 0..S {
+                            p[r] += contribution[r];
+                        }
+                    }
+                    phenotype.insert(g, p);
+                }
+            },
+            FitnessModel::Empirical { path } => {
+                phenotype = Self::read_phenotypes(path).unwrap_or_else(|e| {
+                    panic!("could not read the phenotypes from {}: {}", path, e)
+                });
+            },
+            FitnessModel::MountFuji { a, optimum, noise_sd } => {
+                let optimum = if optimum.is_empty() {
+                    Genotype::<L>::from_sequence(&[1; L])
+                } else {
+                    assert_eq!(optimum.len(), L, "the optimum does not have L loci");
+                    Genotype::<L>::from_sequence(optimum)
+                };
+                let normal = Normal::new(0., *noise_sd).unwrap();
+                let mut rng = rand::thread_rng();
+                for seq in possible_sequences::<L>() {
+                    let g = Genotype::<L>::from_sequence(&seq);
+                    let distance = g.n_differences(&optimum) as f64;
+
+                    let mut p = Vector::new();
+                    for x in p.iter_mut() {
+                        *x = -a * distance + normal.sample(&mut rng);
+                    }
+                    phenotype.insert(g, p);
+                }
             }
         }
 
@@ -73,6 +135,129 @@ impl<const L: usize, const S: usize> MultidimensionalRoughMountFuji<L, S> {
         }
     }
 
+    /// Reads the phenotype of each genotype from a landscape file in CBOR or JSON format
+    fn read_phenotypes(path: &str) -> Result<HashMap<Genotype<L>, Vector<S>>, Box<dyn Error>> {
+        let bytes = std::fs::read(path)?;
+        let vec: VecRMF = match serde_cbor::from_slice(&bytes) {
+            Ok(vec) => vec,
+            Err(_)  => serde_json::from_slice(&bytes)?
+        };
+
+        let (phenotypes, _) = vec.v;
+        if phenotypes.len() != 1 << L || phenotypes.iter().any(|(g, p)| g.len() != L || p.len() != S) {
+            return Err(format!("the file does not contain a landscape with L = {} and S = {}", L, S).into())
+        }
+        Ok(phenotypes.iter().map(|(g, p)| (Genotype::from_sequence(g), Vector::from_vec(p))).collect())
+    }
+
+    /// Creates the landscape from the phenotype of each genotype
+    pub fn from_phenotype_map(phenotype: HashMap<Genotype<L>, Vector<S>>, fitness_model: FitnessModel<S>) -> Self {
+        Self {
+            phenotype, fitness_model
+        }
+    }
+
+    /// Returns a copy of the landscape with independent gaussian noise with standard deviation
+    /// `noise_sd` added to each component of each phenotype. Genotypes are processed in order so
+    /// that the result is reproducible for a seeded generator.
+    pub fn with_phenotype_noise(&self, noise_sd: f64, rng: &mut impl Rng) -> Self {
+        let normal = Normal::new(0., noise_sd).unwrap();
+        let mut genotypes: Vec<&Genotype<L>> = self.phenotype.keys().collect();
+        genotypes.sort_unstable();
+
+        let phenotype = genotypes.into_iter().map(|g| {
+            let mut p = self.phenotype[g];
+            for x in p.iter_mut() {
+                *x += normal.sample(rng);
+            }
+            (*g, p)
+        }).collect();
+        Self::from_phenotype_map(phenotype, self.fitness_model.clone())
+    }
+
+    /// Returns the phenotype of each genotype
+    #[inline]
+    pub fn phenotype_map(&self) -> &HashMap<Genotype<L>, Vector<S>> {
+        &self.phenotype
+    }
+
+    #[inline]
+    pub fn fitness_model(&self) -> FitnessModel<S> {
+        self.fitness_model.clone()
+    }
+
+    /// Returns the mean and the covariance matrix of the phenotypes of all genotypes
+    fn phenotype_covariance(&self) -> ([f64; S], [[f64; S]; S]) {
+        let n = self.phenotype.len() as f64;
+        let mut mean = [0_f64; S];
+        for p in self.phenotype.values() {
+            for (m, &pi) in mean.iter_mut().zip(p.iter()) {
+                *m += pi / n;
+            }
+        }
+
+        let mut covariance = [[0_f64; S]; S];
+        for p in self.phenotype.values() {
+            for i in 0..S {
+                for j in 0..S {
+                    covariance[i][j] += (p[i] - mean[i]) * (p[j] - mean[j]) / (n - 1.);
+                }
+            }
+        }
+        (mean, covariance)
+    }
+
+    /// Returns the principal component directions of the phenotypes, one per row, and the
+    /// fraction of the phenotypic variance explained by each of them
+    pub fn phenotype_pca(&self) -> ([[f64; S]; S], [f64; S]) {
+        let (_, covariance) = self.phenotype_covariance();
+        let (eigenvalues, directions) = SquareMatrix::from(covariance).eigenvalues_symmetric();
+
+        let total: f64 = eigenvalues.iter().sum();
+        let mut explained = [0_f64; S];
+        for (e, &lambda) in explained.iter_mut().zip(eigenvalues.iter()) {
+            *e = lambda / total;
+        }
+        (directions, explained)
+    }
+
+    /// Returns the projection of the centered phenotype of a genotype onto a principal component.
+    /// The principal components are recomputed in each call.
+    pub fn project_phenotype(&self, g: Genotype<L>, component: usize) -> f64 {
+        let (mean, _) = self.phenotype_covariance();
+        let (directions, _) = self.phenotype_pca();
+        let p = self[g];
+        (0..S).map(|i| (p[i] - mean[i]) * directions[component][i]).sum()
+    }
+
+    /// Returns the Euclidean distances between the phenotypes of all pairs of genotypes, indexed
+    /// by `Genotype::index()`
+    pub fn phenotypic_distance_matrix(&self) -> Vec<Vec<f64>> {
+        let n = 1_usize << L;
+        let mut distances = vec![vec![0_f64; n]; n];
+        for i in 0..n {
+            let pi = self[Genotype::from_index(i)];
+            for j in (i + 1)..n {
+                let pj = self[Genotype::from_index(j)];
+                let d = (0..S).map(|r| (pi[r] - pj[r]).powi(2)).sum::<f64>().sqrt();
+                distances[i][j] = d;
+                distances[j][i] = d;
+            }
+        }
+        distances
+    }
+
+    /// Returns the Pearson correlation between the phenotypic and the Hamming distances over all
+    /// pairs of distinct genotypes
+    pub fn phenotypic_neighbor_correlation(&self) -> f64 {
+        let distances = self.phenotypic_distance_matrix();
+        let (hamming, phenotypic): (Vec<f64>, Vec<f64>) = (0..distances.len()).flat_map(|i| {
+            let distances = &distances;
+            ((i + 1)..distances.len()).map(move |j| ((i ^ j).count_ones() as f64, distances[i][j]))
+        }).unzip();
+        statistics::pearson(&hamming, &phenotypic)
+    }
+
     #[inline]
     pub fn get_multiplicative(&self, g: Genotype<L>) -> Vector<S> {
         let mut phenotype = self[g];
@@ -113,3 +298,123 @@ impl<const L: usize, const S: usize> Index<Genotype<L>> for MultidimensionalRoug
         }
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::parameters::Parameters;
+
+    #[test]
+    fn pca() {
+        const L: usize = 10;
+        let cb = SquareMatrix::from([[1., 0.], [0., 0.01]]);
+        let landscape = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::HoC { cb });
+
+        let (directions, explained) = landscape.phenotype_pca();
+        assert!(directions[0][0].abs() > 0.99 && directions[1][1].abs() > 0.99);
+        assert!(explained[0] > 0.9);
+        assert!((explained.iter().sum::<f64>() - 1.).abs() < 1e-12);
+
+        // Projections onto the principal components are centered
+        let mean_projection: f64 = possible_sequences::<L>().iter().map(|seq| {
+            landscape.project_phenotype(Genotype::from_sequence(seq), 0)
+        }).sum::<f64>() / (1 << L) as f64;
+        assert!(mean_projection.abs() < 1e-10);
+    }
+
+    #[test]
+    fn mount_fuji() {
+        use super::super::fitness_landscape::{FitnessLandscape, FitnessType};
+        const L: usize = 5;
+        let optimum = vec![1, 0, 0, 1, 1];
+        let landscape = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::MountFuji { a: 1., optimum: optimum.clone(), noise_sd: 0. });
+        let mut fitness = FitnessLandscape::<L>::new(FitnessType::Additive);
+        for (g, p) in landscape.phenotype_map() {
+            assert_eq!(p[0], p[1]);
+            fitness.add_genotype(*g, p[0]);
+        }
+        assert!((fitness.additive_r_squared() - 1.).abs() < 1e-12);
+        assert_eq!(fitness.maxima(), vec![Genotype::from_sequence(&optimum)]);
+        assert_eq!(fitness.get(&Genotype::new()), Some(&-3.));
+
+        let noisy = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::new_mf(vec![0.1, 1.]));
+        assert!(noisy.phenotype_map().values().all(|p| p[0] != p[1]));
+    }
+
+    #[test]
+    fn network_epistasis() {
+        const L: usize = 4;
+        let filename = std::env::temp_dir().join(format!("adjacency_{}.txt", std::process::id()));
+        std::fs::write(&filename, "# contact map\n0 1\n1 2\n2 1\n").unwrap();
+        let adjacency = Parameters::<2>::from_adjacency_file(filename.to_str().unwrap()).unwrap();
+        std::fs::write(&filename, "0 1 2\n").unwrap();
+        assert!(Parameters::<2>::from_adjacency_file(filename.to_str().unwrap()).is_err());
+        std::fs::remove_file(&filename).unwrap();
+        assert_eq!(adjacency, vec![vec![1], vec![0, 2], vec![1]]);
+
+        // The effect of a mutation at locus 3, which does not interact, is the same in all backgrounds
+        let landscape = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::NetworkEpistasis { adjacency, noise_sd: 1. });
+        let effect = |g: Genotype<L>, i: usize| landscape[g.cmutate(i)][0] - landscape[g][0];
+        let wildtype = Genotype::<L>::new();
+        for seq in possible_sequences::<L>().iter().filter(|seq| seq[3] == 0) {
+            assert!((effect(Genotype::from_sequence(seq), 3) - effect(wildtype, 3)).abs() < 1e-12);
+        }
+        // The effect of a mutation at locus 0 depends on the allele at locus 1
+        assert!((effect(wildtype.cmutate(1), 0) - effect(wildtype, 0)).abs() > 1e-12);
+    }
+
+    #[test]
+    fn phenotypic_distances() {
+        const L: usize = 6;
+        // Additive model in which each locus changes a different phenotypic dimension
+        let phenotype = possible_sequences::<L>().iter().map(|seq| {
+            (Genotype::from_sequence(seq), Vector::from_vec(&seq.iter().map(|&x| x as f64).collect::<Vec<f64>>()))
+        }).collect();
+        let landscape = MultidimensionalRoughMountFuji::<L, L>::from_phenotype_map(phenotype, FitnessModel::new_additive(vec![0., 1., 0.]));
+
+        let distances = landscape.phenotypic_distance_matrix();
+        assert_eq!(distances.len(), 1 << L);
+        for (i, row) in distances.iter().enumerate() {
+            for (j, &d) in row.iter().enumerate() {
+                assert_eq!(d, distances[j][i]);
+                assert!((d - ((i ^ j).count_ones() as f64).sqrt()).abs() < 1e-12);
+            }
+        }
+
+        // Phenotypic distances grow with the number of mutations in the additive model, but not in
+        // the house of cards model
+        assert!(landscape.phenotypic_neighbor_correlation() > 0.95);
+        let hoc = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::new_hoc(vec![1., 0.]));
+        assert!(hoc.phenotypic_neighbor_correlation().abs() < 0.2);
+    }
+
+    #[test]
+    fn empirical() {
+        const L: usize = 4;
+        let landscape = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::new_hoc(vec![1., 0.2]));
+        let path = std::env::temp_dir().join(format!("empirical_{}.dat", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+
+        for json in [false, true] {
+            let file = std::fs::File::create(&path).unwrap();
+            if json {
+                serde_json::to_writer(file, &landscape.to_vec()).unwrap();
+            } else {
+                serde_cbor::to_writer(file, &landscape.to_vec()).unwrap();
+            }
+            let loaded = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::Empirical { path: path.clone() });
+            // JSON does not guarantee that floats are read back exactly
+            for (g, p) in landscape.phenotype_map() {
+                assert!((0..2).all(|r| (loaded[*g][r] - p[r]).abs() < 1e-12));
+            }
+
+            // Saving and reading again the empirical landscape keeps the phenotypes
+            serde_cbor::to_writer(std::fs::File::create(&path).unwrap(), &loaded.to_vec()).unwrap();
+            let reloaded = MultidimensionalRoughMountFuji::<L, 2>::new(FitnessModel::Empirical { path: path.clone() });
+            assert_eq!(reloaded.phenotype_map(), loaded.phenotype_map());
+        }
+        assert!(MultidimensionalRoughMountFuji::<3, 2>::read_phenotypes(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
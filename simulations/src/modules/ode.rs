@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use super::{
+    genotype::Genotype,
+    fitness_landscape::{FitnessLandscape, FitnessType}
+};
+
+/// Returns the time derivative of the genotype frequencies given by the replicator equation,
+/// `dx_g/dt = x_g (f_g - f̄)`, with the multiplicative fitness of the landscape
+fn replicator_derivative<const L: usize>(freqs: &HashMap<Genotype<L>, f64>, landscape: &FitnessLandscape<L>) -> HashMap<Genotype<L>, f64> {
+    let fitness: HashMap<Genotype<L>, f64> = freqs.keys().map(|g| {
+        (*g, landscape.get_fitness(g, FitnessType::Multiplicative).expect("genotype missing from the fitness landscape"))
+    }).collect();
+    let mean_fitness: f64 = freqs.iter().map(|(g, x)| x * fitness[g]).sum();
+    freqs.iter().map(|(g, x)| (*g, x * (fitness[g] - mean_fitness))).collect()
+}
+
+/// Returns `x + a * dx` for each genotype
+fn add_scaled<const L: usize>(x: &HashMap<Genotype<L>, f64>, dx: &HashMap<Genotype<L>, f64>, a: f64) -> HashMap<Genotype<L>, f64> {
+    x.iter().map(|(g, xg)| (*g, xg + a * dx[g])).collect()
+}
+
+/// Integrates the replicator equation over a time `dt` with one step of the Euler method
+pub fn replicator_euler<const L: usize>(freqs: &HashMap<Genotype<L>, f64>, landscape: &FitnessLandscape<L>, dt: f64) -> HashMap<Genotype<L>, f64> {
+    add_scaled(freqs, &replicator_derivative(freqs, landscape), dt)
+}
+
+/// Integrates the replicator equation over a time `dt` with one step of the classical fourth
+/// order Runge-Kutta method
+pub fn replicator_rk4<const L: usize>(freqs: &HashMap<Genotype<L>, f64>, landscape: &FitnessLandscape<L>, dt: f64) -> HashMap<Genotype<L>, f64> {
+    let k1 = replicator_derivative(freqs, landscape);
+    let k2 = replicator_derivative(&add_scaled(freqs, &k1, dt / 2.), landscape);
+    let k3 = replicator_derivative(&add_scaled(freqs, &k2, dt / 2.), landscape);
+    let k4 = replicator_derivative(&add_scaled(freqs, &k3, dt), landscape);
+    freqs.iter().map(|(g, x)| {
+        (*g, x + dt / 6. * (k1[g] + 2. * k2[g] + 2. * k3[g] + k4[g]))
+    }).collect()
+}
+
+/// Integrates the replicator equation for `steps` Runge-Kutta steps of length `dt`, which
+/// describes the evolution of an infinite population under selection alone when the fitness
+/// differences per generation are small. Returns the frequencies at every step, starting with
+/// the initial ones.
+pub fn run_replicator_dynamics<const L: usize>(
+    initial_freqs: HashMap<Genotype<L>, f64>,
+    landscape: &FitnessLandscape<L>,
+    dt: f64,
+    steps: usize
+) -> Vec<HashMap<Genotype<L>, f64>> {
+    let mut trajectory = Vec::with_capacity(steps + 1);
+    trajectory.push(initial_freqs);
+    for _ in 0..steps {
+        let next = replicator_rk4(trajectory.last().unwrap(), landscape, dt);
+        trajectory.push(next);
+    }
+    trajectory
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_distr::{Binomial, Distribution};
+
+    #[test]
+    fn replicator_dynamics() {
+        const L: usize = 1;
+        let s = 0.05;
+        let (wildtype, mutant) = (Genotype::<L>::new(), Genotype::<L>::from_sequence(&[1]));
+        let mut landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
+        landscape.add_genotype(wildtype, 1.);
+        landscape.add_genotype(mutant, 1. + s);
+
+        let x0 = 0.1;
+        let initial: HashMap<Genotype<L>, f64> = [(wildtype, 1. - x0), (mutant, x0)].into_iter().collect();
+        let trajectory = run_replicator_dynamics(initial.clone(), &landscape, 1., 100);
+        assert_eq!(trajectory.len(), 101);
+
+        // The frequency of the mutant follows the logistic curve
+        let logistic = |t: f64| x0 * (s * t).exp() / (1. - x0 + x0 * (s * t).exp());
+        for (t, freqs) in trajectory.iter().enumerate() {
+            assert!((freqs[&mutant] - logistic(t as f64)).abs() < 1e-8);
+            assert!((freqs.values().sum::<f64>() - 1.).abs() < 1e-12);
+        }
+        let euler = (0..1000).fold(initial, |x, _| replicator_euler(&x, &landscape, 0.1));
+        assert!((euler[&mutant] - logistic(100.)).abs() < 1e-2);
+
+        // Large Wright-Fisher populations follow the deterministic trajectory
+        let mut rng = rand::thread_rng();
+        let n = 1_000_000;
+        let mut mutants = (x0 * n as f64) as u64;
+        for _ in 0..100 {
+            let x = mutants as f64 / n as f64;
+            let p = x * (1. + s) / (1. + s * x);
+            mutants = Binomial::new(n, p).unwrap().sample(&mut rng);
+        }
+        assert!((mutants as f64 / n as f64 - trajectory[100][&mutant]).abs() < 0.05);
+    }
+}
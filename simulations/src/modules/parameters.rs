@@ -1,10 +1,22 @@
-use clap::{Arg, App, AppSettings, ArgGroup, values_t, value_t};
+use std::{
+    collections::HashMap,
+    error::Error
+};
+
+use clap::{Arg, App, AppSettings, ArgGroup, ArgMatches, values_t, value_t};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
 
 use super::{
-    fitness_model::FitnessModel,
-    math::linear_algebra::Vector
+    fitness_model::{FitnessModel, ParseError},
+    data::{Convergence, StableTopGenotypes, EntropyThreshold, FitnessVarianceThreshold, BUFFER_SIZE, MAX_GENERATIONS},
+    math::linear_algebra::{SquareMatrix, Vector}
 };
 
+/// Default window and threshold of the adaptive minimum number of generations
+const ADAPT_T_MIN_WINDOW: usize = 200;
+const ADAPT_T_MIN_THRESHOLD: f64 = 0.01;
 
 pub struct Parameters<const S: usize> {
     pub pop_size: Vec<usize>,
@@ -15,13 +27,69 @@ pub struct Parameters<const S: usize> {
     pub landscapes: [usize; 2],
     pub null_model: bool,
     pub load_landscape: bool,
-    pub folder_name: String
+    pub folder_name: String,
+    pub compare_env: Option<[Vector<S>; 2]>,
+    pub target_landscape: Option<usize>,
+    pub landscape_noise: f64,
+    pub shift_rate: f64,
+    pub stop_on_fixation: bool,
+    pub buffer_size: usize,
+    pub stable_window: usize,
+    pub snapshots: bool,
+    pub track_fixation: bool,
+    pub progress_interval: Option<usize>,
+    pub progress_file: Option<String>,
+    pub save_landscape_every: Option<usize>,
+    pub t_min: Option<usize>,
+    pub adapt_t_min: Option<(usize, f64)>,
+    pub carrying_capacity: Option<usize>,
+    pub growth_rate: f64,
+    pub convergence: Convergence,
+    pub threads: usize,
+    pub seed: Option<u64>
+}
+
+impl<const S: usize> Default for Parameters<S> {
+    fn default() -> Self {
+        Self {
+            pop_size: vec![0],
+            mutation_rate_per_locus: 0.,
+            model: FitnessModel::HoC { cb: SquareMatrix::Null },
+            replicates: 0,
+            resources: Vector::from([1.; S]),
+            landscapes: [0, 0],
+            null_model: false,
+            load_landscape: false,
+            folder_name: "".to_string(),
+            compare_env: None,
+            target_landscape: None,
+            landscape_noise: 0.,
+            shift_rate: 0.,
+            stop_on_fixation: false,
+            buffer_size: BUFFER_SIZE,
+            stable_window: MAX_GENERATIONS,
+            snapshots: false,
+            track_fixation: false,
+            progress_interval: None,
+            progress_file: None,
+            save_landscape_every: None,
+            t_min: None,
+            adapt_t_min: None,
+            carrying_capacity: None,
+            growth_rate: 0.,
+            convergence: Convergence::default(),
+            threads: 0,
+            seed: None
+        }
+    }
 }
 
 impl<const S: usize> Parameters<S> {
     pub fn from_command_line() -> Self {
         let rn: Vec<String> = (0..S).map(|i| format!("res {}", i+1)).collect();
         let resource_names: Vec<&str> = rn.iter().map(|s| s.as_str()).collect();
+        let en: Vec<String> = (1..=2).flat_map(|e| (0..S).map(move |i| format!("env {} res {}", e, i+1))).collect();
+        let environment_names: Vec<&str> = en.iter().map(|s| s.as_str()).collect();
 
         let matches = App::new("")
               .author("André Amado <andre.amado@pm.me>")
@@ -30,55 +98,158 @@ impl<const S: usize> Parameters<S> {
               .arg(Arg::with_name("population_size").help("List of population sizes").short("s").long("size").takes_value(true).multiple(true).required(true))
               .arg(Arg::with_name("mutation_rate_per_locus").help("Mutation rate per locus per generation").short("m").long("mutation_rate").value_name("rate").takes_value(true).required(true))
               .arg(Arg::with_name("resources").help("Amount of each resource").short("r").long("resources").takes_value(true).value_names(&resource_names[..]).required(true))
+              .arg(Arg::with_name("compare_env").help("Prints and records the rank correlation between the fitness landscapes under two resource vectors").long("compare-env2").takes_value(true).value_names(&environment_names[..]))
 
               .arg(Arg::with_name("landscapes").long("landscapes").short("l").value_names(&["first_landscape", "last_landscape"]).help("Range of landscapes to analize").required(true))
+              .arg(Arg::with_name("track_fixation").long("track-fixation").help("Writes the generation at which new alleles reach frequency 0.5 and 1 at each locus to a separate file"))
+              .arg(Arg::with_name("stop_on_fixation").long("stop-on-fixation").help("Stops a replicate once the population loses its diversity and a single genotype is left"))
+              .arg(Arg::with_name("shift_rate").long("shift-rate").takes_value(true).value_name("RATE").help("Rate per generation at which the landscape is replaced by a new one drawn from the same model (default: 0)"))
+              .arg(Arg::with_name("landscape_noise").long("landscape-noise").takes_value(true).help("Standard deviation of the noise added to the phenotypes of the landscape in each generation (default: 0)"))
+              .arg(Arg::with_name("target_landscape").long("target_landscape").takes_value(true).help("Index of a landscape towards which the landscape changes gradually during the simulation"))
               .arg(Arg::with_name("replicates").long("replicates").takes_value(true).help("Number of replicates per landscapes").required(true))
               .arg(Arg::with_name("load_landscape").long("load").help("Flag loading existing landscape"))
+              .arg(Arg::with_name("threads").long("threads").takes_value(true).help("Number of threads running replicates in parallel (default: all CPUs)"))
+              .arg(Arg::with_name("seed").long("seed").takes_value(true).help("Seed of the random number generator"))
+              .arg(Arg::with_name("convergence").long("convergence").takes_value(true).possible_values(&["topgenotypes", "entropy", "variance"]).help("Criterion used to stop the simulation (default: topgenotypes)"))
+              .arg(Arg::with_name("convergence_threshold").long("convergence_threshold").takes_value(true).required_ifs(&[("convergence", "entropy"), ("convergence", "variance")]).help("Threshold for the entropy or variance convergence criteria"))
+              .arg(Arg::with_name("convergence_window").long("convergence_window").takes_value(true).help("Number of generations the convergence criterion has to hold (default: the stable window)"))
+              .arg(Arg::with_name("progress_interval").long("progress-interval").takes_value(true).value_name("N").help("Reports the progress every N generations"))
+              .arg(Arg::with_name("progress_file").long("progress-file").takes_value(true).value_name("PATH").help("Appends the progress to a log file instead of printing it"))
+              .arg(Arg::with_name("save_landscape_every").long("save-landscape-every").takes_value(true).value_name("N").help("Writes the full fitness landscape with the statistics every N generations"))
+              .arg(Arg::with_name("buffer_size").long("buffer-size").takes_value(true).help("Number of generations kept in memory (default: 5000)"))
+              .arg(Arg::with_name("stable_window").long("stable-window").takes_value(true).help("Number of generations used to identify the top genotypes and written per replicate (default: 500)"))
+              .arg(Arg::with_name("t_min").long("t-min").takes_value(true).value_name("T").help("Minimum number of generations before the population can be considered stable"))
+              .arg(Arg::with_name("adapt_t_min").long("adapt-tmin").takes_value(true).min_values(0).max_values(2).value_names(&["WINDOW", "THRESHOLD"]).help("Starts recording once the standard deviation of the entropy over WINDOW generations falls below THRESHOLD (default: 200 0.01)"))
+              .arg(Arg::with_name("carrying_capacity").long("carrying-capacity").takes_value(true).value_name("K").requires("growth_rate").help("Changes the population size with logistic growth, N_{t+1} = N_t exp(r (1 - N_t/K))"))
+              .arg(Arg::with_name("growth_rate").long("growth-rate").takes_value(true).value_name("r").requires("carrying_capacity").help("Growth rate of the logistic growth"))
 
               // Models
               .arg(Arg::with_name("HoC").long("hoc").help("House of Cards model").takes_value(true).value_names(&["cb_diag", "cb_offdiag"]))
               .arg(Arg::with_name("additive").long("add").help("Additive model").takes_value(true).value_names(&["mu", "ca_diag", "ca_offdiag"]))
               .arg(Arg::with_name("RMF").long("rmf").help("Rough Mount Fuji model").takes_value(true).value_names(&["mu", "ca_diag", "ca_offdiag", "cb_diag", "cb_offdiag"]))
-              .group(ArgGroup::with_name("model").args(&["HoC", "additive", "RMF"]).required(true))
+              .arg(Arg::with_name("MF").long("mf").help("Mount Fuji model with House of Cards noise, with the optimum at the all-derived genotype").takes_value(true).value_names(&["a", "noise_sd"]))
+              .arg(Arg::with_name("network").long("network").help("Epistasis along the interaction network in an edge-list file").takes_value(true).value_names(&["adjacency_file", "noise_sd"]))
+              .arg(Arg::with_name("empirical").long("empirical").help("Phenotypes read from a landscape file in CBOR or JSON format").takes_value(true).value_name("PATH"))
+              .group(ArgGroup::with_name("model").args(&["HoC", "additive", "RMF", "MF", "network", "empirical"]).required(true))
+              .arg(Arg::with_name("model_noise").long("model-noise").takes_value(true).value_name("SD").help("Standard deviation of the noise added to the parameters of the HoC, additive and RMF models (default: 0)"))
 
               .arg(Arg::with_name("null_model").long("null").help("Flags the usage of the null model"))
 
               .get_matches();
 
-        let model = if matches.is_present("HoC") {
-            let model_params = values_t!(matches.values_of("HoC"), f64).unwrap();
-            FitnessModel::<S>::new_hoc(model_params)
-        } else if matches.is_present("additive") {
-            let model_params = values_t!(matches.values_of("additive"), f64).unwrap();
-            FitnessModel::<S>::new_additive(model_params)
-        } else if matches.is_present("RMF") {
-            let model_params = values_t!(matches.values_of("RMF"), f64).unwrap();
-            FitnessModel::<S>::new_rmf(model_params)
-        } else {
-            panic!("No model found!")
-        };
+        let model = FitnessModel::<S>::from_clap_matches(&matches, &mut Self::seeded_rng(&matches)).unwrap_or_else(|e| e.exit());
 
-        let resources_v = values_t!(matches.values_of("resources"), f64).unwrap();
+        let resources_v = values_t!(matches.values_of("resources"), f64).unwrap_or_else(|e| e.exit());
         let mut resources = Vector::<S>::new();
         for i in 0..S {
             resources[i] = resources_v[i];
         }
 
+        let compare_env = if matches.is_present("compare_env") {
+            let environments_v = values_t!(matches.values_of("compare_env"), f64).unwrap_or_else(|e| e.exit());
+            let (mut r1, mut r2) = (Vector::<S>::new(), Vector::<S>::new());
+            for i in 0..S {
+                r1[i] = environments_v[i];
+                r2[i] = environments_v[S + i];
+            }
+            Some([r1, r2])
+        } else {
+            None
+        };
+
+        let buffer_size = if matches.is_present("buffer_size") {
+            value_t!(matches.value_of("buffer_size"), usize).unwrap_or_else(|e| e.exit())
+        } else {
+            BUFFER_SIZE
+        };
+        let stable_window = if matches.is_present("stable_window") {
+            value_t!(matches.value_of("stable_window"), usize).unwrap_or_else(|e| e.exit())
+        } else {
+            MAX_GENERATIONS
+        };
+
+        let convergence = match matches.value_of("convergence") {
+            None            => Convergence::StableTopGenotypes(StableTopGenotypes { window: stable_window }),
+            Some(criterion) => {
+                let window = if matches.is_present("convergence_window") {
+                    value_t!(matches.value_of("convergence_window"), usize).unwrap_or_else(|e| e.exit())
+                } else {
+                    stable_window
+                };
+                match criterion {
+                    "topgenotypes" => Convergence::StableTopGenotypes(StableTopGenotypes { window }),
+                    "entropy"      => Convergence::EntropyThreshold(EntropyThreshold {
+                        max_entropy: value_t!(matches.value_of("convergence_threshold"), f64).unwrap_or_else(|e| e.exit()),
+                        window
+                    }),
+                    "variance"     => Convergence::FitnessVarianceThreshold(FitnessVarianceThreshold {
+                        max_var: value_t!(matches.value_of("convergence_threshold"), f64).unwrap_or_else(|e| e.exit()),
+                        window
+                    }),
+                    _ => unreachable!()
+                }
+            }
+        };
+
+        let adapt_t_min = if matches.is_present("adapt_t_min") {
+            let value = |i: usize| matches.values_of("adapt_t_min").and_then(|mut values| values.nth(i));
+            Some((
+                value(0).map_or(Ok(ADAPT_T_MIN_WINDOW), str::parse).unwrap_or_else(|e| {
+                    clap::Error::value_validation_auto(format!("invalid WINDOW for --adapt-tmin: {}", e)).exit()
+                }),
+                value(1).map_or(Ok(ADAPT_T_MIN_THRESHOLD), str::parse).unwrap_or_else(|e| {
+                    clap::Error::value_validation_auto(format!("invalid THRESHOLD for --adapt-tmin: {}", e)).exit()
+                })
+            ))
+        } else {
+            None
+        };
+
         let null_model = matches.is_present("null_model");
         let load_landscape = matches.is_present("load_landscape");
 
-        let landscapes: [usize; 2] = values_t!(matches.values_of("landscapes"), usize).unwrap().try_into().unwrap();
+        let landscapes: [usize; 2] = values_t!(matches.values_of("landscapes"), usize).unwrap_or_else(|e| e.exit()).try_into().unwrap();
 
-        Self {
-            pop_size: values_t!(matches.values_of("population_size"), usize).unwrap(),
-            mutation_rate_per_locus: value_t!(matches.value_of("mutation_rate_per_locus"), f64).unwrap(),
+        let parameters = Self {
+            pop_size: values_t!(matches.values_of("population_size"), usize).unwrap_or_else(|e| e.exit()),
+            mutation_rate_per_locus: value_t!(matches.value_of("mutation_rate_per_locus"), f64).unwrap_or_else(|e| e.exit()),
             model,
-            replicates: value_t!(matches.value_of("replicates"), usize).unwrap(),
+            replicates: value_t!(matches.value_of("replicates"), usize).unwrap_or_else(|e| e.exit()),
             resources,
             landscapes,
             null_model,
             load_landscape,
-            folder_name: "".to_string()
+            folder_name: "".to_string(),
+            compare_env,
+            stop_on_fixation: matches.is_present("stop_on_fixation"),
+            buffer_size,
+            stable_window,
+            snapshots: false,
+            track_fixation: matches.is_present("track_fixation"),
+            progress_interval: if matches.is_present("progress_interval") { Some(value_t!(matches.value_of("progress_interval"), usize).unwrap_or_else(|e| e.exit())) } else { None },
+            progress_file: matches.value_of("progress_file").map(|path| path.to_string()),
+            save_landscape_every: if matches.is_present("save_landscape_every") { Some(value_t!(matches.value_of("save_landscape_every"), usize).unwrap_or_else(|e| e.exit())) } else { None },
+            t_min: if matches.is_present("t_min") { Some(value_t!(matches.value_of("t_min"), usize).unwrap_or_else(|e| e.exit())) } else { None },
+            adapt_t_min,
+            carrying_capacity: if matches.is_present("carrying_capacity") { Some(value_t!(matches.value_of("carrying_capacity"), usize).unwrap_or_else(|e| e.exit())) } else { None },
+            growth_rate: if matches.is_present("growth_rate") { value_t!(matches.value_of("growth_rate"), f64).unwrap_or_else(|e| e.exit()) } else { 0. },
+            landscape_noise: if matches.is_present("landscape_noise") { value_t!(matches.value_of("landscape_noise"), f64).unwrap_or_else(|e| e.exit()) } else { 0. },
+            shift_rate: if matches.is_present("shift_rate") { value_t!(matches.value_of("shift_rate"), f64).unwrap_or_else(|e| e.exit()) } else { 0. },
+            target_landscape: if matches.is_present("target_landscape") { Some(value_t!(matches.value_of("target_landscape"), usize).unwrap_or_else(|e| e.exit())) } else { None },
+            convergence,
+            threads: if matches.is_present("threads") { value_t!(matches.value_of("threads"), usize).unwrap_or_else(|e| e.exit()) } else { 0 },
+            seed: if matches.is_present("seed") { Some(value_t!(matches.value_of("seed"), u64).unwrap_or_else(|e| e.exit())) } else { None }
+        };
+        let violations = parameters.validate().err().unwrap_or_default();
+        parameters.exit_if_invalid(violations)
+    }
+
+    /// Returns a random number generator seeded with the seed argument, or from the system's
+    /// entropy if it is not given
+    fn seeded_rng(matches: &ArgMatches) -> StdRng {
+        match matches.value_of("seed") {
+            Some(_) => StdRng::seed_from_u64(value_t!(matches.value_of("seed"), u64).unwrap_or_else(|e| e.exit())),
+            None    => StdRng::from_entropy()
         }
     }
 
@@ -92,44 +263,43 @@ impl<const S: usize> Parameters<S> {
               // General arguments
 
               .arg(Arg::with_name("landscapes").long("landscapes").short("l").takes_value(true).help("Number of landscapes to analize").required(true))
+              .arg(Arg::with_name("seed").long("seed").takes_value(true).help("Seed of the random number generator of the model noise"))
 
               // Models
               .arg(Arg::with_name("HoC").long("hoc").help("House of Cards model").takes_value(true).value_names(&["cb_diag", "cb_offdiag"]))
               .arg(Arg::with_name("additive").long("add").help("Additive model").takes_value(true).value_names(&["mu", "ca_diag", "ca_offdiag"]))
               .arg(Arg::with_name("RMF").long("rmf").help("Rough Mount Fuji model").takes_value(true).value_names(&["mu", "ca_diag", "ca_offdiag", "cb_diag", "cb_offdiag"]))
-              .group(ArgGroup::with_name("model").args(&["HoC", "additive", "RMF"]).required(true))
+              .arg(Arg::with_name("MF").long("mf").help("Mount Fuji model with House of Cards noise, with the optimum at the all-derived genotype").takes_value(true).value_names(&["a", "noise_sd"]))
+              .arg(Arg::with_name("network").long("network").help("Epistasis along the interaction network in an edge-list file").takes_value(true).value_names(&["adjacency_file", "noise_sd"]))
+              .arg(Arg::with_name("empirical").long("empirical").help("Phenotypes read from a landscape file in CBOR or JSON format").takes_value(true).value_name("PATH"))
+              .group(ArgGroup::with_name("model").args(&["HoC", "additive", "RMF", "MF", "network", "empirical"]).required(true))
+              .arg(Arg::with_name("model_noise").long("model-noise").takes_value(true).value_name("SD").help("Standard deviation of the noise added to the parameters of the HoC, additive and RMF models (default: 0)"))
 
               .get_matches();
 
-        let model = if matches.is_present("HoC") {
-            let model_params = values_t!(matches.values_of("HoC"), f64).unwrap();
-            FitnessModel::<S>::new_hoc(model_params)
-        } else if matches.is_present("additive") {
-            let model_params = values_t!(matches.values_of("additive"), f64).unwrap();
-            FitnessModel::<S>::new_additive(model_params)
-        } else if matches.is_present("RMF") {
-            let model_params = values_t!(matches.values_of("RMF"), f64).unwrap();
-            FitnessModel::<S>::new_rmf(model_params)
-        } else {
-            panic!("No model found!")
-        };
+        let model = FitnessModel::<S>::from_clap_matches(&matches, &mut Self::seeded_rng(&matches)).unwrap_or_else(|e| e.exit());
 
         let mut resources = Vector::<S>::new();
         for i in 0..S {
             resources[i] = 1.;
         }
 
-        Self {
+        let parameters = Self {
             pop_size: vec![0],
             mutation_rate_per_locus: 0.,
             model,
             replicates: 0,
             resources,
-            landscapes: [value_t!(matches.value_of("landscapes"), usize).unwrap(), 0],
+            landscapes: [value_t!(matches.value_of("landscapes"), usize).unwrap_or_else(|e| e.exit()), 0],
             null_model: false,
             load_landscape: false,
-            folder_name: "".to_string()
-        }
+            folder_name: "".to_string(),
+            seed: if matches.is_present("seed") { Some(value_t!(matches.value_of("seed"), u64).unwrap_or_else(|e| e.exit())) } else { None },
+            ..Self::default()
+        };
+        // Only the landscape parameters are used
+        let violations = parameters.model_violations();
+        parameters.exit_if_invalid(violations)
     }
 
     pub fn from_command_line_convergence() -> Self {
@@ -145,32 +315,26 @@ impl<const S: usize> Parameters<S> {
               .arg(Arg::with_name("resources").help("Amount of each resource").short("r").long("resources").takes_value(true).value_names(&resource_names[..]).required(true))
 
               .arg(Arg::with_name("landscape").long("landscape").short("l").takes_value(true).help("Index of the landscape to analize").required(true))
-              .arg(Arg::with_name("folder").long("folder").short("f").takes_value(true).help("Name of the folder where to store the results").required(true))
+              .arg(Arg::with_name("folder").long("folder").short("f").takes_value(true).help("Name of the folder where to store the results (default: named after the parameters)"))
+              .arg(Arg::with_name("snapshots").long("snapshots").help("Writes the population in each generation to the standard output as line-delimited JSON"))
+              .arg(Arg::with_name("save_landscape_every").long("save-landscape-every").takes_value(true).value_name("N").help("Writes the full fitness landscape with the statistics every N generations"))
 
               // Models
               .arg(Arg::with_name("HoC").long("hoc").help("House of Cards model").takes_value(true).value_names(&["cb_diag", "cb_offdiag"]))
               .arg(Arg::with_name("additive").long("add").help("Additive model").takes_value(true).value_names(&["mu", "ca_diag", "ca_offdiag"]))
               .arg(Arg::with_name("RMF").long("rmf").help("Rough Mount Fuji model").takes_value(true).value_names(&["mu", "ca_diag", "ca_offdiag", "cb_diag", "cb_offdiag"]))
-              .group(ArgGroup::with_name("model").args(&["HoC", "additive", "RMF"]).required(true))
+              .arg(Arg::with_name("MF").long("mf").help("Mount Fuji model with House of Cards noise, with the optimum at the all-derived genotype").takes_value(true).value_names(&["a", "noise_sd"]))
+              .arg(Arg::with_name("network").long("network").help("Epistasis along the interaction network in an edge-list file").takes_value(true).value_names(&["adjacency_file", "noise_sd"]))
+              .arg(Arg::with_name("empirical").long("empirical").help("Phenotypes read from a landscape file in CBOR or JSON format").takes_value(true).value_name("PATH"))
+              .group(ArgGroup::with_name("model").args(&["HoC", "additive", "RMF", "MF", "network", "empirical"]).required(true))
 
               .arg(Arg::with_name("null_model").long("null").help("Flags the usage of the null model"))
 
               .get_matches();
 
-        let model = if matches.is_present("HoC") {
-            let model_params = values_t!(matches.values_of("HoC"), f64).unwrap();
-            FitnessModel::<S>::new_hoc(model_params)
-        } else if matches.is_present("additive") {
-            let model_params = values_t!(matches.values_of("additive"), f64).unwrap();
-            FitnessModel::<S>::new_additive(model_params)
-        } else if matches.is_present("RMF") {
-            let model_params = values_t!(matches.values_of("RMF"), f64).unwrap();
-            FitnessModel::<S>::new_rmf(model_params)
-        } else {
-            panic!("No model found!")
-        };
+        let model = FitnessModel::<S>::from_clap_matches(&matches, &mut Self::seeded_rng(&matches)).unwrap_or_else(|e| e.exit());
 
-        let resources_v = values_t!(matches.values_of("resources"), f64).unwrap();
+        let resources_v = values_t!(matches.values_of("resources"), f64).unwrap_or_else(|e| e.exit());
         let mut resources = Vector::<S>::new();
         for i in 0..S {
             resources[i] = resources_v[i];
@@ -178,23 +342,281 @@ impl<const S: usize> Parameters<S> {
 
         let null_model = matches.is_present("null_model");
 
-        let mut folder_name = value_t!(matches.value_of("folder"), String).unwrap();
+        let mut folder_name = matches.value_of("folder").unwrap_or("").to_string();
         if folder_name.len() > 0 && !folder_name.ends_with("/") {
             folder_name.push('/');
             println!("Warning: / appended to folder name ({})", folder_name);
         }
 
-        Self {
-            pop_size: values_t!(matches.values_of("population_size"), usize).unwrap(),
-            mutation_rate_per_locus: value_t!(matches.value_of("mutation_rate_per_locus"), f64).unwrap(),
+        let mut parameters = Self {
+            pop_size: values_t!(matches.values_of("population_size"), usize).unwrap_or_else(|e| e.exit()),
+            mutation_rate_per_locus: value_t!(matches.value_of("mutation_rate_per_locus"), f64).unwrap_or_else(|e| e.exit()),
             model,
             replicates: 0,
             resources,
-            landscapes: [value_t!(matches.value_of("landscape"), usize).unwrap(), 0],
+            landscapes: [value_t!(matches.value_of("landscape"), usize).unwrap_or_else(|e| e.exit()), 0],
             null_model,
             load_landscape: true,
-            folder_name
+            folder_name,
+            snapshots: matches.is_present("snapshots"),
+            save_landscape_every: if matches.is_present("save_landscape_every") { Some(value_t!(matches.value_of("save_landscape_every"), usize).unwrap_or_else(|e| e.exit())) } else { None },
+            ..Self::default()
+        };
+        if parameters.folder_name.is_empty() {
+            parameters.folder_name = parameters.default_folder_name();
         }
+        let mut violations = parameters.model_violations();
+        violations.extend(parameters.population_violations());
+        parameters.exit_if_invalid(violations)
     }
 
+    /// Checks the parameters, returning the list of all the problems found
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut violations = self.model_violations();
+        violations.extend(self.population_violations());
+        if self.landscapes[0] > self.landscapes[1] {
+            violations.push(format!("the first landscape ({}) is after the last one ({})", self.landscapes[0], self.landscapes[1]));
+        }
+        if self.replicates < 1 {
+            violations.push("the number of replicates must be at least 1".to_string());
+        }
+        if self.carrying_capacity == Some(0) || !self.growth_rate.is_finite() {
+            violations.push(format!("the carrying capacity must be at least 1 and the growth rate finite, got {:?} and {}", self.carrying_capacity, self.growth_rate));
+        }
+        if let Some((window, threshold)) = self.adapt_t_min {
+            if window < 2 || threshold.is_nan() || threshold <= 0. {
+                violations.push(format!("the entropy window must be at least 2 and the threshold positive, got {} and {}", window, threshold));
+            }
+        }
+        if violations.is_empty() { Ok(()) } else { Err(violations) }
+    }
+
+    /// Checks the mutation rate, the resources and the parameters of the fitness model
+    fn model_violations(&self) -> Vec<String> {
+        let mut violations = Vec::new();
+        if !(0. ..=1.).contains(&self.mutation_rate_per_locus) {
+            violations.push(format!("the mutation rate must be between 0 and 1, got {}", self.mutation_rate_per_locus));
+        }
+        if self.resources.iter().any(|r| r.is_nan() || *r < 0.) {
+            violations.push(format!("the resources must be non-negative, got [{}]", self.resources.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", ")));
+        }
+
+        let mut check_covariance = |name: &str, m: &SquareMatrix<S>| {
+            if m.is_null() { return }
+            if (0..S).any(|i| m.get(i, i).is_nan() || m.get(i, i) <= 0.) {
+                violations.push(format!("the diagonal of {} must be positive", name));
+            } else if m.cholesky().is_err() {
+                violations.push(format!("{} is not positive-definite", name));
+            }
+        };
+        match &self.model {
+            FitnessModel::HoC { cb } => check_covariance("cb", cb),
+            FitnessModel::Additive { ca, .. } => check_covariance("ca", ca),
+            FitnessModel::RoughMountFuji { ca, cb, .. } => {
+                check_covariance("ca", ca);
+                check_covariance("cb", cb);
+            },
+            FitnessModel::NetworkEpistasis { noise_sd, .. } | FitnessModel::MountFuji { noise_sd, .. } => {
+                if noise_sd.is_nan() || *noise_sd < 0. {
+                    violations.push(format!("the noise standard deviation must be non-negative, got {}", noise_sd));
+                }
+            },
+            FitnessModel::Empirical { path } => {
+                if !std::path::Path::new(path).is_file() {
+                    violations.push(format!("the landscape file {} does not exist", path));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Checks the population sizes
+    fn population_violations(&self) -> Vec<String> {
+        if self.pop_size.iter().any(|&n| n < 1) {
+            vec!["the population sizes must be at least 1".to_string()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Prints the violations and exits if there are any, returning the parameters otherwise
+    fn exit_if_invalid(self, violations: Vec<String>) -> Self {
+        if violations.is_empty() {
+            return self
+        }
+        eprintln!("error: invalid parameters");
+        for violation in violations {
+            eprintln!("  - {}", violation);
+        }
+        std::process::exit(1)
+    }
+
+    /// Returns a folder name identifying the simulation by its model, mutation rate, resources and
+    /// type of model, e.g., to store the results without naming the folder by hand
+    pub fn default_folder_name(&self) -> String {
+        format!(
+            "data/{}_m{:e}_r[{}]_{}/",
+            self.model.get_name(), self.mutation_rate_per_locus,
+            self.resources.iter().map(|r| format!("{:.3}", r)).collect::<Vec<_>>().join(","),
+            if self.null_model { "null" } else { "full" }
+        )
+    }
+
+    /// Reads an interaction network from a text file with one edge `i j` between loci i and j per
+    /// line, and returns the loci interacting with each locus. Lines starting with `#` are ignored.
+    pub fn from_adjacency_file(path: &str) -> Result<Vec<Vec<usize>>, Box<dyn Error>> {
+        let mut adjacency: Vec<Vec<usize>> = Vec::new();
+        for (n, line) in std::fs::read_to_string(path)?.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+
+            let edge: Vec<&str> = line.split(|c: char| c.is_whitespace() || c == ',').filter(|v| !v.is_empty()).collect();
+            let (i, j) = match edge[..] {
+                [i, j] => (i.parse::<usize>()?, j.parse::<usize>()?),
+                _      => return Err(format!("line {}: expected an edge 'i j', found '{}'", n+1, line).into())
+            };
+            if adjacency.len() <= i.max(j) {
+                adjacency.resize(i.max(j) + 1, Vec::new());
+            }
+            if i != j && !adjacency[i].contains(&j) {
+                adjacency[i].push(j);
+                adjacency[j].push(i);
+            }
+        }
+        for neighbors in adjacency.iter_mut() {
+            neighbors.sort_unstable();
+        }
+        Ok(adjacency)
+    }
+
+    /// Returns the parameters as YAML, one `key: value` line per field. The values are written in
+    /// JSON, which is also valid YAML.
+    pub fn to_yaml(&self) -> String {
+        let compare_env = self.compare_env.as_ref().map(|[r1, r2]| [r1.to_vec(), r2.to_vec()]);
+        let fields = [
+            ("pop_size",                json!(self.pop_size)),
+            ("mutation_rate_per_locus", json!(self.mutation_rate_per_locus)),
+            ("model",                   json!(self.model.get_name())),
+            ("model_parameters",        serde_json::from_str(&self.model.to_json()).unwrap()),
+            ("replicates",              json!(self.replicates)),
+            ("resources",               json!(self.resources.to_vec())),
+            ("landscapes",              json!(self.landscapes)),
+            ("null_model",              json!(self.null_model)),
+            ("load_landscape",          json!(self.load_landscape)),
+            ("folder_name",             json!(self.folder_name)),
+            ("compare_env",             json!(compare_env)),
+            ("target_landscape",        json!(self.target_landscape)),
+            ("landscape_noise",         json!(self.landscape_noise)),
+            ("shift_rate",              json!(self.shift_rate)),
+            ("stop_on_fixation",        json!(self.stop_on_fixation)),
+            ("buffer_size",             json!(self.buffer_size)),
+            ("stable_window",           json!(self.stable_window)),
+            ("snapshots",               json!(self.snapshots)),
+            ("track_fixation",          json!(self.track_fixation)),
+            ("progress_interval",       json!(self.progress_interval)),
+            ("progress_file",           json!(self.progress_file)),
+            ("save_landscape_every",    json!(self.save_landscape_every)),
+            ("t_min",                   json!(self.t_min)),
+            ("adapt_t_min",             json!(self.adapt_t_min)),
+            ("carrying_capacity",       json!(self.carrying_capacity)),
+            ("growth_rate",             json!(self.growth_rate)),
+            ("convergence",             json!(self.convergence)),
+            ("threads",                 json!(self.threads)),
+            ("seed",                    json!(self.seed))
+        ];
+        fields.iter().map(|(key, value)| format!("{}: {}\n", key, value)).collect()
+    }
+
+    /// Reads the parameters written by `to_yaml`. Lines may start with `#`, as in the header of
+    /// the output files, and unknown keys are ignored.
+    pub fn from_yaml(yaml: &str) -> Result<Self, String> {
+        let mut fields = HashMap::new();
+        for line in yaml.lines() {
+            let line = line.trim_start_matches('#').trim();
+            if let Some((key, value)) = line.split_once(": ") {
+                let value: Value = serde_json::from_str(value).map_err(|e| format!("invalid value for {}: {}", key, e))?;
+                fields.insert(key.to_string(), value);
+            }
+        }
+        fn field<T: DeserializeOwned>(fields: &HashMap<String, Value>, key: &str) -> Result<T, String> {
+            let value = fields.get(key).ok_or(format!("missing field {}", key))?;
+            serde_json::from_value(value.clone()).map_err(|e| format!("invalid value for {}: {}", key, e))
+        }
+
+        let model_parameters: Value = field(&fields, "model_parameters")?;
+        let compare_env: Option<[Vec<f64>; 2]> = field(&fields, "compare_env")?;
+        Ok(Self {
+            pop_size: field(&fields, "pop_size")?,
+            mutation_rate_per_locus: field(&fields, "mutation_rate_per_locus")?,
+            model: model_parameters.to_string().parse().map_err(|e: ParseError| e.to_string())?,
+            replicates: field(&fields, "replicates")?,
+            resources: Vector::from_vec(&field(&fields, "resources")?),
+            landscapes: field(&fields, "landscapes")?,
+            null_model: field(&fields, "null_model")?,
+            load_landscape: field(&fields, "load_landscape")?,
+            folder_name: field(&fields, "folder_name")?,
+            compare_env: compare_env.map(|[r1, r2]| [Vector::from_vec(&r1), Vector::from_vec(&r2)]),
+            target_landscape: field(&fields, "target_landscape")?,
+            landscape_noise: field(&fields, "landscape_noise")?,
+            // An infinite rate is written as null in JSON
+            shift_rate: field::<Option<f64>>(&fields, "shift_rate")?.unwrap_or(f64::INFINITY),
+            stop_on_fixation: field(&fields, "stop_on_fixation")?,
+            buffer_size: field(&fields, "buffer_size")?,
+            stable_window: field(&fields, "stable_window")?,
+            snapshots: field(&fields, "snapshots")?,
+            track_fixation: field(&fields, "track_fixation")?,
+            progress_interval: field(&fields, "progress_interval")?,
+            progress_file: field(&fields, "progress_file")?,
+            save_landscape_every: field(&fields, "save_landscape_every")?,
+            t_min: field(&fields, "t_min")?,
+            adapt_t_min: field(&fields, "adapt_t_min")?,
+            carrying_capacity: field(&fields, "carrying_capacity")?,
+            growth_rate: field(&fields, "growth_rate")?,
+            convergence: field(&fields, "convergence")?,
+            threads: field(&fields, "threads")?,
+            seed: field(&fields, "seed")?
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let valid = || Parameters::<2> {
+            pop_size: vec![100],
+            mutation_rate_per_locus: 0.01,
+            model: FitnessModel::new_rmf(vec![0.1, 0.5, 0.1, 0.3, 0.05]),
+            replicates: 1,
+            landscapes: [0, 2],
+            ..Parameters::default()
+        };
+        assert_eq!(valid().validate(), Ok(()));
+
+        let invalid = Parameters::<2> { mutation_rate_per_locus: 2., ..valid() };
+        assert_eq!(invalid.validate(), Err(vec!["the mutation rate must be between 0 and 1, got 2".to_string()]));
+
+        let invalid = Parameters::<2> {
+            pop_size: vec![100, 0],
+            resources: Vector::from([1., -1.]),
+            landscapes: [3, 2],
+            replicates: 0,
+            model: FitnessModel::HoC { cb: SquareMatrix::from([[1., 2.], [2., 1.]]) },
+            ..valid()
+        };
+        assert_eq!(invalid.validate().unwrap_err(), vec![
+            "the resources must be non-negative, got [1, -1]",
+            "cb is not positive-definite",
+            "the population sizes must be at least 1",
+            "the first landscape (3) is after the last one (2)",
+            "the number of replicates must be at least 1"
+        ]);
+
+        let folder = valid().default_folder_name();
+        assert!(folder.starts_with("data/RMF_S2_") && folder.ends_with("_r[1.000,1.000]_full/"));
+        assert_ne!(folder, Parameters::<2> { mutation_rate_per_locus: 0.02, ..valid() }.default_folder_name());
+    }
 }
@@ -6,15 +6,71 @@ use std::{
     process::Command
 };
 
-use super::genotype::Genotype;
+use super::{
+    genotype::Genotype,
+    math::combinatorics::binomial
+};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Color {
     RGB(i32, i32, i32),
     Hex(String)
 }
 
 impl Color {
+    /// Creates a color from its hue in degrees and its saturation and value in [0, 1]
+    pub fn from_hsv(h: f64, s: f64, v: f64) -> Color {
+        let c = v * s;
+        Self::from_hue_chroma(h, c, v - c)
+    }
+
+    /// Creates a color from its hue in degrees and its saturation and lightness in [0, 1]
+    pub fn from_hsl(h: f64, s: f64, l: f64) -> Color {
+        let c = (1. - (2. * l - 1.).abs()) * s;
+        Self::from_hue_chroma(h, c, l - c / 2.)
+    }
+
+    /// Common step of the HSV and HSL conversions to RGB, with chroma c and lightness offset m
+    fn from_hue_chroma(h: f64, c: f64, m: f64) -> Color {
+        let h = h.rem_euclid(360.) / 60.;
+        let x = c * (1. - (h % 2. - 1.).abs());
+        let (r, g, b) = match h as usize {
+            0 => (c, x, 0.),
+            1 => (x, c, 0.),
+            2 => (0., c, x),
+            3 => (0., x, c),
+            4 => (x, 0., c),
+            _ => (c, 0., x)
+        };
+        let channel = |v: f64| ((v + m) * 255.).round() as i32;
+        Color::RGB(channel(r), channel(g), channel(b))
+    }
+
+    /// Returns the hue in degrees and the saturation and value in [0, 1]
+    fn to_hsv(&self) -> (f64, f64, f64) {
+        let (r, g, b) = self.clamp().as_tuple();
+        let (r, g, b) = (r as f64 / 255., g as f64 / 255., b as f64 / 255.);
+        let max = r.max(g).max(b);
+        let c = max - r.min(g).min(b);
+        let h = if c == 0. {
+            0.
+        } else if max == r {
+            60. * ((g - b) / c).rem_euclid(6.)
+        } else if max == g {
+            60. * ((b - r) / c + 2.)
+        } else {
+            60. * ((r - g) / c + 4.)
+        };
+        let s = if max == 0. { 0. } else { c / max };
+        (h, s, max)
+    }
+
+    /// Returns the color with each RGB channel clipped to [0, 255]
+    pub fn clamp(&self) -> Color {
+        let (r, g, b) = self.as_tuple();
+        Color::RGB(r.clamp(0, 255), g.clamp(0, 255), b.clamp(0, 255))
+    }
+
     fn to_rgb(&self) -> Self {
         match self {
             Color::RGB(..) => (*self).clone(),
@@ -52,7 +108,15 @@ impl Color {
 
     fn gradient(c1: &Color, c2: &Color, pos: f64) -> Color {
         let dif = (*c2).clone() - (*c1).clone();
-        (*c1).clone() + dif * pos
+        ((*c1).clone() + dif * pos).clamp()
+    }
+
+    /// Interpolates between two colors in HSV space, taking the shortest path around the hue circle
+    pub fn gradient_hsv(c1: &Color, c2: &Color, pos: f64) -> Color {
+        let (h1, s1, v1) = c1.to_hsv();
+        let (h2, s2, v2) = c2.to_hsv();
+        let dh = (h2 - h1 + 180.).rem_euclid(360.) - 180.;
+        Color::from_hsv(h1 + dh * pos, s1 + (s2 - s1) * pos, v1 + (v2 - v1) * pos)
     }
 }
 
@@ -236,29 +300,23 @@ impl<'a, const L: usize> FitnessLandscapePlot<'a, L> {
     }
 
     pub fn plot(&self, filename: &str) -> Result<(), Box<dyn Error>> {
-        // brew install librsvg
-        // https://superuser.com/questions/134679/command-line-application-for-converting-svg-to-png-on-mac-os-x
+        let mut graph = svg_header(self.size, self.background_color);
+        graph.push_str(&self.draw());
+        graph.push_str("</svg>");
+        save_svg(filename, &graph, self.render)
+    }
 
+    /// Returns the SVG elements of the plot, without the document header and footer
+    fn draw(&self) -> String {
         let ((l, r), (b, t)) = self.margins;
         let (w, h) = self.size;
 
         let mut graph = String::new();
 
         ///////////////////////////////////////////////////////////////////////////////////////////
-        // header
-        graph.push_str(format!(
-r#"<?xml version="1.0" standalone="no"?>
-<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN"
-"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
-<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" style="background-color:{background_color}">
-"#,
-        width=w, height=h, background_color=self.background_color).as_str());
-        ///////////////////////////////////////////////////////////////////////////////////////////
-
-        ///////////////////////////////////////////////////////////////////////////////////////////
-        // axes
+        // background
         graph.push_str("    <!-- Fill the background -->\n");
-        graph.push_str(r#"    <rect width="100%" height="100%" fill="white" class="background"/>"#);
+        graph.push_str(format!(r#"    <rect width="{}" height="{}" fill="white" class="background"/>"#, w, h).as_str());
         graph.push_str("\n");
         ///////////////////////////////////////////////////////////////////////////////////////////
 
@@ -491,38 +549,7 @@ r##"    <text x="{x:.1}" y="{y:.1}" text-anchor="middle" style="font-size:{font_
         }
         ///////////////////////////////////////////////////////////////////////////////////////////
 
-        ///////////////////////////////////////////////////////////////////////////////////////////
-        // footer
-        graph.push_str("</svg>");
-        ///////////////////////////////////////////////////////////////////////////////////////////
-
-        let file = File::create(filename)?;
-        let mut file = BufWriter::new(file);
-        file.write(graph.as_bytes())?;
-        file.flush()?;
-
-        match self.render {
-            "pdf" => {
-                match Command::new("sh")
-                    .args(["-c", format!("rsvg-convert -f pdf {} -o {}.pdf", filename, &filename[..(filename.len()-4)]).as_str(),])
-                    .spawn() {
-                      Ok(_) => {},
-                      Err(_) => println!("Unable to generate png file (only svg generated). Is 'rsvg-convert' installed?")
-                    }
-            },
-            "png" => {
-                match Command::new("sh") 
-                    .args(["-c", format!("rsvg-convert -f png {} -o {}.png", filename, &filename[..(filename.len()-4)]).as_str(),])
-                    .spawn() {
-                        Ok(_) => {},
-                        Err(_) => println!("Unable to generate png file (only svg generated). Is 'rsvg-convert' installed?")
-                    };
-            },
-            "" => {},
-            _  => { println!("Render format not recognized. Only svg was generated."); }
-        }
-
-        Ok(())
+        graph
     }
 
     #[inline]
@@ -598,7 +625,7 @@ r##"    <text x="{x:.1}" y="{y:.1}" text-anchor="middle" style="font-size:{font_
             if last_g_mutations != self.genotypes[i].sum() as i64 {
                 last_g_mutations += 1;
 
-                let n = binomial_coefficient((last_g_mutations - 1) as usize, L) as f64;
+                let n = binomial(L as u64, (last_g_mutations - 1) as u64) as f64;
 
                 x_positions.push((
                     current_position - (lw / 3.0 + n * fw + (n-1.) * sw) * scale,
@@ -621,9 +648,153 @@ r##"    <text x="{x:.1}" y="{y:.1}" text-anchor="middle" style="font-size:{font_
 
 }
 
-fn factorial(n: usize) -> usize {
-    (1..=n).product()
+/// Several landscape plots arranged side by side in a single SVG document, e.g., to compare the
+/// landscapes before and after an environmental shift
+pub struct MultiPanelPlot<'a, const L: usize> {
+    panels: Vec<FitnessLandscapePlot<'a, L>>,
+    labels: Vec<Option<String>>,
+    pub label_font_size: f64,
+    pub render: &'a str
 }
-fn binomial_coefficient(k: usize, n: usize) -> usize {
-    factorial(n) / (factorial(k) * factorial(n - k))
+
+impl<'a, const L: usize> MultiPanelPlot<'a, L> {
+    /// Creates the plot from its panels, from left to right. If `shared_ylims` is set, all panels
+    /// use the smallest range of fitnesses that contains the ranges of every panel.
+    pub fn new(mut landscapes: Vec<FitnessLandscapePlot<'a, L>>, shared_ylims: bool) -> Self {
+        if shared_ylims {
+            let ylims = landscapes.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |acc, panel| {
+                (acc.0.min(panel.ylims.0), acc.1.max(panel.ylims.1))
+            });
+            for panel in landscapes.iter_mut() {
+                panel.ylims = ylims;
+            }
+        }
+        let n_panels = landscapes.len();
+        Self {
+            panels: landscapes,
+            labels: vec![None; n_panels],
+            label_font_size: 40.,
+            render: ""
+        }
+    }
+
+    /// Writes a label, e.g., "A" or "before the shift", on the top left corner of a panel
+    pub fn add_panel_label(&mut self, panel_idx: usize, label: &str) {
+        self.labels[panel_idx] = Some(label.to_string());
+    }
+
+    pub fn plot(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let width  = self.panels.iter().map(|panel| panel.size.0).sum();
+        let height = self.panels.iter().map(|panel| panel.size.1).fold(0., f64::max);
+        let background_color = self.panels.first().map_or("white", |panel| panel.background_color);
+
+        let mut graph = svg_header((width, height), background_color);
+        let mut x = 0.;
+        for (i, (panel, label)) in self.panels.iter().zip(&self.labels).enumerate() {
+            graph.push_str(format!("\n    <!-- Panel {} -->\n    <g transform=\"translate({:.1} 0)\" class=\"panel\">\n", i, x).as_str());
+            graph.push_str(&panel.draw());
+            if let Some(label) = label {
+                let pos = (panel.margins.0.0, panel.margins.1.1 / 2. + self.label_font_size / 2.);
+                graph.push_str(text(label, pos, self.label_font_size, "start").replace("<text", "<text class=\"panel_label\"").as_str());
+            }
+            graph.push_str("    </g>\n");
+            x += panel.size.0;
+        }
+        graph.push_str("</svg>");
+        save_svg(filename, &graph, self.render)
+    }
+}
+
+/// Returns the beginning of an SVG document of the given size
+fn svg_header(size: (f64, f64), background_color: &str) -> String {
+    format!(
+r#"<?xml version="1.0" standalone="no"?>
+<!DOCTYPE svg PUBLIC "-//W3C//DTD SVG 1.1//EN"
+"http://www.w3.org/Graphics/SVG/1.1/DTD/svg11.dtd">
+<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" style="background-color:{background_color}">
+"#,
+    width=size.0, height=size.1, background_color=background_color)
+}
+
+/// Writes an SVG document and converts it to pdf or png if `render` is set
+fn save_svg(filename: &str, graph: &str, render: &str) -> Result<(), Box<dyn Error>> {
+    // brew install librsvg
+    // https://superuser.com/questions/134679/command-line-application-for-converting-svg-to-png-on-mac-os-x
+    let file = File::create(filename)?;
+    let mut file = BufWriter::new(file);
+    file.write_all(graph.as_bytes())?;
+    file.flush()?;
+
+    match render {
+        "pdf" => {
+            match Command::new("sh")
+                .args(["-c", format!("rsvg-convert -f pdf {} -o {}.pdf", filename, &filename[..(filename.len()-4)]).as_str(),])
+                .spawn() {
+                  Ok(_) => {},
+                  Err(_) => println!("Unable to generate png file (only svg generated). Is 'rsvg-convert' installed?")
+                }
+        },
+        "png" => {
+            match Command::new("sh") 
+                .args(["-c", format!("rsvg-convert -f png {} -o {}.png", filename, &filename[..(filename.len()-4)]).as_str(),])
+                .spawn() {
+                    Ok(_) => {},
+                    Err(_) => println!("Unable to generate png file (only svg generated). Is 'rsvg-convert' installed?")
+                };
+        },
+        "" => {},
+        _  => { println!("Render format not recognized. Only svg was generated."); }
+    }
+
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_panel_plot() {
+        const L: usize = 3;
+        let landscapes: Vec<HashMap<Genotype<L>, f64>> = (0..3).map(|i| {
+            (0..1 << L).map(|index| (Genotype::<L>::from_index(index), 1. + (i * index) as f64 / 10.)).collect()
+        }).collect();
+        let panels = landscapes.iter().map(|landscape| FitnessLandscapePlot::new(landscape, None, None)).collect();
+        let mut plot = MultiPanelPlot::new(panels, true);
+        assert!(plot.panels.iter().all(|panel| panel.ylims == (1., 2.4)));
+        plot.add_panel_label(0, "A");
+        plot.add_panel_label(2, "C");
+
+        let filename = std::env::temp_dir().join(format!("multi_panel_plot_{}.svg", std::process::id()));
+        plot.plot(filename.to_str().unwrap()).unwrap();
+        let svg = std::fs::read_to_string(&filename).unwrap();
+        std::fs::remove_file(&filename).unwrap();
+        assert!(svg.starts_with("<?xml") && svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<svg").count(), 1);
+        assert_eq!(svg.matches("class=\"ylabel\"").count(), 3);
+        assert_eq!(svg.matches("class=\"panel_label\"").count(), 2);
+        assert_eq!(svg.matches("<g ").count(), svg.matches("</g>").count());
+        assert!(svg.contains("width=\"6000\" height=\"1200\""));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(Color::from_hsv(0., 1., 1.), Color::RGB(255, 0, 0));
+        assert_eq!(Color::from_hsv(120., 1., 0.5), Color::RGB(0, 128, 0));
+        assert_eq!(Color::from_hsv(240., 0., 1.), Color::RGB(255, 255, 255));
+        assert_eq!(Color::from_hsl(0., 1., 0.5), Color::RGB(255, 0, 0));
+        assert_eq!(Color::from_hsl(180., 1., 0.25), Color::RGB(0, 128, 128));
+
+        assert_eq!(Color::RGB(300, -10, 128).clamp(), Color::RGB(255, 0, 128));
+        assert_eq!(Color::gradient(&Color::RGB(0, 0, 0), &Color::RGB(255, 255, 255), 1.5), Color::RGB(255, 255, 255));
+
+        // Red to blue goes through magenta rather than through green
+        let red  = Color::RGB(255, 0, 0);
+        let blue = Color::RGB(0, 0, 255);
+        assert_eq!(Color::gradient_hsv(&red, &blue, 0.), red);
+        assert_eq!(Color::gradient_hsv(&red, &blue, 0.5), Color::RGB(255, 0, 255));
+        assert_eq!(Color::gradient_hsv(&red, &blue, 1.), blue);
+    }
 }
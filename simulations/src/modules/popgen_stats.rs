@@ -0,0 +1,142 @@
+use std::fmt;
+
+use serde::{Serialize, Deserialize};
+
+use super::population::{FixedSizePopulation, DiversityAccumulator};
+
+/// Population genetic summary statistics of a whole population, which is taken as the sample
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PopGenStats {
+    pub entropy: f64,
+    pub haplotype_diversity: f64,
+    /// Expected number of differences between two individuals drawn with replacement
+    pub nucleotide_diversity: f64,
+    /// Mean number of differences between two distinct individuals
+    pub mean_pairwise_differences: f64,
+    pub n_segregating_sites: usize,
+    pub watterson_theta: f64,
+    /// NaN if there are no segregating sites or fewer than two individuals
+    pub tajimas_d: f64,
+    /// Number of segregating sites where the derived allele is carried by k individuals at
+    /// index k - 1, for k = 1, ..., n - 1
+    pub sfs: Vec<usize>
+}
+
+impl PopGenStats {
+    /// Computes all statistics with a single scan of the genotypes in the population
+    pub fn compute<const L: usize>(pop: &FixedSizePopulation<L>) -> Self {
+        let mut accumulator = DiversityAccumulator::<L>::new();
+        for (g, &n) in pop.iter() {
+            accumulator.update(g, n);
+        }
+        let (entropy, haplotype_diversity) = accumulator.finalize();
+        let n = accumulator.size();
+
+        let mut sfs = vec![0; n.saturating_sub(1)];
+        for &c in accumulator.derived_counts() {
+            if c > 0 && c < n {
+                sfs[c - 1] += 1;
+            }
+        }
+        let n_segregating_sites: usize = sfs.iter().sum();
+
+        let pairs = (n * n.saturating_sub(1)) as f64;
+        let mean_pairwise_differences = accumulator.derived_counts().iter().map(|&c| {
+            2. * (c * (n - c)) as f64
+        }).sum::<f64>() / pairs;
+
+        let a1: f64 = (1..n).map(|i| 1. / i as f64).sum();
+        let watterson_theta = if n < 2 { f64::NAN } else { n_segregating_sites as f64 / a1 };
+
+        Self {
+            entropy,
+            haplotype_diversity,
+            nucleotide_diversity: accumulator.nucleotide_diversity(),
+            mean_pairwise_differences,
+            n_segregating_sites,
+            watterson_theta,
+            tajimas_d: tajimas_d(n, n_segregating_sites, mean_pairwise_differences),
+            sfs
+        }
+    }
+}
+
+/// Returns Tajima's D for a sample of n individuals with the given number of segregating sites and
+/// mean number of pairwise differences, or NaN if it is undefined
+fn tajimas_d(n: usize, segregating_sites: usize, mean_pairwise_differences: f64) -> f64 {
+    if n < 2 || segregating_sites == 0 {
+        return f64::NAN
+    }
+    let (nf, s) = (n as f64, segregating_sites as f64);
+    let a1: f64 = (1..n).map(|i| 1. / i as f64).sum();
+    let a2: f64 = (1..n).map(|i| 1. / (i * i) as f64).sum();
+    let b1 = (nf + 1.) / (3. * (nf - 1.));
+    let b2 = 2. * (nf * nf + nf + 3.) / (9. * nf * (nf - 1.));
+    let c1 = b1 - 1. / a1;
+    let c2 = b2 - (nf + 2.) / (a1 * nf) + a2 / (a1 * a1);
+    let (e1, e2) = (c1 / a1, c2 / (a1 * a1 + a2));
+    (mean_pairwise_differences - s / a1) / (e1 * s + e2 * s * (s - 1.)).sqrt()
+}
+
+impl fmt::Display for PopGenStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<28}{:.6}", "entropy", self.entropy)?;
+        writeln!(f, "{:<28}{:.6}", "haplotype_diversity", self.haplotype_diversity)?;
+        writeln!(f, "{:<28}{:.6}", "nucleotide_diversity", self.nucleotide_diversity)?;
+        writeln!(f, "{:<28}{:.6}", "mean_pairwise_differences", self.mean_pairwise_differences)?;
+        writeln!(f, "{:<28}{}", "n_segregating_sites", self.n_segregating_sites)?;
+        writeln!(f, "{:<28}{:.6}", "watterson_theta", self.watterson_theta)?;
+        writeln!(f, "{:<28}{:.6}", "tajimas_d", self.tajimas_d)?;
+        // Only the classes with segregating sites
+        let sfs: Vec<String> = self.sfs.iter().enumerate().filter(|&(_, &s)| s > 0).map(|(k, s)| {
+            format!("{}:{}", k + 1, s)
+        }).collect();
+        write!(f, "{:<28}{}", "sfs", sfs.join(" "))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{genotype::Genotype, population::InitialPopulation};
+
+    #[test]
+    fn summary_statistics() {
+        const L: usize = 8;
+        let mut population = FixedSizePopulation::<L>::new(200);
+        population.initialize(InitialPopulation::Binomial(0.3));
+        let stats = PopGenStats::compute(&population);
+        assert!((stats.entropy - population.shannon_entropy()).abs() < 1e-9);
+        assert!((stats.haplotype_diversity - population.haplotype_diversity()).abs() < 1e-12);
+        assert!((stats.nucleotide_diversity - population.nucleotide_diversity()).abs() < 1e-9);
+        assert!((stats.mean_pairwise_differences - stats.nucleotide_diversity * 200. / 199.).abs() < 1e-9);
+        assert_eq!(stats.n_segregating_sites, population.segregating_sites());
+        assert_eq!(stats.sfs.len(), 199);
+
+        let bytes = serde_cbor::to_vec(&stats).unwrap();
+        assert_eq!(serde_cbor::from_slice::<PopGenStats>(&bytes).unwrap(), stats);
+        assert_eq!(stats.to_string().lines().count(), 8);
+
+        // A monomorphic population has no segregating sites
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::from_index(5)));
+        let stats = PopGenStats::compute(&population);
+        assert_eq!((stats.n_segregating_sites, stats.watterson_theta), (0, 0.));
+        assert!(stats.tajimas_d.is_nan());
+
+        // Rare variants give a negative Tajima's D and intermediate frequencies a positive one
+        let mut rare = FixedSizePopulation::<L>::new(20);
+        rare.add_genotype(Genotype::new(), 20 - L);
+        for i in 0..L {
+            rare.add_genotype(Genotype::new().cmutate(i), 1);
+        }
+        let stats = PopGenStats::compute(&rare);
+        assert_eq!(stats.sfs[0], L);
+        assert!(stats.tajimas_d < 0.);
+
+        let mut balanced = FixedSizePopulation::<L>::new(20);
+        balanced.add_genotype(Genotype::new(), 10);
+        balanced.add_genotype(Genotype::from_index(255), 10);
+        assert!(PopGenStats::compute(&balanced).tajimas_d > 0.);
+    }
+}
@@ -1,5 +1,5 @@
-use rand_distr::{Binomial, Bernoulli, Distribution, WeightedAliasIndex};
-use rand::prelude::IteratorRandom;
+use rand_distr::{Binomial, Bernoulli, Distribution, Hypergeometric, WeightedAliasIndex};
+use rand::{Rng, prelude::IteratorRandom};
 
 use std::{
     collections::HashMap,
@@ -10,24 +10,62 @@ use std::{
     error::Error
 };
 
+use serde::{Serialize, Deserialize};
+
 use super::{
     genotype::Genotype,
     resource_based_landscape::ResourceBasedFitnessLandscape,
-    math::linear_algebra::Vector
+    fitness_landscape::FitnessLandscape,
+    math::{
+        combinatorics::binomial,
+        linear_algebra::{SquareMatrix, Vector}
+    }
 };
 
 #[allow(dead_code)]
 pub enum InitialPopulation<const L: usize> {
     NeutralSFS,
     Binomial(f64), // The value is the probability of derived allele
-    SingleGenotype(Genotype<L>)
+    SingleGenotype(Genotype<L>),
+    HammingClass(usize) // All individuals carry the same random genotype with the given number of derived alleles
+}
+
+/// Composition of a population in a self-describing format, meant to be serialized and read by
+/// external tools
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PopulationSnapshot<const L: usize> {
+    /// Number of loci
+    pub l: usize,
+    pub pop_size: usize,
+    /// Number of individuals carrying each genotype
+    #[serde(with = "genotype_counts")]
+    pub population: HashMap<Vec<u8>, usize>
+}
+
+/// Serializes the genotype counts as a list of (genotype, count) pairs sorted by genotype, since
+/// formats like JSON only accept strings as map keys
+mod genotype_counts {
+    use std::collections::HashMap;
+    use serde::{Serialize, Deserialize, Serializer, Deserializer};
+
+    pub fn serialize<Ser: Serializer>(population: &HashMap<Vec<u8>, usize>, serializer: Ser) -> Result<Ser::Ok, Ser::Error> {
+        let mut counts: Vec<(&Vec<u8>, &usize)> = population.iter().collect();
+        counts.sort_unstable();
+        counts.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<Vec<u8>, usize>, D::Error> {
+        Ok(Vec::<(Vec<u8>, usize)>::deserialize(deserializer)?.into_iter().collect())
+    }
 }
 
 #[derive(Clone)]
 pub struct FixedSizePopulation<const L: usize> {
     population: HashMap<Genotype<L>, usize>,
     pop_size:   usize,
-    binomial_coefficients: [f64; L]
+    binomial_coefficients: [f64; L],
+    /// Genotype carried by all individuals when the population was initialized, if any
+    founder:    Option<Genotype<L>>
 }
 
 impl<const L: usize> FixedSizePopulation<L> {
@@ -37,8 +75,9 @@ impl<const L: usize> FixedSizePopulation<L> {
             population: HashMap::new(),
             pop_size:   size,
             binomial_coefficients: (1..=L).map(|n| {
-                binomial(L, n)
-            }).collect::<Vec<f64>>().try_into().unwrap()
+                binomial(L as u64, n as u64) as f64
+            }).collect::<Vec<f64>>().try_into().unwrap(),
+            founder:    None
         }
     }
 
@@ -63,9 +102,16 @@ impl<const L: usize> FixedSizePopulation<L> {
 
     pub fn initialize(&mut self, initial_population: InitialPopulation<L>) {
         self.population.clear();
+        self.founder = None;
         match initial_population {
             InitialPopulation::SingleGenotype(genotype) => {
                 self.population.insert(genotype, self.pop_size);
+                self.founder = Some(genotype);
+            },
+            InitialPopulation::HammingClass(d) => {
+                let genotype = Genotype::<L>::random_in_hamming_class(d, &mut rand::thread_rng());
+                self.population.insert(genotype, self.pop_size);
+                self.founder = Some(genotype);
             },
             InitialPopulation::NeutralSFS => {
                 unimplemented!();
@@ -93,11 +139,16 @@ impl<const L: usize> FixedSizePopulation<L> {
     }
 
     pub fn mutation(&mut self, mutation_rate_per_locus: f64) {
-        let mut rng = rand::thread_rng();
+        self.mutation_with_rng(mutation_rate_per_locus, &mut rand::thread_rng())
+    }
 
+    /// Applies mutation using the given random number generator. Genotypes are processed in a
+    /// fixed order so that the result is reproducible for a seeded generator.
+    pub fn mutation_with_rng<R: Rng>(&mut self, mutation_rate_per_locus: f64, rng: &mut R) {
         // The probability of a genotype acquiring one or more mutations is one minus the probability
         // of not acquiring any mutation.
         let genotype_mutation_probability = 1. - (1. - mutation_rate_per_locus).powi(L as i32);
+        if genotype_mutation_probability == 0. { return }
 
         // Distribution that checks the number of mutations
         let m = mutation_rate_per_locus;
@@ -106,11 +157,14 @@ impl<const L: usize> FixedSizePopulation<L> {
         }).collect();
         let number_of_mutations = WeightedAliasIndex::new(weights).unwrap();
 
+        let mut genotypes: Vec<(Genotype<L>, usize)> = self.population.iter().map(|(&g, &n)| (g, n)).collect();
+        genotypes.sort_unstable();
+
         // For each genotype present in the population
-        for (genotype, n) in self.population.clone().drain() {
+        for (genotype, n) in genotypes {
             // Count how many individuals will carry mutations
             let bin = Binomial::new(n as u64, genotype_mutation_probability).unwrap();
-            let individuals_with_mutations = bin.sample(&mut rng) as usize;
+            let individuals_with_mutations = bin.sample(rng) as usize;
 
             // Remove the mutated individuals from the population
             match self.population.get_mut(&genotype) {
@@ -123,10 +177,10 @@ impl<const L: usize> FixedSizePopulation<L> {
                 let mut new_genotype = genotype.clone();
 
                 // How many mutations?
-                let n_mutations = number_of_mutations.sample(&mut rng) + 1;
+                let n_mutations = number_of_mutations.sample(rng) + 1;
 
                 // which mutations?
-                for i in (0..L).choose_multiple(&mut rng, n_mutations) {
+                for i in (0..L).choose_multiple(rng, n_mutations) {
                     new_genotype.mutate(i);
                 }
                 self.add_individual(new_genotype)
@@ -137,6 +191,75 @@ impl<const L: usize> FixedSizePopulation<L> {
         self.clean_population();
     }
 
+    /// Replaces each individual by the offspring of two random parents whose chromosomes recombine
+    /// at `n_crossovers` breakpoints drawn uniformly in `1..L`
+    pub fn crossover_multipoint(&mut self, n_crossovers: usize, rng: &mut impl Rng) {
+        let parents = self.individuals();
+        if parents.is_empty() || L < 2 { return }
+
+        let mut offspring = Vec::with_capacity(parents.len());
+        for _ in 0..parents.len() {
+            let (p1, p2) = (parents[rng.gen_range(0..parents.len())], parents[rng.gen_range(0..parents.len())]);
+            let mut breakpoints: Vec<usize> = (0..n_crossovers).map(|_| rng.gen_range(1..L)).collect();
+            breakpoints.sort_unstable();
+
+            // Copies the loci of the second parent in every other segment
+            let mut child = p1;
+            let mut from_second = false;
+            let mut segment_start = 0;
+            for &b in breakpoints.iter().chain(std::iter::once(&L)) {
+                if from_second {
+                    Self::copy_tract(&mut child, &p2, segment_start..b);
+                }
+                from_second = !from_second;
+                segment_start = b;
+            }
+            offspring.push(child);
+        }
+        self.set_individuals(offspring);
+    }
+
+    /// With probability `rate`, copies a tract of `tract_length` consecutive loci from a random
+    /// donor into each individual
+    pub fn gene_conversion(&mut self, tract_length: usize, rate: f64, rng: &mut impl Rng) {
+        let donors = self.individuals();
+        if donors.is_empty() { return }
+        let tract_length = tract_length.min(L);
+        let conversion = Bernoulli::new(rate).unwrap();
+
+        let mut individuals = donors.clone();
+        for individual in individuals.iter_mut() {
+            if conversion.sample(rng) {
+                let donor = donors[rng.gen_range(0..donors.len())];
+                let start = rng.gen_range(0..=(L - tract_length));
+                Self::copy_tract(individual, &donor, start..(start + tract_length));
+            }
+        }
+        self.set_individuals(individuals);
+    }
+
+    /// Sets the alleles of `g` in the given loci to the ones of `donor`
+    fn copy_tract(g: &mut Genotype<L>, donor: &Genotype<L>, loci: std::ops::Range<usize>) {
+        for i in loci {
+            if g[i] != donor[i] { g.mutate(i); }
+        }
+    }
+
+    /// Returns the genotype of each individual, sorted so that the order is reproducible
+    fn individuals(&self) -> Vec<Genotype<L>> {
+        let mut genotypes: Vec<(&Genotype<L>, &usize)> = self.population.iter().collect();
+        genotypes.sort_unstable();
+        genotypes.into_iter().flat_map(|(&g, &n)| std::iter::repeat(g).take(n)).collect()
+    }
+
+    /// Replaces the population by the given individuals
+    fn set_individuals(&mut self, individuals: Vec<Genotype<L>>) {
+        self.population.clear();
+        for g in individuals {
+            self.add_individual(g);
+        }
+    }
+
     pub fn to_vector(&self) -> (Vec<Genotype<L>>, Vec<usize>) {
         let mut genotypes = Vec::<Genotype<L>>::with_capacity(self.population.len());
         let mut ns = Vec::<usize>::with_capacity(self.population.len());
@@ -168,6 +291,114 @@ impl<const L: usize> FixedSizePopulation<L> {
         Ok(())
     }
 
+    /// Saves the population as a minimal VCF v4.2 file with one variant per locus. The population
+    /// is stored as a single sample whose genotype is the number of derived alleles at the locus
+    /// (FORMAT field DC), and the derived allele frequency is given in the INFO field AF.
+    pub fn save_vcf(&self, filename: &str, sample_name: &str) -> Result<(), Box<dyn Error>> {
+        let file = File::create(filename)?;
+        let mut file = BufWriter::new(file);
+
+        writeln!(file, "##fileformat=VCFv4.2")?;
+        writeln!(file, "##fileDate={}", vcf_file_date())?;
+        writeln!(file, "##source=dynamic-fitness-landscapes")?;
+        writeln!(file, "##INFO=<ID=AF,Number=A,Type=Float,Description=\"Derived allele frequency\">")?;
+        writeln!(file, "##FORMAT=<ID=DC,Number=1,Type=Integer,Description=\"Number of derived alleles in the population\">")?;
+        writeln!(file, "#CHROM\tPOS\tID\tREF\tALT\tQUAL\tFILTER\tINFO\tFORMAT\t{}", sample_name)?;
+
+        let mut counts = [0_usize; L];
+        for (g, &n) in &self.population {
+            for (c, &allele) in counts.iter_mut().zip(g.iter()) {
+                *c += allele as usize * n;
+            }
+        }
+        for (i, &c) in counts.iter().enumerate() {
+            writeln!(
+                file, "1\t{}\t.\t0\t1\t.\tPASS\tAF={}\tDC\t{}",
+                i + 1, c as f64 / self.pop_size as f64, c
+            )?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
+    pub fn to_snapshot(&self) -> PopulationSnapshot<L> {
+        PopulationSnapshot {
+            l: L,
+            pop_size: self.pop_size,
+            population: self.population.iter().map(|(g, &n)| (g.to_vec(), n)).collect()
+        }
+    }
+
+    pub fn from_snapshot(snapshot: &PopulationSnapshot<L>) -> Self {
+        assert_eq!(snapshot.l, L, "the snapshot has a different number of loci");
+        let mut population = Self::new(snapshot.pop_size);
+        for (g, &n) in &snapshot.population {
+            population.add_genotype(Genotype::from_sequence(&g[..]), n);
+        }
+        population
+    }
+
+    /// Writes a snapshot of the population as a single line of JSON, so that a sequence of
+    /// snapshots can be streamed to another process
+    pub fn write_snapshot_to_writer<W: Write>(&self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer(&mut *writer, &self.to_snapshot())?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Reads a population from a CSV file with one row per individual (`g0,...,gL-1`) or one row
+    /// per genotype with the number of individuals in a last column (`g0,...,gL-1,count`). An
+    /// optional header row starting with `g0` is skipped.
+    pub fn from_csv(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = std::fs::read_to_string(filename)?;
+        let mut counts = HashMap::<Genotype<L>, usize>::new();
+        for (i, row) in contents.lines().enumerate() {
+            let row = row.trim();
+            if row.is_empty() || (i == 0 && row.starts_with("g0")) { continue }
+
+            let fields: Vec<&str> = row.split(',').map(|field| field.trim()).collect();
+            let n = match fields.len() {
+                n if n == L   => 1,
+                n if n == L+1 => fields[L].parse::<usize>().map_err(|_| {
+                    format!("{}, line {}: invalid count '{}'", filename, i+1, fields[L])
+                })?,
+                n => return Err(format!("{}, line {}: expected {} or {} columns, found {}", filename, i+1, L, L+1, n).into())
+            };
+            let mut seq = [0_u8; L];
+            for (allele, field) in seq.iter_mut().zip(&fields) {
+                *allele = match *field {
+                    "0" => 0,
+                    "1" => 1,
+                    _   => return Err(format!("{}, line {}: invalid allele '{}'", filename, i+1, field).into())
+                };
+            }
+            *counts.entry(Genotype::from_sequence(&seq)).or_insert(0) += n;
+        }
+
+        let mut population = Self::new(counts.values().sum());
+        for (g, n) in counts {
+            population.add_genotype(g, n);
+        }
+        Ok(population)
+    }
+
+    /// Writes the population to a CSV file with one row per genotype and the number of individuals
+    /// carrying it in the last column, in the format read by `from_csv`
+    pub fn to_csv(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = BufWriter::new(File::create(filename)?);
+        let header: Vec<String> = (0..L).map(|i| format!("g{}", i)).collect();
+        writeln!(file, "{},count", header.join(","))?;
+
+        let mut genotypes: Vec<(&Genotype<L>, &usize)> = self.population.iter().collect();
+        genotypes.sort_unstable();
+        for (g, n) in genotypes {
+            let alleles: Vec<String> = g.iter().map(|a| a.to_string()).collect();
+            writeln!(file, "{},{}", alleles.join(","), n)?;
+        }
+        file.flush()?;
+        Ok(())
+    }
+
     pub fn from_vec(vec: &Vec<(Vec<u8>, usize)>) -> Self {
         let pop_size = vec.iter().fold(0, |acc, (_, n)| acc + n);
         let mut population = Self::new(pop_size);
@@ -180,10 +411,15 @@ impl<const L: usize> FixedSizePopulation<L> {
 
 
     pub fn wright_fisher<const S: usize>(&mut self, landscape: &ResourceBasedFitnessLandscape<L,S>, resources: &Vector<S>) {
-        let mut rng = rand::thread_rng();
+        self.wright_fisher_with_rng(landscape, resources, &mut rand::thread_rng())
+    }
 
+    /// Applies Wright-Fisher sampling using the given random number generator. Genotypes are
+    /// processed in a fixed order so that the result is reproducible for a seeded generator.
+    pub fn wright_fisher_with_rng<const S: usize, R: Rng>(&mut self, landscape: &ResourceBasedFitnessLandscape<L,S>, resources: &Vector<S>, rng: &mut R) {
         // Get the fitnesses of the genotypes
-        let fitness_landscape = landscape.get_occupied_fitness_landscape(&self, resources);
+        let mut fitness_landscape: Vec<(Genotype<L>, f64)> = landscape.get_occupied_fitness_landscape(self, resources).into_iter().collect();
+        fitness_landscape.sort_unstable_by_key(|&(g, _)| g);
         let n_genotypes = fitness_landscape.len();
 
         let mut genotypes = Vec::<Genotype<L>>::with_capacity(n_genotypes);
@@ -198,7 +434,7 @@ impl<const L: usize> FixedSizePopulation<L> {
         let mut new_population = vec![0_usize; n_genotypes];
         let new_indices = rand::distributions::WeightedIndex::new(&fitnesses).unwrap();
         for _ in 0..self.pop_size {
-            new_population[new_indices.sample(&mut rng)] += 1;
+            new_population[new_indices.sample(rng)] += 1;
         }
 
         self.population.clear();
@@ -209,6 +445,201 @@ impl<const L: usize> FixedSizePopulation<L> {
         }
     }
 
+    /// Applies selection without genetic drift: each genotype gets its expected number of
+    /// offspring under Wright-Fisher sampling, rounded with the largest remainder method so that
+    /// the population size is kept
+    pub fn apply_selection_only<const S: usize>(&mut self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) {
+        let mut fitness_landscape: Vec<(Genotype<L>, f64)> = landscape.get_occupied_fitness_landscape(self, resources).into_iter().collect();
+        fitness_landscape.sort_unstable_by_key(|&(g, _)| g);
+        let total: f64 = fitness_landscape.iter().map(|&(_, f)| f).sum();
+
+        let expected: Vec<(Genotype<L>, f64)> = fitness_landscape.into_iter().map(|(g, f)| {
+            (g, f / total * self.pop_size as f64)
+        }).collect();
+        let mut counts: Vec<usize> = expected.iter().map(|&(_, n)| n.floor() as usize).collect();
+
+        // Hands the individuals left over to the genotypes with the largest remainders
+        let mut order: Vec<usize> = (0..expected.len()).collect();
+        order.sort_by(|&i, &j| (expected[j].1 - counts[j] as f64).total_cmp(&(expected[i].1 - counts[i] as f64)));
+        let missing = self.pop_size.saturating_sub(counts.iter().sum());
+        for &i in order.iter().take(missing) {
+            counts[i] += 1;
+        }
+
+        self.population.clear();
+        for ((g, _), n) in expected.into_iter().zip(counts) {
+            if n > 0 {
+                self.population.insert(g, n);
+            }
+        }
+    }
+
+    /// Returns the genotype frequencies after one generation of selection in an infinite
+    /// population with the given frequencies
+    pub fn infinite_population_step<const S: usize>(
+        frequencies: &HashMap<Genotype<L>, f64>,
+        landscape: &ResourceBasedFitnessLandscape<L, S>,
+        resources: &Vector<S>
+    ) -> HashMap<Genotype<L>, f64> {
+        landscape.get_offspring_fractions(frequencies, resources)
+    }
+
+    /// Keeps `n_survivors` individuals sampled without replacement, e.g., to model the dilution
+    /// of a culture. The population size is not changed, so the next Wright-Fisher step regrows
+    /// the population from the survivors.
+    pub fn bottleneck(&mut self, n_survivors: usize, rng: &mut impl Rng) {
+        let mut genotypes: Vec<(Genotype<L>, usize)> = self.population.iter().map(|(&g, &n)| (g, n)).collect();
+        genotypes.sort_unstable();
+
+        let mut remaining: usize = genotypes.iter().map(|&(_, n)| n).sum();
+        let mut draws = n_survivors.min(remaining);
+        self.population.clear();
+        for (g, n) in genotypes {
+            if draws == 0 { break }
+            let survivors = Hypergeometric::new(remaining as u64, n as u64, draws as u64).unwrap().sample(rng) as usize;
+            if survivors > 0 {
+                self.population.insert(g, survivors);
+            }
+            remaining -= n;
+            draws -= survivors;
+        }
+    }
+
+    /// Changes the population size, e.g., in a population crash or expansion. A smaller population
+    /// keeps individuals sampled without replacement and a larger one is drawn with replacement,
+    /// as in a neutral Wright-Fisher step.
+    pub fn resize(&mut self, new_size: usize, rng: &mut impl Rng) {
+        if new_size == self.pop_size { return }
+        self.population = self.sample(new_size, rng).population;
+        self.pop_size = new_size;
+    }
+
+    /// Returns a sample of `n` individuals of the population, e.g., to mimic sequencing data.
+    /// Individuals are drawn without replacement when `n` is at most the number of individuals in
+    /// the population and with replacement otherwise.
+    pub fn sample(&self, n: usize, rng: &mut impl Rng) -> Self {
+        let mut sample = Self::new(n);
+        if n <= self.population.values().sum() {
+            sample.population = self.population.clone();
+            sample.bottleneck(n, rng);
+            return sample
+        }
+        self.sample_with_replacement(n, rng)
+    }
+
+    /// Returns a population of `new_size` individuals formed by `round(fraction_from_p1 *
+    /// new_size)` individuals sampled from p1 and the rest sampled from p2, e.g., when two
+    /// isolated populations reconnect. Individuals are sampled as in `sample`.
+    pub fn merge(p1: &Self, p2: &Self, fraction_from_p1: f64, new_size: usize, rng: &mut impl Rng) -> Self {
+        let n1 = ((fraction_from_p1 * new_size as f64).round() as usize).min(new_size);
+        let mut merged = Self::new(new_size);
+        for (p, n) in [(p1, n1), (p2, new_size - n1)] {
+            if n == 0 { continue }
+            assert!(!p.population.is_empty(), "can not sample individuals from an empty population");
+            for (&g, &k) in p.sample(n, rng).population.iter() {
+                merged.add_genotype(g, k);
+            }
+        }
+        merged
+    }
+
+    /// Returns the population with all the individuals of both populations, of size
+    /// `p1.size() + p2.size()`
+    pub fn merge_equal(p1: &Self, p2: &Self) -> Self {
+        let mut merged = Self::new(p1.size() + p2.size());
+        for (&g, &n) in p1.population.iter().chain(p2.population.iter()) {
+            merged.add_genotype(g, n);
+        }
+        merged
+    }
+
+    /// Returns a bootstrap sample, drawing as many individuals as the population size with
+    /// replacement
+    pub fn bootstrap_sample(&self, rng: &mut impl Rng) -> Self {
+        self.sample_with_replacement(self.pop_size, rng)
+    }
+
+    /// Returns a population of `n` individuals drawn with replacement
+    fn sample_with_replacement(&self, n: usize, rng: &mut impl Rng) -> Self {
+        let mut genotypes: Vec<(Genotype<L>, usize)> = self.population.iter().map(|(&g, &n)| (g, n)).collect();
+        genotypes.sort_unstable();
+        let weights: Vec<f64> = genotypes.iter().map(|&(_, n)| n as f64).collect();
+        let indices = WeightedAliasIndex::new(weights).unwrap();
+
+        let mut sample = Self::new(n);
+        for _ in 0..n {
+            sample.add_individual(genotypes[indices.sample(rng)].0);
+        }
+        sample
+    }
+
+    /// Returns the 95% bootstrap confidence interval of the haplotype diversity, as the 2.5% and
+    /// 97.5% percentiles over `n_boot` bootstrap samples
+    pub fn bootstrap_diversity_ci(&self, n_boot: usize, rng: &mut impl Rng) -> (f64, f64) {
+        if n_boot == 0 {
+            return (f64::NAN, f64::NAN)
+        }
+        let mut diversities: Vec<f64> = (0..n_boot).map(|_| {
+            self.bootstrap_sample(rng).haplotype_diversity()
+        }).collect();
+        diversities.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let percentile = |p: f64| diversities[((p * (n_boot - 1) as f64).round() as usize).min(n_boot - 1)];
+        (percentile(0.025), percentile(0.975))
+    }
+
+    /// Returns the fitness per individual of each genotype in the population, as given by
+    /// get_occupied_fitness_landscape, together with the number of individuals carrying it
+    fn per_capita_fitness<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> Vec<(f64, usize)> {
+        landscape.get_occupied_fitness_landscape(self, resources).iter().map(|(g, &f)| {
+            let n = self.population[g];
+            (f / n as f64, n)
+        }).collect()
+    }
+
+    /// Returns the mean fitness of the individuals in the population
+    pub fn mean_fitness<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> f64 {
+        let size = self.pop_size as f64;
+        self.per_capita_fitness(landscape, resources).iter().map(|&(f, n)| f * n as f64 / size).sum()
+    }
+
+    /// Returns the variance of the fitness of the individuals in the population
+    pub fn fitness_variance<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> f64 {
+        let size = self.pop_size as f64;
+        let fitness = self.per_capita_fitness(landscape, resources);
+        let mean: f64 = fitness.iter().map(|&(f, n)| f * n as f64 / size).sum();
+        fitness.iter().map(|&(f, n)| (f - mean) * (f - mean) * n as f64 / size).sum()
+    }
+
+    /// Returns the largest fitness among the individuals in the population
+    pub fn max_fitness<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> f64 {
+        self.per_capita_fitness(landscape, resources).iter().map(|&(f, _)| f).fold(f64::NAN, f64::max)
+    }
+
+    /// Returns the smallest fitness among the individuals in the population
+    pub fn min_fitness<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> f64 {
+        self.per_capita_fitness(landscape, resources).iter().map(|&(f, _)| f).fold(f64::NAN, f64::min)
+    }
+
+    /// Returns the genetic load `(W_max - W̄) / W_max` of the population, where W_max is the
+    /// largest fitness of any genotype for the given resources
+    pub fn genetic_load<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> f64 {
+        self.substitutional_load(&landscape.get_full_fitness_landscape(self, resources))
+    }
+
+    /// Returns the load `(W_max - W̄) / W_max` of the population in the fitness landscape, where
+    /// W_max is the largest fitness in the landscape. Returns NaN if the landscape is empty.
+    pub fn substitutional_load(&self, landscape: &FitnessLandscape<L>) -> f64 {
+        let w_max = landscape.max().map_or(f64::NAN, |(_, &f)| f);
+        (w_max - landscape.expected_fitness(self)) / w_max
+    }
+
+    /// Returns true if all individuals in the population carry the same genotype
+    #[inline]
+    pub fn is_fixed(&self) -> bool {
+        self.population.len() == 1
+    }
+
     /// Returns the number of genotypes *currently* present in the population
     #[inline]
     pub fn n_genotypes(&self) -> usize {
@@ -251,6 +682,210 @@ impl<const L: usize> FixedSizePopulation<L> {
         h
     }
 
+    /// Returns the number of individuals with k derived alleles at index k, for k = 0, ..., L
+    pub fn by_hamming_class(&self) -> Vec<usize> {
+        let mut classes = vec![0; L + 1];
+        for (g, &n) in &self.population {
+            classes[g.sum()] += n;
+        }
+        classes
+    }
+
+    /// Returns the fraction of individuals with k derived alleles at index k, for k = 0, ..., L
+    pub fn fraction_by_hamming_class(&self) -> Vec<f64> {
+        let size = self.pop_size as f64;
+        self.by_hamming_class().iter().map(|&n| n as f64 / size).collect()
+    }
+
+    /// Returns the mean and the variance of the number of derived alleles carried by the
+    /// individuals in the population
+    pub fn hamming_class_moments(&self) -> (f64, f64) {
+        let fractions = self.fraction_by_hamming_class();
+        let mean: f64 = fractions.iter().enumerate().map(|(k, f)| k as f64 * f).sum();
+        let var:  f64 = fractions.iter().enumerate().map(|(k, f)| (k as f64 - mean).powi(2) * f).sum();
+        (mean, var)
+    }
+
+    /// Returns the mean number of differences between the individuals and the founding genotype,
+    /// or None if the population was not initialized from a single genotype
+    pub fn mean_genetic_distance_from_founder(&self) -> Option<f64> {
+        let founder = self.founder?;
+        let size = self.pop_size as f64;
+        Some(self.population.iter().map(|(g, &n)| g.n_differences(&founder) as f64 * n as f64 / size).sum())
+    }
+
+    /// Returns the largest number of differences between an individual and the founding genotype,
+    /// or None if the population was not initialized from a single genotype
+    pub fn max_genetic_distance_from_founder(&self) -> Option<usize> {
+        let founder = self.founder?;
+        Some(self.population.keys().map(|g| g.n_differences(&founder)).max().unwrap_or(0))
+    }
+
+    /// Returns the haplotype diversity within each occupied Hamming class (number of derived
+    /// alleles), together with the class frequency, sorted by class
+    fn class_diversities(&self) -> Vec<(usize, f64, f64)> {
+        let class_sizes = self.by_hamming_class();
+        let mut homozygosity = vec![0_f64; L + 1];
+        for (g, &n) in &self.population {
+            homozygosity[g.sum()] += (n as f64 / class_sizes[g.sum()] as f64).powi(2);
+        }
+        let size = self.pop_size as f64;
+        class_sizes.iter().zip(homozygosity.iter()).enumerate().filter(|(_, (&n, _))| n > 0).map(|(c, (&n, h))| {
+            (c, n as f64 / size, 1. - h)
+        }).collect()
+    }
+
+    /// Returns the haplotype diversity within each occupied Hamming class, sorted by class
+    pub fn heterozygosity_by_class(&self) -> Vec<(usize, f64)> {
+        self.class_diversities().into_iter().map(|(c, _, h)| (c, h)).collect()
+    }
+
+    /// Splits the haplotype diversity into the probability that two random individuals of the same
+    /// Hamming class differ and the probability that they belong to different classes. The class
+    /// diversities are weighted by the squared class frequencies so that both components add up to
+    /// the haplotype diversity.
+    pub fn diversity_partition(&self) -> (f64, f64) {
+        let classes = self.class_diversities();
+        let within  = classes.iter().map(|&(_, w, h)| w * w * h).sum();
+        let between = 1. - classes.iter().map(|&(_, w, _)| w * w).sum::<f64>();
+        (within, between)
+    }
+
+    /// Returns the number of genotypes, the Shannon entropy and the haplotype diversity as a line
+    /// for progress messages, e.g., `n_genotypes=42 entropy=3.12 haplotype_div=0.87`
+    pub fn diversity_summary_line(&self) -> String {
+        let size = self.pop_size as f64;
+        let (mut entropy, mut h) = (0_f64, 1_f64);
+        for &n in self.population.values().filter(|&&n| n > 0) {
+            let f = n as f64 / size;
+            entropy -= f * f.ln();
+            h -= f * f;
+        }
+        format!("n_genotypes={} entropy={:.2} haplotype_div={:.2}", self.n_genotypes(), entropy, h)
+    }
+
+    /// Returns whether the Shannon entropy of the population is at least `min_entropy`
+    #[inline]
+    pub fn is_diverse(&self, min_entropy: f64) -> bool {
+        self.shannon_entropy() >= min_entropy
+    }
+
+    /// Returns the frequency of the derived allele (1) at each locus
+    pub fn allele_frequencies(&self) -> [f64; L] {
+        let mut frequencies = [0_f64; L];
+        for (g, &n) in &self.population {
+            for (f, &allele) in frequencies.iter_mut().zip(g.iter()) {
+                *f += (allele as usize * n) as f64;
+            }
+        }
+        let size = self.pop_size as f64;
+        for f in frequencies.iter_mut() {
+            *f /= size;
+        }
+        frequencies
+    }
+
+    /// Returns the phenotype averaged over the individuals in the population
+    pub fn phenotypic_mean<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>) -> Vector<S> {
+        let size = self.pop_size as f64;
+        let mut mean = Vector::new();
+        for (&g, &n) in &self.population {
+            for (m, &p) in mean.iter_mut().zip(landscape.phenotype(g).iter()) {
+                *m += p * n as f64 / size;
+            }
+        }
+        mean
+    }
+
+    /// Returns the covariance matrix of the phenotypes of the individuals in the population,
+    /// computed from the pairwise differences between genotypes so that it is exactly zero for a
+    /// monomorphic population
+    pub fn phenotypic_covariance<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>) -> SquareMatrix<S> {
+        let size = self.pop_size as f64;
+        let phenotypes: Vec<(Vector<S>, f64)> = self.population.iter().map(|(&g, &n)| (landscape.phenotype(g), n as f64)).collect();
+        let mut covariance = [[0_f64; S]; S];
+        for (a, (p1, n1)) in phenotypes.iter().enumerate() {
+            for (p2, n2) in &phenotypes[(a+1)..] {
+                let weight = n1 * n2 / (size * size);
+                for (i, row) in covariance.iter_mut().enumerate() {
+                    for (j, c) in row.iter_mut().enumerate() {
+                        *c += (p1[i] - p2[i]) * (p1[j] - p2[j]) * weight;
+                    }
+                }
+            }
+        }
+        SquareMatrix::from(covariance)
+    }
+
+    /// Orders genotypes by decreasing abundance and, for equal abundance, by genotype
+    fn by_abundance(a: &(Genotype<L>, usize), b: &(Genotype<L>, usize)) -> std::cmp::Ordering {
+        b.1.cmp(&a.1).then(a.0.cmp(&b.0))
+    }
+
+    /// Returns the n most abundant genotypes with their number of individuals, sorted by decreasing
+    /// abundance and, for equal abundance, by genotype. Only the top n genotypes are sorted.
+    pub fn top_n_by_frequency(&self, n: usize) -> Vec<(Genotype<L>, usize)> {
+        let mut genotypes: Vec<(Genotype<L>, usize)> = self.population.iter().map(|(&g, &count)| (g, count)).collect();
+        if n < genotypes.len() {
+            genotypes.select_nth_unstable_by(n, Self::by_abundance);
+            genotypes.truncate(n);
+        }
+        genotypes.sort_unstable_by(Self::by_abundance);
+        genotypes
+    }
+
+    /// Returns the n most abundant genotypes with their number of individuals, same as
+    /// `top_n_by_frequency`
+    pub fn top_n_genotypes(&self, n: usize) -> Vec<(Genotype<L>, usize)> {
+        self.top_n_by_frequency(n)
+    }
+
+    /// Returns the genotype at the given rank, starting from 0, in the order of `top_n_by_frequency`
+    /// with its number of individuals, or None if there are not enough genotypes. Takes linear
+    /// time, without sorting the genotypes.
+    pub fn genotype_at_rank(&self, rank: usize) -> Option<(Genotype<L>, usize)> {
+        let mut genotypes: Vec<(Genotype<L>, usize)> = self.population.iter().map(|(&g, &count)| (g, count)).collect();
+        if rank >= genotypes.len() {
+            return None
+        }
+        let (_, &mut nth, _) = genotypes.select_nth_unstable_by(rank, Self::by_abundance);
+        Some(nth)
+    }
+
+    /// Returns the most abundant genotype with its number of individuals
+    pub fn modal_genotype(&self) -> Option<(Genotype<L>, usize)> {
+        self.genotype_at_rank(0)
+    }
+
+    /// Returns the genotype carrying the most frequent allele at each locus, with ties broken to
+    /// the ancestral allele (0). The consensus genotype need not be present in the population.
+    pub fn consensus_genotype(&self) -> Genotype<L> {
+        let mut counts = [0_usize; L];
+        for (g, &n) in &self.population {
+            for (c, &allele) in counts.iter_mut().zip(g.iter()) {
+                *c += allele as usize * n;
+            }
+        }
+        let consensus: Vec<u8> = counts.iter().map(|&c| (2 * c > self.pop_size) as u8).collect();
+        Genotype::from_sequence(&consensus)
+    }
+
+    /// Returns the fitness of the consensus genotype in the current population
+    pub fn consensus_fitness<const S: usize>(&self, landscape: &ResourceBasedFitnessLandscape<L, S>, resources: &Vector<S>) -> f64 {
+        let fitness_landscape = landscape.get_full_fitness_landscape(self, resources);
+        *fitness_landscape.get(&self.consensus_genotype()).unwrap_or(&f64::NAN)
+    }
+
+    /// Returns the frequency of the derived allele averaged over all loci
+    pub fn mean_derived_allele_frequency(&self) -> f64 {
+        self.allele_frequencies().iter().sum::<f64>() / L as f64
+    }
+
+    /// Returns the number of loci where both alleles are present in the population
+    pub fn segregating_sites(&self) -> usize {
+        self.allele_frequencies().iter().filter(|&&f| f > 0. && f < 1.).count()
+    }
+
     pub fn nucleotide_diversity(&self) -> f64 {
         let mut pi = 0_f64;
         let size = self.pop_size as f64;
@@ -266,6 +901,74 @@ impl<const L: usize> FixedSizePopulation<L> {
         pi
     }
 
+    /// Returns the Shannon entropy, the haplotype diversity and the nucleotide diversity of the
+    /// population computed in a single scan of the genotypes. The nucleotide diversity follows from
+    /// the allele frequencies, `π = Σ_i 2 p_i (1 - p_i)`, which avoids comparing all pairs of
+    /// genotypes.
+    pub fn diversity_stats_in_one_pass(&self) -> (f64, f64, f64) {
+        let mut accumulator = DiversityAccumulator::<L>::new();
+        for (g, &n) in &self.population {
+            accumulator.update(g, n);
+        }
+        let (entropy, haplotype_diversity) = accumulator.finalize();
+        (entropy, haplotype_diversity, accumulator.nucleotide_diversity())
+    }
+}
+
+/// Accumulates the counts of a stream of genotypes to compute the diversity of the population
+/// they form without storing it. Each genotype must be added only once.
+#[derive(Default)]
+pub struct DiversityAccumulator<const L: usize> {
+    size: usize,
+    sum_n_ln_n: f64,
+    sum_n_squared: f64,
+    derived_counts: Vec<usize>
+}
+
+impl<const L: usize> DiversityAccumulator<L> {
+    pub fn new() -> Self {
+        Self { derived_counts: vec![0; L], ..Self::default() }
+    }
+
+    /// Adds `count` individuals carrying the genotype
+    pub fn update(&mut self, genotype: &Genotype<L>, count: usize) {
+        if count == 0 { return }
+        let n = count as f64;
+        self.size += count;
+        self.sum_n_ln_n += n * n.ln();
+        self.sum_n_squared += n * n;
+        for (c, &allele) in self.derived_counts.iter_mut().zip(genotype.iter()) {
+            *c += allele as usize * count;
+        }
+    }
+
+    /// Returns the Shannon entropy and the haplotype diversity of the genotypes added so far, or
+    /// NaN if there are none
+    pub fn finalize(&self) -> (f64, f64) {
+        if self.size == 0 { return (f64::NAN, f64::NAN) }
+        let size = self.size as f64;
+        (size.ln() - self.sum_n_ln_n / size, 1. - self.sum_n_squared / (size * size))
+    }
+
+    /// Returns the number of individuals added so far
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Returns the number of individuals carrying the derived allele at each locus
+    pub fn derived_counts(&self) -> &[usize] {
+        &self.derived_counts
+    }
+
+    /// Returns the nucleotide diversity of the genotypes added so far, or NaN if there are none
+    pub fn nucleotide_diversity(&self) -> f64 {
+        if self.size == 0 { return f64::NAN }
+        let size = self.size as f64;
+        self.derived_counts.iter().map(|&c| {
+            let p = c as f64 / size;
+            2. * p * (1. - p)
+        }).sum()
+    }
 }
 
 impl<const L: usize> Index<Genotype<L>> for FixedSizePopulation<L> {
@@ -304,6 +1007,82 @@ impl<const L: usize> fmt::Display for FixedSizePopulation<L> {
     }
 }
 
+/// Records, for each locus, the first generation at which the allele absent from the initial
+/// population reaches frequency 0.5 (putative fixation) and 1 (full fixation)
+pub struct FixationTracker<const L: usize> {
+    ancestral: [u8; L],
+    half_frequency_time: Vec<Option<usize>>,
+    fixation_time: Vec<Option<usize>>
+}
+
+impl<const L: usize> FixationTracker<L> {
+    /// Creates a tracker taking the most frequent allele at each locus as the ancestral one
+    pub fn new(initial_population: &FixedSizePopulation<L>) -> Self {
+        let mut ancestral = [0; L];
+        for (a, f) in ancestral.iter_mut().zip(initial_population.allele_frequencies()) {
+            *a = (f > 0.5) as u8;
+        }
+        Self {
+            ancestral,
+            half_frequency_time: vec![None; L],
+            fixation_time: vec![None; L]
+        }
+    }
+
+    /// Records the frequency of the new alleles in generation t
+    pub fn update(&mut self, population: &FixedSizePopulation<L>, t: usize) {
+        for (i, f) in population.allele_frequencies().into_iter().enumerate() {
+            let f = if self.ancestral[i] == 0 { f } else { 1. - f };
+            if f >= 0.5 && self.half_frequency_time[i].is_none() {
+                self.half_frequency_time[i] = Some(t);
+            }
+            if f >= 1. && self.fixation_time[i].is_none() {
+                self.fixation_time[i] = Some(t);
+            }
+        }
+    }
+
+    /// Returns the first generation at which the new allele at the locus was fixed, if it was
+    #[inline]
+    pub fn locus_fixation_time(&self, locus: usize) -> Option<usize> {
+        self.fixation_time[locus]
+    }
+
+    /// Returns the first generation at which the new allele at the locus reached frequency 0.5,
+    /// if it did
+    #[inline]
+    pub fn locus_half_frequency_time(&self, locus: usize) -> Option<usize> {
+        self.half_frequency_time[locus]
+    }
+
+    /// Returns the mean fixation time over the loci where a new allele was fixed, or NaN if no
+    /// new allele was fixed
+    pub fn mean_fixation_time(&self) -> f64 {
+        let times: Vec<usize> = self.fixation_time.iter().flatten().copied().collect();
+        times.iter().sum::<usize>() as f64 / times.len() as f64
+    }
+}
+
+/// Returns the current date in the YYYYMMDD format used by the VCF fileDate header
+fn vcf_file_date() -> String {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() / 86_400) as i64;
+
+    // Conversion from days since 1970-01-01 to the civil date
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}{:02}{:02}", year, month, day)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -321,10 +1100,519 @@ mod tests {
         population.mutation(1.);
         assert_eq!(population[Genotype::<L>::new()], 0);
     }
-}
 
-/// Computes the binomial coefficient
-fn binomial(n: usize, k: usize) -> f64 {
-      (n-k..=n).product::<usize>() as f64
-    / (1..=k  ).product::<usize>() as f64
+    #[test]
+    fn allele_frequencies() {
+        let population = FixedSizePopulation::<3>::from_vec(&vec![
+            (vec![1, 0, 0], 3),
+            (vec![1, 1, 0], 1)
+        ]);
+        assert_eq!(population.allele_frequencies(), [1., 0.25, 0.]);
+        assert_eq!(population.segregating_sites(), 1);
+        assert!((population.mean_derived_allele_frequency() - 1.25 / 3.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fixation() {
+        const L: usize = 4;
+        let mut population = FixedSizePopulation::<L>::new(50);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        assert!(population.is_fixed());
+
+        let population = FixedSizePopulation::<L>::from_vec(&vec![
+            (vec![0, 0, 0, 0], 25),
+            (vec![1, 0, 0, 0], 25)
+        ]);
+        assert!(!population.is_fixed());
+    }
+
+    #[test]
+    fn recombination() {
+        const L: usize = 6;
+        let mut rng = rand::thread_rng();
+        let parents = FixedSizePopulation::<L>::from_vec(&vec![
+            (vec![0; L], 50),
+            (vec![1; L], 50)
+        ]);
+        let is_parental = |g: &Genotype<L>| g.sum() == 0 || g.sum() == L;
+
+        // Converting the full chromosome replaces each individual by a copy of the donor
+        let mut population = parents.clone();
+        population.gene_conversion(L, 1., &mut rng);
+        assert_eq!(population.iter().map(|(_, &n)| n).sum::<usize>(), population.size());
+        assert!(population.iter().all(|(g, _)| is_parental(g)));
+
+        let mut population = parents.clone();
+        population.crossover_multipoint(0, &mut rng);
+        assert!(population.iter().all(|(g, _)| is_parental(g)));
+
+        let mut population = parents.clone();
+        population.crossover_multipoint(3, &mut rng);
+        assert_eq!(population.iter().map(|(_, &n)| n).sum::<usize>(), population.size());
+        assert!(population.iter().any(|(g, _)| !is_parental(g)));
+
+        let mut population = parents;
+        population.gene_conversion(2, 1., &mut rng);
+        assert!(population.iter().all(|(g, _)| g.sum() <= 2 || g.sum() >= L - 2));
+    }
+
+    #[test]
+    fn mean_fitness() {
+        const L: usize = 4;
+        let landscape = ResourceBasedFitnessLandscape::<L, 2>::new(
+            super::super::fitness_model::FitnessModel::new_hoc(vec![0.1, 0.05])
+        );
+        let resources = Vector::from([1., 1.]);
+        let g = Genotype::<L>::from_sequence(&[0, 1, 1, 0]);
+
+        let mut population = FixedSizePopulation::<L>::new(50);
+        population.initialize(InitialPopulation::SingleGenotype(g));
+        let f = landscape.get_occupied_fitness_landscape(&population, &resources)[&g] / 50.;
+        assert!((population.mean_fitness(&landscape, &resources) - f).abs() < 1e-12);
+        assert!(population.fitness_variance(&landscape, &resources).abs() < 1e-12);
+        assert_eq!(population.max_fitness(&landscape, &resources), f);
+        assert_eq!(population.min_fitness(&landscape, &resources), f);
+
+        population.mutation(0.2);
+        let (mean, max, min) = (
+            population.mean_fitness(&landscape, &resources),
+            population.max_fitness(&landscape, &resources),
+            population.min_fitness(&landscape, &resources)
+        );
+        assert!(min <= mean && mean <= max);
+    }
+
+    #[test]
+    fn diversity_in_one_pass() {
+        const L: usize = 8;
+        let mut population = FixedSizePopulation::<L>::new(500);
+        for p in [0.02, 0.2, 0.5] {
+            population.initialize(InitialPopulation::Binomial(p));
+            let (entropy, haplotype_diversity, nucleotide_diversity) = population.diversity_stats_in_one_pass();
+            assert!((entropy - population.shannon_entropy()).abs() < 1e-9);
+            assert!((haplotype_diversity - population.haplotype_diversity()).abs() < 1e-12);
+            assert!((nucleotide_diversity - population.nucleotide_diversity()).abs() < 1e-9);
+        }
+
+        let mut accumulator = DiversityAccumulator::<L>::new();
+        assert!(accumulator.finalize().0.is_nan());
+        accumulator.update(&Genotype::from_index(3), 10);
+        assert_eq!(accumulator.finalize(), (0., 0.));
+        accumulator.update(&Genotype::from_index(5), 10);
+        let (entropy, haplotype_diversity) = accumulator.finalize();
+        assert!((entropy - 2_f64.ln()).abs() < 1e-12);
+        assert!((haplotype_diversity - 0.5).abs() < 1e-12);
+        assert!((accumulator.nucleotide_diversity() - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn genetic_load() {
+        const L: usize = 3;
+        let mut landscape = FitnessLandscape::<L>::new(super::super::fitness_landscape::FitnessType::Multiplicative);
+        for index in 0..1 << L {
+            landscape.add_genotype(Genotype::from_index(index), index as f64);
+        }
+        let mut population = FixedSizePopulation::<L>::new(20);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::from_index(7)));
+        assert_eq!(population.substitutional_load(&landscape), 0.);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::from_index(0)));
+        assert_eq!(population.substitutional_load(&landscape), 1.);
+        population.initialize(InitialPopulation::Binomial(0.5));
+        let load = population.substitutional_load(&landscape);
+        assert!(load > 0. && load < 1.);
+
+        // In the null model the ranking of the genotypes does not depend on the population
+        let mut landscape = ResourceBasedFitnessLandscape::<L, 2>::new(
+            super::super::fitness_model::FitnessModel::new_hoc(vec![0.1, 0.05])
+        );
+        landscape.as_null_model();
+        let resources = Vector::from([1., 1.]);
+        let top = *landscape.get_full_fitness_landscape(&population, &resources).max().unwrap().0;
+        population.initialize(InitialPopulation::SingleGenotype(top));
+        assert!(population.genetic_load(&landscape, &resources).abs() < 1e-12);
+    }
+
+    #[test]
+    fn snapshot() {
+        const L: usize = 5;
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        population.mutation(0.1);
+
+        let mut stream = Vec::new();
+        population.write_snapshot_to_writer(&mut stream).unwrap();
+        population.write_snapshot_to_writer(&mut stream).unwrap();
+        let lines: Vec<&str> = std::str::from_utf8(&stream).unwrap().lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("\"l\":5"));
+
+        let snapshot: PopulationSnapshot<L> = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(snapshot, population.to_snapshot());
+        let restored = FixedSizePopulation::<L>::from_snapshot(&snapshot);
+        assert_eq!(restored.size(), population.size());
+        for (g, &n) in population.iter() {
+            assert_eq!(restored[*g], n);
+        }
+    }
+
+    #[test]
+    fn csv() {
+        const L: usize = 5;
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        population.mutation(0.2);
+
+        let filename = std::env::temp_dir().join(format!("population_{}.csv", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        population.to_csv(filename).unwrap();
+        let restored = FixedSizePopulation::<L>::from_csv(filename).unwrap();
+        assert_eq!(restored.size(), population.size());
+        assert_eq!(restored.distribution(), population.distribution());
+
+        // One row per individual, without header
+        std::fs::write(filename, "0,1,0,0,1\n0,1,0,0,1\n1,1,1,1,1\n").unwrap();
+        let population = FixedSizePopulation::<L>::from_csv(filename).unwrap();
+        assert_eq!(population.size(), 3);
+        assert_eq!(population[Genotype::from_sequence(&[0, 1, 0, 0, 1])], 2);
+
+        for invalid in ["0,1,0,1\n", "0,1,2,0,1\n", "0,1,0,0,1,-3\n"] {
+            std::fs::write(filename, invalid).unwrap();
+            assert!(FixedSizePopulation::<L>::from_csv(filename).is_err());
+        }
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn phenotypic_moments() {
+        const L: usize = 3;
+        let model = super::super::fitness_model::FitnessModel::new_hoc(vec![0.1, 0.05]);
+        let landscape = ResourceBasedFitnessLandscape::<L, 2>::new(model);
+        let (g1, g2) = (Genotype::from_sequence(&[1, 0, 1]), Genotype::from_sequence(&[0, 1, 1]));
+
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(g1.to_vec(), 10)]);
+        let mean = population.phenotypic_mean(&landscape);
+        assert!((0..2).all(|i| (mean[i] - landscape.phenotype(g1)[i]).abs() < 1e-12));
+        assert_eq!(population.phenotypic_covariance(&landscape).as_full(), [[0.; 2]; 2]);
+
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(g1.to_vec(), 5), (g2.to_vec(), 5)]);
+        let (p1, p2) = (landscape.phenotype(g1), landscape.phenotype(g2));
+        let mean = population.phenotypic_mean(&landscape);
+        let covariance = population.phenotypic_covariance(&landscape);
+        for i in 0..2 {
+            assert!((mean[i] - (p1[i] + p2[i]) / 2.).abs() < 1e-12);
+            for j in 0..2 {
+                assert!((covariance[(i, j)] - (p1[i] - p2[i]) * (p1[j] - p2[j]) / 4.).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn top_genotypes() {
+        const L: usize = 3;
+        let population = FixedSizePopulation::<L>::from_vec(&vec![
+            (vec![0, 0, 1], 15), (vec![1, 1, 0], 60), (vec![0, 1, 0], 15), (vec![1, 0, 0], 10)
+        ]);
+        assert_eq!(population.modal_genotype(), Some((Genotype::from_sequence(&[1, 1, 0]), 60)));
+        assert_eq!(population.top_n_by_frequency(3), vec![
+            (Genotype::from_sequence(&[1, 1, 0]), 60),
+            (Genotype::from_sequence(&[0, 0, 1]), 15),
+            (Genotype::from_sequence(&[0, 1, 0]), 15)
+        ]);
+        assert_eq!(population.top_n_by_frequency(10).len(), 4);
+        assert_eq!(population.top_n_genotypes(3), population.top_n_by_frequency(3));
+        for (rank, top) in population.top_n_by_frequency(4).into_iter().enumerate() {
+            assert_eq!(population.genotype_at_rank(rank), Some(top));
+        }
+        assert_eq!(population.genotype_at_rank(4), None);
+
+        let population = FixedSizePopulation::<L>::from_vec(&(0..8).map(|i| {
+            (Genotype::<L>::from_index(i).to_vec(), (i * 7) % 8 + 1)
+        }).collect());
+        assert_eq!(population.genotype_at_rank(0), Some((Genotype::from_index(1), 8)));
+        assert_eq!(population.genotype_at_rank(7), Some((Genotype::from_index(0), 1)));
+        assert_eq!(FixedSizePopulation::<L>::new(0).modal_genotype(), None);
+    }
+
+    #[test]
+    fn consensus() {
+        const L: usize = 3;
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![1, 0, 1], 10)]);
+        assert_eq!(population.consensus_genotype(), Genotype::from_sequence(&[1, 0, 1]));
+
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![1, 1, 0], 5), (vec![0, 1, 1], 5)]);
+        assert_eq!(population.consensus_genotype(), Genotype::from_sequence(&[0, 1, 0]));
+
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![1, 1, 0], 4), (vec![0, 1, 1], 3), (vec![1, 0, 1], 3)]);
+        assert_eq!(population.consensus_genotype(), Genotype::from_sequence(&[1, 1, 1]));
+
+        // The fitness of a genotype fixed in the population is 1
+        let model = super::super::fitness_model::FitnessModel::new_hoc(vec![0.1, 0.05]);
+        let landscape = ResourceBasedFitnessLandscape::<L, 2>::new(model);
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![1, 0, 1], 10)]);
+        assert!((population.consensus_fitness(&landscape, &Vector::from([1., 1.])) - 1.).abs() < 1e-12);
+    }
+
+    #[test]
+    fn fixation_tracker() {
+        const L: usize = 3;
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![0, 1, 0], 10)]);
+        let mut tracker = FixationTracker::new(&population);
+        tracker.update(&population, 0);
+
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![1, 1, 0], 5), (vec![0, 0, 0], 5)]);
+        tracker.update(&population, 1);
+        let population = FixedSizePopulation::<L>::from_vec(&vec![(vec![1, 0, 0], 10)]);
+        tracker.update(&population, 2);
+
+        assert_eq!(tracker.locus_half_frequency_time(0), Some(1));
+        assert_eq!(tracker.locus_fixation_time(0), Some(2));
+        assert_eq!(tracker.locus_half_frequency_time(1), Some(1));
+        assert_eq!(tracker.locus_fixation_time(1), Some(2));
+        assert_eq!(tracker.locus_fixation_time(2), None);
+        assert_eq!(tracker.mean_fixation_time(), 2.);
+    }
+
+    #[test]
+    fn vcf() {
+        use noodles_vcf as vcf;
+        use vcf::variant::record_buf::{
+            info::field::{Value as InfoValue, value::Array as InfoArray},
+            samples::sample::Value as SampleValue
+        };
+
+        const L: usize = 6;
+        let mut population = FixedSizePopulation::<L>::new(37);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        population.mutation(0.2);
+
+        let filename = std::env::temp_dir().join(format!("population_test_{}.vcf", std::process::id()));
+        population.save_vcf(filename.to_str().unwrap(), "pop").unwrap();
+        let vcf = std::fs::read_to_string(&filename).unwrap();
+        assert!(vcf.starts_with("##fileformat=VCFv4.2\n"));
+        assert!(vcf.contains("##source=dynamic-fitness-landscapes\n"));
+
+        // Round trip through a VCF parser. AF is parsed as a single precision float, so the exact
+        // frequencies are recovered from the allele counts in DC.
+        let mut reader = vcf::io::reader::Builder::default().build_from_path(&filename).unwrap();
+        let header = reader.read_header().unwrap();
+        let records: Vec<_> = reader.record_bufs(&header).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), L);
+
+        let frequencies = population.allele_frequencies();
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(record.reference_sequence_name(), "1");
+            assert_eq!(record.variant_start().map(usize::from), Some(i + 1));
+            match record.info().get("AF") {
+                Some(Some(InfoValue::Array(InfoArray::Float(af)))) => assert_eq!(af[..], [Some(frequencies[i] as f32)]),
+                af => panic!("unexpected AF {:?}", af)
+            }
+            let dc = record.samples().select("DC").unwrap();
+            match dc.get(0) {
+                Some(Some(&SampleValue::Integer(dc))) => assert_eq!(dc as f64 / population.size() as f64, frequencies[i]),
+                dc => panic!("unexpected DC {:?}", dc)
+            }
+        }
+    }
+
+    #[test]
+    fn bottleneck() {
+        const L: usize = 4;
+        let mut rng = rand::thread_rng();
+        let mut population = FixedSizePopulation::<L>::new(1000);
+        population.initialize(InitialPopulation::Binomial(0.5));
+        let before = population.clone();
+
+        population.bottleneck(100, &mut rng);
+        assert_eq!(population.iter().map(|(_, &n)| n).sum::<usize>(), 100);
+        assert!(population.iter().all(|(g, &n)| n <= before.population[g]));
+        assert_eq!(population.size(), 1000);
+
+        population.bottleneck(1000, &mut rng);
+        assert_eq!(population.iter().map(|(_, &n)| n).sum::<usize>(), 100);
+    }
+
+    #[test]
+    fn sample() {
+        const L: usize = 6;
+        let mut rng = rand::thread_rng();
+        let mut population = FixedSizePopulation::<L>::new(500);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        for n in [10, 500, 2000] {
+            let sample = population.sample(n, &mut rng);
+            assert!(sample.is_fixed());
+            assert_eq!(sample.size(), n);
+            assert_eq!(sample[Genotype::new()], n);
+        }
+        assert!(population.bootstrap_sample(&mut rng).is_fixed());
+        assert_eq!(population.bootstrap_diversity_ci(100, &mut rng), (0., 0.));
+
+        population.initialize(InitialPopulation::Binomial(0.5));
+        let sample = population.sample(100, &mut rng);
+        assert_eq!(sample.iter().map(|(_, &n)| n).sum::<usize>(), 100);
+        assert!(sample.iter().all(|(g, &n)| n <= population.population[g]));
+
+        let (low, high) = population.bootstrap_diversity_ci(200, &mut rng);
+        let h = population.haplotype_diversity();
+        assert!(low <= high && low < h + 0.01 && high > h - 0.01);
+    }
+
+    #[test]
+    fn resize() {
+        const L: usize = 6;
+        let mut rng = rand::thread_rng();
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::Binomial(0.3));
+        let genotypes: Vec<Genotype<L>> = population.iter().map(|(&g, _)| g).collect();
+
+        population.resize(50, &mut rng);
+        assert_eq!(population.size(), 50);
+        assert_eq!(population.values().sum::<usize>(), 50);
+        population.resize(400, &mut rng);
+        assert_eq!(population.size(), 400);
+        assert_eq!(population.values().sum::<usize>(), 400);
+        assert!(population.iter().all(|(g, _)| genotypes.contains(g)));
+
+        population.resize(400, &mut rng);
+        assert_eq!(population.values().sum::<usize>(), 400);
+    }
+
+    #[test]
+    fn merge() {
+        const L: usize = 6;
+        let mut rng = rand::thread_rng();
+        let mut population = FixedSizePopulation::<L>::new(300);
+        population.initialize(InitialPopulation::Binomial(0.3));
+        let empty = FixedSizePopulation::<L>::new(0);
+
+        let merged = FixedSizePopulation::merge(&population, &empty, 1., 300, &mut rng);
+        assert_eq!(merged.distribution(), population.distribution());
+        let merged = FixedSizePopulation::merge_equal(&population, &empty);
+        assert_eq!((merged.size(), merged.distribution()), (300, population.distribution()));
+
+        let mut other = FixedSizePopulation::<L>::new(100);
+        other.initialize(InitialPopulation::SingleGenotype(Genotype::from_sequence(&[1; L])));
+        let merged = FixedSizePopulation::merge(&population, &other, 0.75, 200, &mut rng);
+        assert_eq!(merged.size(), 200);
+        assert_eq!(merged.iter().map(|(_, &n)| n).sum::<usize>(), 200);
+        assert!(merged[Genotype::from_sequence(&[1; L])] >= 50);
+        let merged = FixedSizePopulation::merge_equal(&population, &other);
+        assert_eq!(merged.iter().map(|(_, &n)| n).sum::<usize>(), 400);
+    }
+
+    #[test]
+    fn selection_only() {
+        use super::super::{
+            fitness_model::FitnessModel,
+            multidimensional_rough_mount_fuji::MultidimensionalRoughMountFuji
+        };
+        const L: usize = 2;
+        // With a single resource, the fitness of each genotype is proportional to its uptake rate
+        let (fit, unfit) = (Genotype::<L>::from_sequence(&[1, 0]), Genotype::<L>::new());
+        let phenotypes = [(fit, Vector::from([2_f64.ln()])), (unfit, Vector::from([0.]))].into_iter().collect();
+        let phenotypic_landscape = MultidimensionalRoughMountFuji::<L, 1>::from_phenotype_map(phenotypes, FitnessModel::new_hoc(vec![1., 0.]));
+        let landscape = ResourceBasedFitnessLandscape::from_vec(&phenotypic_landscape.to_vec());
+        let resources = Vector::from([1.]);
+
+        let mut population = FixedSizePopulation::<L>::from_vec(&vec![(fit.to_vec(), 150), (unfit.to_vec(), 150)]);
+        population.apply_selection_only(&landscape, &resources);
+        assert_eq!((population[fit], population[unfit]), (200, 100));
+
+        // The ratio of the frequencies of the two genotypes doubles every generation
+        let frequencies: HashMap<Genotype<L>, f64> = [(fit, 0.2), (unfit, 0.8)].into_iter().collect();
+        let next = FixedSizePopulation::infinite_population_step(&frequencies, &landscape, &resources);
+        assert!((next[&fit] / next[&unfit] - 2. * 0.2 / 0.8).abs() < 1e-12);
+        assert!((next.values().sum::<f64>() - 1.).abs() < 1e-12);
+
+        // Rounding keeps the population size: the expected counts are 2.4 and 0.6
+        let mut population = FixedSizePopulation::<L>::from_vec(&vec![(fit.to_vec(), 2), (unfit.to_vec(), 1)]);
+        population.apply_selection_only(&landscape, &resources);
+        assert_eq!(population.iter().map(|(_, &n)| n).sum::<usize>(), 3);
+        assert_eq!((population[fit], population[unfit]), (2, 1));
+    }
+
+    #[test]
+    fn distance_from_founder() {
+        const L: usize = 10;
+        let founder = Genotype::<L>::from_sequence(&[1, 0, 1, 0, 0, 0, 1, 1, 0, 0]);
+        let mut population = FixedSizePopulation::<L>::new(1000);
+        assert_eq!(population.mean_genetic_distance_from_founder(), None);
+        population.initialize(InitialPopulation::SingleGenotype(founder));
+        assert_eq!(population.mean_genetic_distance_from_founder(), Some(0.));
+        assert_eq!(population.max_genetic_distance_from_founder(), Some(0));
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..50 {
+            population.mutation_with_rng(0.01, &mut rng);
+        }
+        let mean = population.mean_genetic_distance_from_founder().unwrap();
+        assert!(mean > 0.);
+        assert!(population.max_genetic_distance_from_founder().unwrap() as f64 >= mean);
+
+        population.initialize(InitialPopulation::Binomial(0.5));
+        assert_eq!(population.mean_genetic_distance_from_founder(), None);
+        assert_eq!(population.max_genetic_distance_from_founder(), None);
+    }
+
+    #[test]
+    fn hamming_classes() {
+        const L: usize = 10;
+        let mut population = FixedSizePopulation::<L>::new(1000);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        let mut expected = vec![0; L + 1];
+        expected[0] = 1000;
+        assert_eq!(population.by_hamming_class(), expected);
+        assert_eq!(population.hamming_class_moments(), (0., 0.));
+
+        // Binomial(L, 1/2) distribution of the number of derived alleles
+        population.initialize(InitialPopulation::Binomial(0.5));
+        let fractions = population.fraction_by_hamming_class();
+        assert!((fractions.iter().sum::<f64>() - 1.).abs() < 1e-12);
+        assert!((fractions[5] - 252. / 1024.).abs() < 0.06);
+        let (mean, var) = population.hamming_class_moments();
+        assert!((mean - 5.).abs() < 0.3 && (var - 2.5).abs() < 0.5);
+    }
+
+    #[test]
+    fn diversity_summary() {
+        const L: usize = 3;
+        let mut population = FixedSizePopulation::<L>::new(4);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        assert_eq!(population.diversity_summary_line(), "n_genotypes=1 entropy=0.00 haplotype_div=0.00");
+        assert!(population.is_diverse(0.) && !population.is_diverse(0.1));
+
+        let mut population = FixedSizePopulation::<L>::new(4);
+        for seq in [[0, 0, 0], [1, 0, 0], [1, 1, 0], [1, 1, 1]] {
+            population.add_genotype(Genotype::from_sequence(&seq), 1);
+        }
+        assert_eq!(population.diversity_summary_line(), "n_genotypes=4 entropy=1.39 haplotype_div=0.75");
+        assert!(population.is_diverse(1.));
+    }
+
+    #[test]
+    fn diversity_partition() {
+        const L: usize = 3;
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::Binomial(0.5));
+        let (within, between) = population.diversity_partition();
+        assert!((within + between - population.haplotype_diversity()).abs() < 1e-12);
+
+        // One genotype per Hamming class: all diversity is between classes
+        let mut split = FixedSizePopulation::<L>::new(8);
+        for seq in [[0, 0, 0], [1, 0, 0], [1, 1, 0], [1, 1, 1]] {
+            split.add_genotype(Genotype::from_sequence(&seq), 2);
+        }
+        assert_eq!(split.diversity_partition(), (0., 0.75));
+        assert!(split.heterozygosity_by_class().iter().all(|&(_, h)| h == 0.));
+
+        // A single Hamming class: all diversity is within the class
+        let mut single = FixedSizePopulation::<L>::new(6);
+        for seq in [[1, 0, 0], [0, 1, 0], [0, 0, 1]] {
+            single.add_genotype(Genotype::from_sequence(&seq), 2);
+        }
+        let (within, between) = single.diversity_partition();
+        assert!((within - 2. / 3.).abs() < 1e-12 && between == 0.);
+        assert_eq!(single.heterozygosity_by_class().len(), 1);
+        assert_eq!(single.heterozygosity_by_class()[0].0, 1);
+    }
 }
+
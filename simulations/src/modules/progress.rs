@@ -0,0 +1,152 @@
+use std::{
+    error::Error,
+    fs::OpenOptions,
+    io::Write
+};
+
+use super::parameters::Parameters;
+
+/// Receives the progress of a simulation
+pub trait ProgressReporter {
+    /// Called every `interval()` generations of every replicate, with the time elapsed in seconds
+    /// since the start of the replicate
+    fn report(&mut self, landscape: usize, replicate: usize, generation: usize, elapsed: f32);
+
+    /// Returns the number of generations between reports, or 0 if generations are not reported,
+    /// so that the callers only need to access the reporter when there is something to report
+    fn interval(&self) -> usize { 0 }
+
+    /// Called after all replicates of a landscape are done, with the time elapsed in seconds since
+    /// the start of the simulation
+    fn report_landscape(&mut self, _landscapes_done: usize, _landscapes_total: usize, _elapsed: f32) {}
+}
+
+/// Returns the progress message for a generation, or None if it should not be reported
+fn generation_message(interval: usize, landscape: usize, replicate: usize, generation: usize, elapsed: f32) -> Option<String> {
+    if interval == 0 || generation % interval != 0 { return None }
+    Some(format!(
+        "landscape {}, replicate {}, generation {} ({:.1}s)",
+        landscape, replicate, generation, elapsed
+    ))
+}
+
+/// Returns the progress message for a landscape, including the estimated time remaining from the
+/// mean time spent per landscape, which is unknown until a landscape is done
+fn landscape_message(landscapes_done: usize, landscapes_total: usize, elapsed: f32) -> String {
+    let remaining = if landscapes_done > 0 {
+        format!("{:.1}s", elapsed / landscapes_done as f32 * landscapes_total.saturating_sub(landscapes_done) as f32)
+    } else {
+        "unknown".to_string()
+    };
+    format!(
+        "{}/{} landscapes done in {:.1}s, estimated time remaining {}",
+        landscapes_done, landscapes_total, elapsed, remaining
+    )
+}
+
+/// Reports nothing
+pub struct SilentReporter;
+
+impl ProgressReporter for SilentReporter {
+    fn report(&mut self, _landscape: usize, _replicate: usize, _generation: usize, _elapsed: f32) {}
+}
+
+/// Prints the progress to the standard output every `interval` generations
+pub struct StdoutReporter {
+    pub interval: usize
+}
+
+impl ProgressReporter for StdoutReporter {
+    fn report(&mut self, landscape: usize, replicate: usize, generation: usize, elapsed: f32) {
+        if let Some(message) = generation_message(self.interval, landscape, replicate, generation, elapsed) {
+            println!("{}", message);
+        }
+    }
+
+    fn interval(&self) -> usize {
+        self.interval
+    }
+
+    fn report_landscape(&mut self, landscapes_done: usize, landscapes_total: usize, elapsed: f32) {
+        println!("{}", landscape_message(landscapes_done, landscapes_total, elapsed));
+    }
+}
+
+/// Appends the progress to a log file every `interval` generations
+pub struct FileReporter {
+    pub path: String,
+    pub interval: usize
+}
+
+impl FileReporter {
+    fn append(&self, message: &str) -> Result<(), Box<dyn Error>> {
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", message)?;
+        Ok(())
+    }
+}
+
+impl ProgressReporter for FileReporter {
+    fn report(&mut self, landscape: usize, replicate: usize, generation: usize, elapsed: f32) {
+        if let Some(message) = generation_message(self.interval, landscape, replicate, generation, elapsed) {
+            if self.append(&message).is_err() {
+                eprintln!("Could not write to {}", self.path);
+            }
+        }
+    }
+
+    fn interval(&self) -> usize {
+        self.interval
+    }
+
+    fn report_landscape(&mut self, landscapes_done: usize, landscapes_total: usize, elapsed: f32) {
+        if self.append(&landscape_message(landscapes_done, landscapes_total, elapsed)).is_err() {
+            eprintln!("Could not write to {}", self.path);
+        }
+    }
+}
+
+/// Default number of generations between reports when only a progress file is given
+const DEFAULT_INTERVAL: usize = 1000;
+
+/// Returns the reporter selected by the parameters, which reports nothing by default
+pub fn from_parameters<const S: usize>(parameters: &Parameters<S>) -> Box<dyn ProgressReporter + Send> {
+    match (&parameters.progress_file, parameters.progress_interval) {
+        (Some(path), interval) => Box::new(FileReporter {
+            path: path.clone(),
+            interval: interval.unwrap_or(DEFAULT_INTERVAL)
+        }),
+        (None, Some(interval)) => Box::new(StdoutReporter { interval }),
+        (None, None)           => Box::new(SilentReporter)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_reporter() {
+        let path = std::env::temp_dir().join(format!("progress_test_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut reporter = FileReporter { path: path.to_str().unwrap().to_string(), interval: 10 };
+        for t in 0..25 {
+            reporter.report(0, 1, t, 0.5);
+        }
+        reporter.report_landscape(1, 4, 2.);
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[2].contains("generation 20"));
+        assert!(lines[3].contains("1/4") && lines[3].contains("remaining 6.0s"));
+    }
+
+    #[test]
+    fn no_landscapes_done() {
+        assert!(landscape_message(0, 4, 2.).ends_with("remaining unknown"));
+        assert!(landscape_message(4, 4, 2.).ends_with("remaining 0.0s"));
+    }
+}
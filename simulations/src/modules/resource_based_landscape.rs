@@ -1,18 +1,26 @@
 use super::{
     multidimensional_rough_mount_fuji::{MultidimensionalRoughMountFuji, VecRMF},
     population::FixedSizePopulation,
-    genotype::{Genotype, possible_sequences},
-    fitness_landscape::{FitnessLandscape, FitnessType},
+    genotype::{Genotype, possible_sequences, landscape_size},
+    fitness_landscape::{FitnessLandscape, FitnessType, NormalizationMode, landscape_correlation},
     fitness_model::FitnessModel,
-    math::linear_algebra::Vector
+    math::{
+        linear_algebra::{SquareMatrix, Vector},
+        statistics
+    }
 };
 
 use std::{
-    collections::HashMap,
+    cell::{Ref, RefCell},
+    collections::{HashMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
     fs::File,
     error::Error
 };
 
+use rand::Rng;
+use serde::{Serialize, Deserialize, Deserializer};
+
 #[derive(Clone)]
 pub struct ResourceBasedFitnessLandscape<const L: usize, const S: usize> {
     phenotypic_landscape: MultidimensionalRoughMountFuji<L, S>,
@@ -82,6 +90,41 @@ impl<const L: usize, const S: usize> ResourceBasedFitnessLandscape<L, S> {
         fitness_landscape
     }
 
+    /// Returns the fraction of the offspring of each genotype in an infinite population with the
+    /// given genotype frequencies, as given by get_occupied_fitness_landscape for a finite one
+    pub fn get_offspring_fractions(&self, frequencies: &HashMap<Genotype<L>, f64>, resources: &Vector<S>) -> HashMap<Genotype<L>, f64> {
+        let phenotypes: HashMap<Genotype<L>, Vector<S>> = frequencies.keys().map(|&g| {
+            (g, self.phenotypic_landscape.get_multiplicative(g))
+        }).collect();
+
+        let mut uptake = [0_f64; S];
+        for (g, &p) in frequencies {
+            for (j, u) in uptake.iter_mut().enumerate() {
+                *u += p * phenotypes[g][j];
+            }
+        }
+
+        let mut offspring: HashMap<Genotype<L>, f64> = frequencies.iter().map(|(g, &p)| {
+            let fitness: f64 = if self.null_model {
+                phenotypes[g].iter().sum()
+            } else {
+                (0..S).map(|j| phenotypes[g][j] * resources[j] / uptake[j]).sum()
+            };
+            (*g, p * fitness)
+        }).collect();
+        let total: f64 = offspring.values().sum();
+        for f in offspring.values_mut() {
+            *f /= total;
+        }
+        offspring
+    }
+
+    /// Returns the phenotype of a genotype, i.e., its uptake rate of each resource
+    #[inline]
+    pub fn phenotype(&self, g: Genotype<L>) -> Vector<S> {
+        self.phenotypic_landscape.get_multiplicative(g)
+    }
+
     pub fn mean_phenotypic_distance(&self, population: &FixedSizePopulation<L>) -> f64 {
         let mut mean_distance = 0f64;
         for (&g1, &n1) in population.iter() {
@@ -102,10 +145,59 @@ impl<const L: usize, const S: usize> ResourceBasedFitnessLandscape<L, S> {
         mean_distance / ((population.size() * (population.size() - 1)) as f64)
     }
 
+    /// Returns the correlation between the phenotypic and the Hamming distances of all pairs of
+    /// genotypes
+    pub fn phenotypic_neighbor_correlation(&self) -> f64 {
+        self.phenotypic_landscape.phenotypic_neighbor_correlation()
+    }
+
+    /// Returns a hash of the phenotypes of all genotypes, which identifies the landscape, e.g., to
+    /// reuse the statistics that do not depend on the population
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.null_model.hash(&mut hasher);
+        for i in 0..landscape_size::<L>() {
+            for p in self.phenotypic_landscape[Genotype::from_index(i)].iter() {
+                p.to_bits().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+
+    /// Returns a copy of the landscape with independent gaussian noise with standard deviation
+    /// `noise_sd` added to each component of the phenotype of each genotype, e.g., to study the
+    /// effect of measurement errors
+    pub fn perturb_phenotype(&self, noise_sd: f64, rng: &mut impl Rng) -> Self {
+        Self {
+            phenotypic_landscape: self.phenotypic_landscape.with_phenotype_noise(noise_sd, rng),
+            null_model: self.null_model
+        }
+    }
+
     pub fn as_null_model(&mut self) {
         self.null_model = true;
     }
 
+    /// Returns the fitness of every genotype for the given population and resources, normalized
+    /// according to `mode`
+    pub fn get_full_fitness_landscape_normalized(
+        &self,
+        population: &FixedSizePopulation<L>,
+        resources:  &Vector<S>,
+        mode: NormalizationMode
+    ) -> FitnessLandscape<L> {
+        let mut fitness_landscape = self.get_full_fitness_landscape(population, resources);
+        match mode {
+            NormalizationMode::PopulationMean => {},
+            NormalizationMode::Mean     => fitness_landscape.normalize_to_mean(),
+            NormalizationMode::Wildtype => fitness_landscape.normalize_to_wildtype(),
+            NormalizationMode::Max      => fitness_landscape.normalize_to_max()
+        }
+        fitness_landscape
+    }
+
+    /// Returns the fitness of every genotype for the given population and resources, relative to
+    /// the mean fitness of the population
     pub fn get_full_fitness_landscape(&self, population: &FixedSizePopulation<L>, resources: &Vector<S>) -> FitnessLandscape<L> {
         let mut fitness_landscape = FitnessLandscape::<L>::new(FitnessType::Multiplicative);
 
@@ -124,12 +216,7 @@ impl<const L: usize, const S: usize> ResourceBasedFitnessLandscape<L, S> {
 
             fitness_landscape.normalize(mean_fitness);
         } else {
-            let sum_r: Vec<f64> = (0..S).map(|r| {
-                population.iter().map(|(&g, &n)| {
-                    let ar = self.phenotypic_landscape.get_multiplicative(g)[r];
-                    (n as f64) * ar
-                }).sum()
-            }).collect();
+            let sum_r = self.resource_uptake(population);
 
             let mean_fitness = resources.iter().sum::<f64>() / population.size() as f64;
             for &g in &possible_sequences::<L>() {
@@ -146,6 +233,133 @@ impl<const L: usize, const S: usize> ResourceBasedFitnessLandscape<L, S> {
         fitness_landscape
     }
 
+    /// Returns the total uptake rate of each resource by the population. Together with the
+    /// population size and the resources, it determines the full fitness landscape.
+    pub fn resource_uptake(&self, population: &FixedSizePopulation<L>) -> Vector<S> {
+        let mut uptake = Vector::<S>::new();
+        for r in 0..S {
+            uptake[r] = population.iter().map(|(&g, &n)| {
+                let ar = self.phenotypic_landscape.get_multiplicative(g)[r];
+                (n as f64) * ar
+            }).sum();
+        }
+        uptake
+    }
+
+    /// Returns the fitness of every genotype for the given population and resources as a plain
+    /// fitness landscape, e.g., to compute its maxima or epistasis
+    pub fn to_scalar_landscape(&self, population: &FixedSizePopulation<L>, resources: &Vector<S>) -> FitnessLandscape<L> {
+        self.get_full_fitness_landscape(population, resources)
+    }
+
+    /// Returns the fitness of every genotype for the given resources in a population where all
+    /// genotypes have the same frequency, so that no population is needed, e.g., to plot the
+    /// landscape
+    pub fn to_scalar_landscape_all_genotypes(&self, resources: &Vector<S>) -> FitnessLandscape<L> {
+        let mut population = FixedSizePopulation::<L>::new(1 << L);
+        for seq in possible_sequences::<L>() {
+            population.add_genotype(Genotype::from_sequence(&seq), 1);
+        }
+        self.to_scalar_landscape(&population, resources)
+    }
+
+    /// Returns the fitness of every genotype, in the order of the genotype index, when only
+    /// resource i is available
+    fn single_resource_fitness(&self, resource_i: usize) -> Vec<f64> {
+        let mut resources = Vector::<S>::new();
+        resources[resource_i] = 1.;
+        let landscape = self.to_scalar_landscape_all_genotypes(&resources);
+        (0..landscape_size::<L>()).map(|index| *landscape.get(&Genotype::from_index(index)).unwrap()).collect()
+    }
+
+    /// Returns the Spearman correlation between the fitness of the genotypes when only resource i
+    /// is available and when only resource j is available. It is positive if the same genotypes
+    /// grow best on both resources (generalists) and negative if there are trade-offs between them
+    /// (specialists).
+    pub fn fitness_correlation_between_resources(&self, resource_i: usize, resource_j: usize) -> f64 {
+        statistics::spearman(&self.single_resource_fitness(resource_i), &self.single_resource_fitness(resource_j))
+    }
+
+    /// Returns the matrix of the fitness correlations between all pairs of resources
+    pub fn all_resource_correlations(&self) -> SquareMatrix<S> {
+        let fitness: Vec<Vec<f64>> = (0..S).map(|i| self.single_resource_fitness(i)).collect();
+        let mut matrix = [[1_f64; S]; S];
+        for i in 0..S {
+            for j in 0..i {
+                matrix[i][j] = statistics::spearman(&fitness[i], &fitness[j]);
+                matrix[j][i] = matrix[i][j];
+            }
+        }
+        SquareMatrix::from(matrix)
+    }
+
+    /// Returns the contribution of each resource to the fitness of every genotype, in the order of
+    /// the genotype index, so that the fitness is the sum of the contributions
+    fn fitness_components(&self, population: &FixedSizePopulation<L>, resources: &Vector<S>) -> Vec<Vector<S>> {
+        let genotypes = possible_sequences::<L>().into_iter().map(|seq| Genotype::<L>::from_sequence(&seq));
+        if self.null_model {
+            let mean_fitness = population.iter().map(|(&g, &n)| {
+                (n as f64) * self.phenotypic_landscape.get_multiplicative(g).iter().sum::<f64>() / S as f64
+            }).sum::<f64>() / population.size() as f64;
+            genotypes.map(|g| {
+                let mut a = self.phenotypic_landscape.get_multiplicative(g);
+                for ar in a.iter_mut() {
+                    *ar /= S as f64 * mean_fitness;
+                }
+                a
+            }).collect()
+        } else {
+            let sum_r = self.resource_uptake(population);
+            let mean_fitness = resources.iter().sum::<f64>() / population.size() as f64;
+            genotypes.map(|g| {
+                let mut a = self.phenotypic_landscape.get_multiplicative(g);
+                for (r, ar) in a.iter_mut().enumerate() {
+                    *ar *= resources[r] / sum_r[r] / mean_fitness;
+                }
+                a
+            }).collect()
+        }
+    }
+
+    /// Returns the finite-difference derivative of the fitness of every genotype with respect to
+    /// the amount of resource `resource_idx`
+    pub fn fitness_sensitivity(&self, population: &FixedSizePopulation<L>, resources: &Vector<S>, resource_idx: usize, delta: f64) -> HashMap<Genotype<L>, f64> {
+        let mut perturbed = *resources;
+        perturbed[resource_idx] += delta;
+        let f0 = self.get_full_fitness_landscape(population, resources);
+        let f1 = self.get_full_fitness_landscape(population, &perturbed);
+        f0.landscape.iter().map(|(g, &f)| (*g, (f1.landscape[g] - f) / delta)).collect()
+    }
+
+    /// Returns the finite-difference Jacobian of the fitness with respect to the resources, where
+    /// the entry (i, j) is the derivative of the contribution of resource i to the fitness with
+    /// respect to the amount of resource j, averaged over all genotypes
+    pub fn jacobian(&self, population: &FixedSizePopulation<L>, resources: &Vector<S>, delta: f64) -> [[f64; S]; S] {
+        let n = landscape_size::<L>() as f64;
+        let components = self.fitness_components(population, resources);
+        let mut jacobian = [[0_f64; S]; S];
+        for j in 0..S {
+            let mut perturbed = *resources;
+            perturbed[j] += delta;
+            let perturbed_components = self.fitness_components(population, &perturbed);
+            for (c0, c1) in components.iter().zip(perturbed_components.iter()) {
+                for (i, row) in jacobian.iter_mut().enumerate() {
+                    row[j] += (c1[i] - c0[i]) / delta / n;
+                }
+            }
+        }
+        jacobian
+    }
+
+    /// Returns the absolute derivative of the fitness with respect to the resources, averaged over
+    /// all genotypes and resources
+    pub fn mean_fitness_sensitivity(&self, population: &FixedSizePopulation<L>, resources: &Vector<S>, delta: f64) -> f64 {
+        let n = (landscape_size::<L>() * S) as f64;
+        (0..S).map(|j| {
+            self.fitness_sensitivity(population, resources, j, delta).values().map(|d| d.abs()).sum::<f64>()
+        }).sum::<f64>() / n
+    }
+
     pub fn to_vec(&self) -> VecRMF {
         self.phenotypic_landscape.to_vec()
     }
@@ -158,12 +372,346 @@ impl<const L: usize, const S: usize> ResourceBasedFitnessLandscape<L, S> {
     }
 
     pub fn load(filename: &str) -> Self {
-        let loaded = if let Ok(file) = File::open(filename) {
-            serde_cbor::from_reader(file).unwrap()
-        } else {
-            panic!("Could not open {}", filename)
-        };
+        match Self::try_load(filename) {
+            Ok(landscape) => landscape,
+            Err(e)        => panic!("Could not open {}: {}", filename, e)
+        }
+    }
+
+    /// Loads a landscape saved with `save`, returning an error if the file cannot be read
+    pub fn try_load(filename: &str) -> Result<Self, Box<dyn Error>> {
+        let loaded = serde_cbor::from_reader(File::open(filename)?)?;
+        Ok(Self::from_vec(&loaded))
+    }
+}
+
+/// Wraps a ResourceBasedFitnessLandscape and keeps the last full fitness landscape computed, so
+/// that it is only recomputed when the resources or the state of the population it depends on
+/// change. The landscape depends on the population only through its size and its total uptake
+/// of each resource, so that, e.g., calling it several times in the same generation or for a
+/// population that does not change computes it once.
+pub struct FitnessCache<const L: usize, const S: usize> {
+    landscape: ResourceBasedFitnessLandscape<L, S>,
+    cache: RefCell<Option<(CacheKey<S>, FitnessLandscape<L>)>>
+}
+
+/// Population size, total resource uptake of the population and resources
+type CacheKey<const S: usize> = (usize, Vector<S>, Vector<S>);
+
+impl<const L: usize, const S: usize> FitnessCache<L, S> {
+    pub fn new(landscape: ResourceBasedFitnessLandscape<L, S>) -> Self {
+        Self {
+            landscape,
+            cache: RefCell::new(None)
+        }
+    }
+
+    /// Returns the wrapped landscape
+    #[inline]
+    pub fn landscape(&self) -> &ResourceBasedFitnessLandscape<L, S> {
+        &self.landscape
+    }
+
+    /// Returns the full fitness landscape, computing it only if the key of the population and
+    /// resources differs from the cached one. The returned reference must be dropped before the
+    /// next call, which may replace the cached landscape.
+    pub fn get_full_fitness_landscape_cached(&self, pop: &FixedSizePopulation<L>, resources: &Vector<S>) -> Ref<'_, FitnessLandscape<L>> {
+        let key = (pop.size(), self.landscape.resource_uptake(pop), *resources);
+        let is_cached = matches!(&*self.cache.borrow(), Some((cached, _)) if *cached == key);
+        if !is_cached {
+            let fitness_landscape = self.landscape.get_full_fitness_landscape(pop, resources);
+            *self.cache.borrow_mut() = Some((key, fitness_landscape));
+        }
+        Ref::map(self.cache.borrow(), |cache| &cache.as_ref().unwrap().1)
+    }
+}
+
+/// Summary statistics of the fitness landscape experienced by a population under a given resource
+/// vector
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LandscapeInfo<const L: usize, const S: usize> {
+    pub n_maxima: usize,
+    pub n_minima: usize,
+    pub global_maximum: f64,
+    pub global_minimum: f64,
+    pub mean: f64,
+    pub variance: f64,
+    #[serde(deserialize_with = "nan_if_null")]
+    pub gamma: f64,
+    #[serde(deserialize_with = "nan_if_null")]
+    pub autocorrelation_length: f64,
+    /// Sizes of the basins of attraction of the local maxima under greedy adaptive walks, in
+    /// decreasing order
+    pub basin_sizes: Vec<usize>,
+    /// Number of accessible shortest paths from the genotype opposite to the global maximum to
+    /// the global maximum
+    pub n_accessible_paths: usize
+}
+
+/// Reads a float that may have been written as null, which is how serde_json stores NaN
+fn nan_if_null<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::NAN))
+}
+
+impl<const L: usize, const S: usize> LandscapeInfo<L, S> {
+    pub fn compute(landscape: &ResourceBasedFitnessLandscape<L, S>, pop: &FixedSizePopulation<L>, resources: &Vector<S>) -> Self {
+        let fitness_landscape = landscape.get_full_fitness_landscape(pop, resources);
+        let (&top, &global_maximum) = fitness_landscape.max().unwrap();
+        let (_, &global_minimum) = fitness_landscape.min().unwrap();
+        let (mean, variance) = fitness_landscape.mean_var();
+        let antipode = (0..L).fold(top, |g, i| g.cmutate(i));
+
+        Self {
+            n_maxima: fitness_landscape.maxima().len(),
+            n_minima: fitness_landscape.minima().len(),
+            global_maximum,
+            global_minimum,
+            mean,
+            variance,
+            gamma: fitness_landscape.gamma(),
+            autocorrelation_length: fitness_landscape.autocorrelation_length(),
+            basin_sizes: fitness_landscape.basin_sizes(),
+            n_accessible_paths: fitness_landscape.accessible_paths(&antipode, &top)
+        }
+    }
+
+    pub fn save_json(&self, filename: &str) -> Result<(), Box<dyn Error>> {
+        serde_json::to_writer_pretty(File::create(filename)?, self)?;
+        Ok(())
+    }
+
+    pub fn load_json(filename: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(serde_json::from_reader(File::open(filename)?)?)
+    }
+}
+
+/// Linearly interpolates the phenotypes of two landscapes, going from `l1` at `t = 0` to `l2` at
+/// `t = 1`. The fitness model and null model flag are taken from `l1`.
+pub fn interpolate<const L: usize, const S: usize>(
+    l1: &ResourceBasedFitnessLandscape<L, S>,
+    l2: &ResourceBasedFitnessLandscape<L, S>,
+    t: f64
+) -> ResourceBasedFitnessLandscape<L, S> {
+    let p2 = l2.phenotypic_landscape.phenotype_map();
+    let phenotype = l1.phenotypic_landscape.phenotype_map().iter().map(|(&g, &p1)| {
+        let mut p = p1;
+        for r in 0..S {
+            p[r] += t * (p2[&g][r] - p1[r]);
+        }
+        (g, p)
+    }).collect();
+
+    ResourceBasedFitnessLandscape {
+        phenotypic_landscape: MultidimensionalRoughMountFuji::from_phenotype_map(
+            phenotype, l1.phenotypic_landscape.fitness_model()
+        ),
+        null_model: l1.null_model
+    }
+}
+
+/// Returns the Spearman rank correlation between the fitness landscapes experienced by the
+/// population under two different resource vectors
+pub fn resource_landscape_correlation<const L: usize, const S: usize>(
+    landscape: &ResourceBasedFitnessLandscape<L, S>,
+    pop: &FixedSizePopulation<L>,
+    r1: &Vector<S>,
+    r2: &Vector<S>
+) -> f64 {
+    let l1 = landscape.get_full_fitness_landscape(pop, r1);
+    let l2 = landscape.get_full_fitness_landscape(pop, r2);
+    landscape_correlation(&l1, &l2)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::population::InitialPopulation;
+
+    #[test]
+    fn resource_correlation() {
+        const L: usize = 10;
+        const S: usize = 2;
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.]));
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::random()));
+
+        let r1 = Vector::from([1., 0.]);
+        let r2 = Vector::from([0., 1.]);
+
+        let rho = resource_landscape_correlation(&landscape, &population, &r1, &r1);
+        assert!((rho - 1.).abs() < 1e-12);
+
+        let rho = resource_landscape_correlation(&landscape, &population, &r1, &r2);
+        assert!(rho.abs() < 0.2);
+    }
+
+    #[test]
+    fn perturb_phenotype() {
+        const L: usize = 6;
+        const S: usize = 2;
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![1., 0.2]));
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        let resources = Vector::from([1., 1.]);
+        let mut rng = rand::thread_rng();
+
+        let same = landscape.perturb_phenotype(0., &mut rng);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::from_sequence(&seq);
+            assert_eq!(same.phenotype(g), landscape.phenotype(g));
+        }
+
+        let full = landscape.get_full_fitness_landscape(&population, &resources);
+        let slightly = landscape.perturb_phenotype(0.01, &mut rng).get_full_fitness_landscape(&population, &resources);
+        let strongly = landscape.perturb_phenotype(10., &mut rng).get_full_fitness_landscape(&population, &resources);
+        assert!(full.spearman_rho(&slightly) > 0.9);
+        assert!(full.spearman_rho(&strongly) < full.spearman_rho(&slightly));
+    }
+
+    #[test]
+    fn fitness_cache() {
+        const L: usize = 5;
+        const S: usize = 2;
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+        let cache = FitnessCache::new(landscape.clone());
+        let resources = Vector::from([1., 1.]);
+
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        for _ in 0..3 {
+            let cached = cache.get_full_fitness_landscape_cached(&population, &resources);
+            let direct = landscape.get_full_fitness_landscape(&population, &resources);
+            assert_eq!(cached.landscape, direct.landscape);
+            population.mutation(0.1);
+        }
+    }
+
+    #[test]
+    fn fitness_sensitivity() {
+        const L: usize = 4;
+        const S: usize = 2;
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+        let mut population = FixedSizePopulation::<L>::new(50);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        let resources = Vector::from([1., 2.]);
+        let delta = 1e-6;
+
+        // The fitness is the sum of the contributions of the resources
+        let full = landscape.get_full_fitness_landscape(&population, &resources);
+        for (index, components) in landscape.fitness_components(&population, &resources).iter().enumerate() {
+            let f = full.get(&Genotype::from_index(index)).unwrap();
+            assert!((components.iter().sum::<f64>() - f).abs() < 1e-12);
+        }
+
+        // The Jacobian summed over the components gives the mean sensitivity
+        let jacobian = landscape.jacobian(&population, &resources, delta);
+        let column_sums: Vec<f64> = (0..S).map(|j| jacobian.iter().map(|row| row[j]).sum()).collect();
+        for (j, column_sum) in column_sums.iter().enumerate() {
+            let sensitivity = landscape.fitness_sensitivity(&population, &resources, j, delta);
+            assert_eq!(sensitivity.len(), 1 << L);
+            let mean = sensitivity.values().sum::<f64>() / (1 << L) as f64;
+            assert!((column_sum - mean).abs() < 1e-6);
+        }
+
+        // In the null model the fitness does not depend on the resources
+        let mut null = landscape.clone();
+        null.as_null_model();
+        assert!(null.mean_fitness_sensitivity(&population, &resources, delta).abs() < 1e-6);
+        assert!(landscape.mean_fitness_sensitivity(&population, &resources, delta) > 0.);
+    }
+
+    #[test]
+    fn interpolation() {
+        const L: usize = 5;
+        const S: usize = 2;
+        let l1 = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+        let l2 = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+        let resources = Vector::from([1., 1.]);
+
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+        population.mutation(0.1);
+
+        for (t, l) in [(0., &l1), (1., &l2)] {
+            let interpolated = interpolate(&l1, &l2, t);
+            let f1 = interpolated.get_full_fitness_landscape(&population, &resources);
+            let f2 = l.get_full_fitness_landscape(&population, &resources);
+            for (g, f) in f1.landscape.iter() {
+                assert!((f - f2.get(g).unwrap()).abs() < 1e-12);
+            }
+        }
+
+        let interpolated = interpolate(&l1, &l2, 0.5);
+        for seq in possible_sequences::<L>() {
+            let g = Genotype::<L>::from_sequence(&seq);
+            let (p, p1, p2) = (
+                interpolated.phenotypic_landscape.get_multiplicative(g),
+                l1.phenotypic_landscape.get_multiplicative(g),
+                l2.phenotypic_landscape.get_multiplicative(g)
+            );
+            for r in 0..S {
+                assert!(p[r] >= p1[r].min(p2[r]) && p[r] <= p1[r].max(p2[r]));
+            }
+        }
+    }
+
+    #[test]
+    fn landscape_info() {
+        const L: usize = 6;
+        const S: usize = 2;
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+        let mut population = FixedSizePopulation::<L>::new(100);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+        let info = LandscapeInfo::compute(&landscape, &population, &Vector::from([1., 1.]));
+
+        assert_eq!(info.basin_sizes.len(), info.n_maxima);
+        assert_eq!(info.basin_sizes.iter().sum::<usize>(), 1 << L);
+        assert!(info.global_minimum <= info.mean && info.mean <= info.global_maximum);
+        assert!(info.n_accessible_paths <= (1..=L).product());
+
+        let filename = std::env::temp_dir().join(format!("landscape_info_{}.json", std::process::id()));
+        let filename = filename.to_str().unwrap();
+        info.save_json(filename).unwrap();
+        let loaded = LandscapeInfo::<L, S>::load_json(filename).unwrap();
+        std::fs::remove_file(filename).unwrap();
+        assert_eq!((info.n_maxima, info.n_minima, &info.basin_sizes), (loaded.n_maxima, loaded.n_minima, &loaded.basin_sizes));
+        assert_eq!(info.n_accessible_paths, loaded.n_accessible_paths);
+        assert!((info.variance - loaded.variance).abs() < 1e-12 && (info.gamma - loaded.gamma).abs() < 1e-12);
+    }
+    #[test]
+    fn scalar_landscape() {
+        const L: usize = 4;
+        const S: usize = 2;
+        let resources = Vector::from([1., 0.5]);
+        for null_model in [false, true] {
+            let mut landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.05]));
+            if null_model { landscape.as_null_model(); }
+            let all_genotypes = landscape.to_scalar_landscape_all_genotypes(&resources);
+            assert_eq!(all_genotypes.landscape.len(), 1 << L);
+
+            let mut population = FixedSizePopulation::<L>::new(3 << L);
+            for seq in possible_sequences::<L>() {
+                population.add_genotype(Genotype::from_sequence(&seq), 3);
+            }
+            let scalar = landscape.to_scalar_landscape(&population, &resources);
+            for (g, _) in population.iter() {
+                assert!((scalar.get(g).unwrap() - all_genotypes.get(g).unwrap()).abs() < 1e-12);
+            }
+        }
+    }
+    #[test]
+    fn resource_correlations() {
+        const L: usize = 6;
+        const S: usize = 2;
+        let tradeoff = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, -0.0999]));
+        assert!(tradeoff.fitness_correlation_between_resources(0, 1) < -0.9);
+        let matrix = tradeoff.all_resource_correlations();
+        assert_eq!(matrix.get(0, 0), 1.);
+        assert!((matrix.get(0, 1) - tradeoff.fitness_correlation_between_resources(0, 1)).abs() < 1e-12);
+        assert_eq!(matrix.get(0, 1), matrix.get(1, 0));
 
-        Self::from_vec(&loaded)
+        let generalist = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_hoc(vec![0.1, 0.0999]));
+        assert!(generalist.fitness_correlation_between_resources(0, 1) > 0.9);
     }
 }
@@ -0,0 +1,73 @@
+use super::{
+    resource_based_landscape::ResourceBasedFitnessLandscape,
+    population::FixedSizePopulation,
+    math::linear_algebra::Vector
+};
+
+use rand::Rng;
+
+/// Simulation of a serial transfer experiment, in which the population grows in batch culture and
+/// is periodically diluted into fresh media
+pub struct SerialTransferSimulation<const L: usize, const S: usize>;
+
+impl<const L: usize, const S: usize> SerialTransferSimulation<L, S> {
+    /// Runs `n_transfers` cycles of growth for `gens_per_transfer` generations of mutation and
+    /// Wright-Fisher sampling with resource supply `supply`, followed by a bottleneck keeping a
+    /// fraction `dilution` of the individuals (at least one). Returns the population at the end of
+    /// the growth phase of each cycle.
+    #[allow(clippy::too_many_arguments)]
+    pub fn run(
+        landscape: &ResourceBasedFitnessLandscape<L, S>,
+        initial_pop: FixedSizePopulation<L>,
+        supply: Vector<S>,
+        dilution: f64,
+        n_transfers: usize,
+        gens_per_transfer: usize,
+        mu: f64,
+        rng: &mut impl Rng
+    ) -> Vec<FixedSizePopulation<L>> {
+        let mut population = initial_pop;
+        let n_survivors = ((dilution * population.size() as f64).round() as usize).max(1);
+
+        let mut snapshots = Vec::with_capacity(n_transfers);
+        for _ in 0..n_transfers {
+            for _ in 0..gens_per_transfer {
+                population.mutation_with_rng(mu, rng);
+                population.wright_fisher_with_rng(landscape, &supply, rng);
+            }
+            snapshots.push(population.clone());
+            population.bottleneck(n_survivors, rng);
+        }
+        snapshots
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        fitness_model::FitnessModel,
+        genotype::Genotype,
+        population::InitialPopulation
+    };
+
+    #[test]
+    fn serial_transfer() {
+        const L: usize = 4;
+        const S: usize = 2;
+        // Every mutation increases the uptake of both resources, so that selection favors the
+        // genotypes with more derived alleles
+        let landscape = ResourceBasedFitnessLandscape::<L, S>::new(FitnessModel::new_additive(vec![1., 0.01, 0.]));
+        let mut population = FixedSizePopulation::<L>::new(1000);
+        population.initialize(InitialPopulation::SingleGenotype(Genotype::new()));
+
+        let mut rng = rand::thread_rng();
+        let snapshots = SerialTransferSimulation::run(&landscape, population, Vector::from([1., 1.]), 0.01, 30, 10, 0.01, &mut rng);
+        assert_eq!(snapshots.len(), 30);
+        assert!(snapshots.iter().all(|p| p.iter().map(|(_, &n)| n).sum::<usize>() == 1000));
+
+        let (mean_mutations, _) = snapshots.last().unwrap().hamming_class_moments();
+        assert!(mean_mutations > 3.);
+    }
+}
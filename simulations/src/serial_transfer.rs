@@ -0,0 +1,69 @@
+//! serial_transfer simulates a serial transfer experiment, in which the population
+//! grows in batch culture and is diluted into fresh media after a fixed number of
+//! generations. Prints one line per transfer with the state of the population at
+//! the end of the growth phase.
+//!
+//! For information on the parameters, run `serial_transfer --help`
+
+pub mod modules;
+use modules::{
+    resource_based_landscape::ResourceBasedFitnessLandscape,
+    population::{FixedSizePopulation, InitialPopulation},
+    serial_transfer::SerialTransferSimulation,
+    genotype::Genotype,
+    math::linear_algebra::Vector
+};
+
+use clap::{Arg, App, value_t, values_t};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    const L: usize = 10;
+    const S: usize = 2;
+
+    let matches = App::new("")
+          .author("André Amado <andre.amado@pm.me>")
+          .arg(Arg::with_name("landscape").help("Landscape file created by create_landscape").required(true))
+          .arg(Arg::with_name("pop_size").help("Population size at the end of the growth phase").long("pop-size").takes_value(true).default_value("10000"))
+          .arg(Arg::with_name("dilution").help("Fraction of the population transferred to fresh media").long("dilution").takes_value(true).default_value("0.01"))
+          .arg(Arg::with_name("transfers").help("Number of transfers").long("transfers").takes_value(true).default_value("100"))
+          .arg(Arg::with_name("generations").help("Generations of growth between transfers").long("generations").takes_value(true).default_value("7"))
+          .arg(Arg::with_name("mutation_rate").help("Mutation rate per locus").long("mutation-rate").takes_value(true).default_value("0.0001"))
+          .arg(Arg::with_name("resources").help("Supply of each resource").long("resources").takes_value(true).number_of_values(S as u64).value_names(&["r1", "r2"]))
+          .arg(Arg::with_name("null_model").help("Uses the null model without competition for resources").long("null-model"))
+          .arg(Arg::with_name("seed").help("Seed of the random number generator").long("seed").takes_value(true))
+          .get_matches();
+
+    let mut landscape = ResourceBasedFitnessLandscape::<L, S>::try_load(matches.value_of("landscape").unwrap())?;
+    if matches.is_present("null_model") { landscape.as_null_model(); }
+
+    let pop_size = value_t!(matches, "pop_size", usize)?;
+    let supply = match matches.values_of("resources") {
+        Some(_) => Vector::from_vec(&values_t!(matches, "resources", f64)?),
+        None    => Vector::from([1.; S])
+    };
+    let seed = if matches.is_present("seed") { value_t!(matches, "seed", u64)? } else { rand::thread_rng().gen() };
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut population = FixedSizePopulation::<L>::new(pop_size);
+    population.initialize(InitialPopulation::SingleGenotype(Genotype::<L>::new()));
+    let snapshots = SerialTransferSimulation::run(
+        &landscape, population, supply,
+        value_t!(matches, "dilution", f64)?,
+        value_t!(matches, "transfers", usize)?,
+        value_t!(matches, "generations", usize)?,
+        value_t!(matches, "mutation_rate", f64)?,
+        &mut rng
+    );
+
+    println!("#seed {}", seed);
+    println!("#transfer\tstrains\tentropy\tmean_mutations\tmodal_genotype");
+    for (transfer, population) in snapshots.iter().enumerate() {
+        let (mean_mutations, _) = population.hamming_class_moments();
+        let modal = population.modal_genotype().map_or(-1, |(g, _)| g.index() as i64);
+        println!("{}\t{}\t{}\t{}\t{}", transfer, population.n_genotypes(), population.shannon_entropy(), mean_mutations, modal);
+    }
+    Ok(())
+}